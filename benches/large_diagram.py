@@ -0,0 +1,81 @@
+"""Benchmark routing and rendering on a diagram with a few hundred boxes.
+
+Run with ``python benches/large_diagram.py`` after installing the package
+(e.g. ``maturin develop --release``).
+"""
+
+import random
+import time
+
+from textdraw import BoundingBox, Box, Pixel, PixelGroup, Point, TextPath, render
+
+ROWS = 15
+COLUMNS = 20
+N_PATHS = 60
+REPEATS = 3
+
+
+def build_boxes(spacing: tuple[int, int] = (10, 6)) -> list[Box]:
+    boxes = []
+    for row in range(ROWS):
+        for column in range(COLUMNS):
+            label = f'{row * COLUMNS + column:03}'
+            boxes.append(Box(label, (column * spacing[0], -row * spacing[1]), padding=(0, 1, 0, 1)))
+    return boxes
+
+
+def solid_barrier(boxes: list[Box]) -> PixelGroup:
+    """Precompute the box cells once so that the timings are dominated by the search."""
+    return PixelGroup(
+        [
+            Pixel('#', (x, y), weight=None)
+            for box in boxes
+            for x in range(box.bbox.left, box.bbox.right + 1)
+            for y in range(box.bbox.bottom, box.bbox.top + 1)
+        ]
+    )
+
+
+def build_paths(boxes: list[Box], router: str = 'grid', barriers: list | None = None) -> list[TextPath]:
+    rng = random.Random(0)
+    bbox = BoundingBox.wrap(boxes)
+    barriers = boxes if barriers is None else barriers
+    paths = []
+    for _ in range(N_PATHS):
+        a, b = rng.sample(boxes, 2)
+        paths.append(
+            TextPath(
+                a.bbox.center_right + Point(1, 0),
+                b.bbox.center_left - Point(1, 0),
+                bend_penalty=5,
+                barriers=barriers,
+                bbox=bbox,
+                router=router,
+            )
+        )
+    return paths
+
+
+def timed(label: str, f):
+    best = float('inf')
+    result = None
+    for _ in range(REPEATS):
+        t0 = time.perf_counter()
+        result = f()
+        best = min(best, time.perf_counter() - t0)
+    print(f'{label:<10} {best * 1000:10.1f} ms')
+    return result
+
+
+if __name__ == '__main__':
+    boxes = build_boxes()
+    print(f'{len(boxes)} boxes, {N_PATHS} paths')
+    timed('wrap', lambda: BoundingBox.wrap(boxes))
+    paths = timed('route', lambda: build_paths(boxes))
+    timed('render', lambda: render([*paths, *boxes]))
+
+    sparse = build_boxes(spacing=(40, 20))
+    barriers = [solid_barrier(sparse)]
+    print(f'{len(sparse)} sparse boxes, {N_PATHS} paths')
+    timed('grid', lambda: build_paths(sparse, router='grid', barriers=barriers))
+    timed('channel', lambda: build_paths(sparse, router='channel', barriers=barriers))
@@ -101,6 +101,6 @@ if __name__ == '__main__':
     bbox = BoundingBox.wrap(objs)
     objs_shifted = duplicate_shifted(
         [*objs, a.barriers, b.barriers, c.barriers],
-        Point(bbox.width + 3, 0),
+        Point(bbox.width + 2, 0),
     )
     print(render([*objs, *objs_shifted]))
@@ -1,6 +1,7 @@
 from textdraw import Box, TextPath, render
 
-if __name__ == '__main__':
+
+def diagram():
     boxes = {
         'A': (0, 0),
         'B': (30, 0),
@@ -30,4 +31,8 @@ if __name__ == '__main__':
         path = TextPath(coords[start], coords[end], style=color, bend_penalty=0, line_style='heavy')
         objs.append(path)
 
-    print(render(list(reversed(objs))))
+    return list(reversed(objs))
+
+
+if __name__ == '__main__':
+    print(render(diagram()))
@@ -1,2 +1,917 @@
+import copy
+import os
+import pickle
+import re
+import runpy
+import shutil
+from pathlib import Path
+
 import pytest
 import textdraw
+from textdraw import Point
+from textdraw.testing import SnapshotMismatch, assert_snapshot, diff_snapshots, snapshot
+
+
+def test_point_subtraction():
+    assert Point(3, 4) - Point(1, 1) == Point(2, 3)
+    assert Point(3, 4) - (1, 1) == Point(2, 3)
+    assert (3, 4) - Point(1, 1) == Point(2, 3)
+    assert Point(1, 1) - Point(3, 4) == Point(-2, -3)
+
+
+def test_point_arithmetic():
+    assert Point(1, 2) + (1, 1) == Point(2, 3)
+    assert 2 * Point(1, -2) == Point(2, -4)
+    assert Point(1, -2) * 3 == Point(3, -6)
+    assert -Point(1, -2) == Point(-1, 2)
+
+
+def test_point_equality_and_hashing():
+    assert Point(1, 2) == (1, 2)
+    assert Point(1, 2) != Point(2, 1)
+    assert len({Point(1, 2), Point(1, 2), (1, 2)}) == 1
+    x, y = Point(1, 2)
+    assert (x, y) == (1, 2)
+
+
+def test_point_distances():
+    assert Point(0, 0).manhattan_distance((3, -4)) == 7
+    assert Point(0, 0).chebyshev_distance((3, -4)) == 4
+
+
+def test_point_neighbors_and_directions():
+    assert Point(0, 0).neighbors() == [(0, 1), (1, 0), (0, -1), (-1, 0)]
+    assert len(Point(0, 0).neighbors(diagonal=True)) == 8
+    assert Point(0, 0).direction_to((0, -3)) == 'down'
+    assert Point(0, 0).direction_to((1, 1)) is None
+    assert Point(0, 0).step('left', 2) == Point(-2, 0)
+    with pytest.raises(ValueError):
+        Point(0, 0).step('sideways')
+
+
+def test_point_line_to():
+    assert Point(0, 0).line_to((4, 2)) == [(0, 0), (1, 1), (2, 1), (3, 2), (4, 2)]
+    assert Point(2, 2).line_to((2, 2)) == [(2, 2)]
+    assert Point(0, 0).line_to((0, -2)) == [(0, 0), (0, -1), (0, -2)]
+
+
+def test_json_round_trip():
+    a = textdraw.Box('hello', (0, 0), title='A', border_style='bold red')
+    b = textdraw.Box('world', (20, 5), line_style='double')
+    path = textdraw.TextPath(a.bbox.center_right, b.bbox.center_left, style='#ff00ff', label='go')
+    objs = [a, b, path, textdraw.Pixel('x', (3, 3)), textdraw.text('hi', (8, 8))]
+    json = textdraw.to_json(objs)
+    loaded = textdraw.from_json(json)
+    assert [type(obj).__name__ for obj in loaded] == ['Box', 'Box', 'TextPath', 'Pixel', 'PixelGroup']
+    assert textdraw.render(loaded) == textdraw.render(objs)
+    assert textdraw.to_json(loaded) == json
+
+
+def test_json_values():
+    assert textdraw.from_json(textdraw.to_json(Point(1, -2))) == Point(1, -2)
+    bbox = textdraw.from_json(textdraw.to_json(textdraw.BoundingBox(3, 4, 1, 2)))
+    assert (bbox.top, bbox.right, bbox.bottom, bbox.left) == (3, 4, 1, 2)
+    style = textdraw.from_json(textdraw.to_json(textdraw.Style('bold #ff00ff on blue')))
+    assert style('x') == textdraw.Style('bold #ff00ff on blue')('x')
+
+
+def test_json_coordinates():
+    textdraw.set_coordinates('screen')
+    try:
+        assert textdraw.to_json(Point(1, 2)) == '{"coordinates":"screen","Point":[1,-2]}'
+        assert textdraw.from_json(textdraw.to_json(Point(1, 2))) == Point(1, 2)
+        box = textdraw.Box('a', (0, 0))
+        objs = [box, textdraw.TextPath((3, 1), (8, 4))]
+        rendered, json, box_json = textdraw.render(objs), textdraw.to_json(objs), textdraw.to_json(box)
+        assert textdraw.render(textdraw.from_json(json)) == rendered
+    finally:
+        textdraw.set_coordinates('math')
+    assert textdraw.to_json(Point(1, 2)) == '{"coordinates":"math","Point":[1,2]}'
+    # Boxes are moved to the corner used in math coordinates, so they cover the same cells.
+    assert textdraw.render(textdraw.from_json(json)) == rendered
+    assert textdraw.from_json(box_json).position == Point(0, -2)
+    # Documents without coordinates were made in math coordinates.
+    assert textdraw.from_json('{"Point":[1,2]}') == Point(1, 2)
+
+
+def test_json_layouts():
+    class Badge:
+        def __textdraw__(self):
+            return textdraw.Box('badge')
+
+    table = textdraw.Table([['a', 'b'], ['c', 'd']], (0, 0), headers=['x', 'y'], justify='right')
+    stack = textdraw.HStack([textdraw.Box('left'), table, Badge()], (1, 2), gap=2)
+    objs = [
+        table,
+        stack,
+        textdraw.VStack([textdraw.Box('top'), stack], (0, 20), justify='center'),
+        textdraw.Grid([[textdraw.Box('a'), textdraw.Box('b')], [table]], (30, 0), gap=(2, 1)),
+        textdraw.Region([(2, 2, 0, 0), (1, 4, 1, 0)]),
+    ]
+    for obj in objs:
+        loaded = textdraw.from_json(textdraw.to_json(obj))
+        assert type(loaded) is type(obj)
+        assert textdraw.render([loaded]) == textdraw.render([obj])
+    loaded = textdraw.from_json(textdraw.to_json(objs))
+    assert [type(obj).__name__ for obj in loaded] == ['Table', 'HStack', 'VStack', 'Grid', 'Region']
+    assert textdraw.render(loaded) == textdraw.render(objs)
+    assert loaded[0].justify == table.justify
+    assert loaded[4] == objs[4]
+    # A child with a __textdraw__ method is stored as the pixels it draws.
+    assert type(loaded[1].children[2]).__name__ == 'PixelGroup'
+
+
+def test_json_flattens_textdraw_objects():
+    class Badge:
+        def __textdraw__(self):
+            return textdraw.Box('badge')
+
+    loaded = textdraw.from_json(textdraw.to_json([Badge()]))
+    assert isinstance(loaded[0], textdraw.PixelGroup)
+    assert textdraw.render(loaded) == textdraw.render([Badge()])
+
+
+def test_json_errors():
+    with pytest.raises(ValueError):
+        textdraw.from_json('{"Point": [1]}')
+    with pytest.raises(ValueError):
+        textdraw.from_json('{"version": 1000, "objects": []}')
+
+
+def test_pickle_round_trip():
+    a = textdraw.Box('hello', (0, 0), title='A')
+    b = textdraw.Box('world', (20, 5))
+    path = textdraw.TextPath(a.bbox.center_right, b.bbox.center_left, label='go')
+    objs = [a, b, path, textdraw.Pixel('x', (3, 3)), textdraw.text('hi', (8, 8))]
+    loaded = pickle.loads(pickle.dumps(objs))
+    assert [type(obj).__name__ for obj in loaded] == ['Box', 'Box', 'TextPath', 'Pixel', 'PixelGroup']
+    assert textdraw.render(loaded) == textdraw.render(objs)
+    assert pickle.loads(pickle.dumps(Point(1, -2))) == Point(1, -2)
+
+
+def test_pickle_layouts():
+    class Badge:
+        def __textdraw__(self):
+            return textdraw.Box('badge')
+
+    table = textdraw.Table([['a', 'b'], ['c', 'd']], (0, 0), headers=['x', 'y'], justify='right')
+    stack = textdraw.HStack([textdraw.Box('left'), table, Badge()], (1, 2), gap=2)
+    objs = [
+        table,
+        stack,
+        textdraw.VStack([textdraw.Box('top'), stack], (0, 20), justify='center'),
+        textdraw.Grid([[textdraw.Box('a'), textdraw.Box('b')], [table]], (30, 0), gap=(2, 1)),
+    ]
+    loaded = pickle.loads(pickle.dumps(objs))
+    assert [type(obj).__name__ for obj in loaded] == ['Table', 'HStack', 'VStack', 'Grid']
+    assert textdraw.render(loaded) == textdraw.render(objs)
+    assert loaded[0].justify == table.justify
+    # A child with a __textdraw__ method is stored as the pixels it draws.
+    assert type(loaded[1].children[2]).__name__ == 'PixelGroup'
+    region = textdraw.Region([(2, 2, 0, 0), (1, 4, 1, 0)])
+    assert pickle.loads(pickle.dumps(region)) == region
+    for duplicate in (copy.copy(stack), copy.deepcopy(stack)):
+        assert duplicate is not stack
+        assert textdraw.render([duplicate]) == textdraw.render([stack])
+    assert copy.deepcopy(region) == region
+
+
+def test_pickle_coordinates():
+    textdraw.set_coordinates('screen')
+    try:
+        box = textdraw.Box('hello', (0, 0), title='A')
+        objs = [
+            box,
+            textdraw.TextPath((7, 1), (12, 4)),
+            textdraw.Table([['a', 'b']], (14, 0)),
+            textdraw.HStack([textdraw.Box('x'), textdraw.Box('y\nz')], (0, 6)),
+            textdraw.Grid([[textdraw.Box('g')]], (20, 6)),
+        ]
+        rendered, dumped = textdraw.render(objs), pickle.dumps(objs)
+        assert pickle.loads(pickle.dumps(box)).position == Point(0, 0)
+    finally:
+        textdraw.set_coordinates('math')
+    loaded = pickle.loads(dumped)
+    assert textdraw.render(loaded) == rendered
+    assert loaded[0].position == Point(0, -2)
+    assert textdraw.render([pickle.loads(pickle.dumps(loaded[0]))]) == textdraw.render([box])
+
+
+def test_screen_coordinates():
+    textdraw.set_coordinates('screen')
+    try:
+        assert textdraw.get_coordinates() == 'screen'
+        # Boxes are placed by their top-left corner, and y grows downwards.
+        box = textdraw.Box('hi', (0, 0))
+        assert plain([box, textdraw.Pixel('x', (0, 3))]) == '┌──┐\n│hi│\n└──┘\nx'
+        assert box.bbox == textdraw.BoundingBox(0, 3, 2, 0)
+        assert (box.bbox.top_left, box.bbox.bottom_right, box.bbox.top_center) == ((0, 0), (3, 2), (1, 0))
+        assert box.text_bbox == textdraw.BoundingBox(1, 2, 1, 1)
+        bbox = textdraw.BoundingBox(0, 5, 3, 0)
+        assert (bbox.top_left, bbox.bottom_left, bbox.height) == ((0, 0), (0, 3), 4)
+        assert Point(0, 1) in bbox
+        assert Point(0, 4) not in bbox
+        assert plain([textdraw.text('ab', (0, 0)), textdraw.text('cd', (0, 1))]) == 'ab\ncd'
+        assert textdraw.Table([['a'], ['b']], (0, 0)).cell_bbox(0, 0) == textdraw.BoundingBox(1, 3, 1, 1)
+        stack = textdraw.VStack([textdraw.Box('A'), textdraw.Box('B')], (0, 0))
+        assert plain([stack]) == '┌─┐\n│A│\n└─┘\n\n┌─┐\n│B│\n└─┘'
+        # Directions are on the screen too, so 'down' leaves the start towards the bottom.
+        screen = [plain([textdraw.TextPath((0, 0), (4, 2), start_direction=d)]) for d in ('up', 'down')]
+        assert screen == ['└───┐\n    │\n    │', '┌───┐\n    │\n    │']
+        with pytest.raises(ValueError, match='Invalid coordinate system'):
+            textdraw.set_coordinates('polar')
+    finally:
+        textdraw.set_coordinates('math')
+    assert textdraw.get_coordinates() == 'math'
+    assert textdraw.BoundingBox(3, 5, 0, 0).top_left == (0, 3)
+    math = [plain([textdraw.TextPath((0, 0), (4, -2), start_direction=d)]) for d in ('up', 'down')]
+    assert math == screen
+
+
+def test_copy():
+    path = textdraw.TextPath((0, 0), (5, 3))
+    for duplicate in (copy.copy(path), copy.deepcopy(path)):
+        assert duplicate is not path
+        assert textdraw.render([duplicate]) == textdraw.render([path])
+    style = textdraw.Style('bold red')
+    assert copy.deepcopy(style)('x') == style('x')
+
+
+def test_bounding_box_equality_and_hashing():
+    bbox = textdraw.BoundingBox(3, 4, 1, 2)
+    assert bbox == textdraw.BoundingBox(3, 4, 1, 2)
+    assert bbox == (3, 4, 1, 2)
+    assert bbox != textdraw.BoundingBox(3, 4, 0, 2)
+    assert hash(bbox) == hash((3, 4, 1, 2))
+    assert textdraw.BoundingBox.empty() == textdraw.BoundingBox(0, -5, 0, 0)
+    assert hash(textdraw.BoundingBox.empty()) == hash(textdraw.BoundingBox(0, -5, 0, 0))
+    assert {bbox: 'a'}[textdraw.BoundingBox(3, 4, 1, 2)] == 'a'
+
+
+def test_bounding_box_size():
+    assert textdraw.BoundingBox(3, 4, 1, 2).width == 3
+    assert textdraw.BoundingBox(3, 4, 1, 2).height == 3
+    cell = textdraw.BoundingBox(0, 0, 0, 0)
+    assert (cell.width, cell.height) == (1, 1)
+    assert cell.center == textdraw.Point(0, 0)
+    empty = textdraw.BoundingBox.empty()
+    assert (empty.width, empty.height) == (0, 0)
+    with pytest.raises(ValueError, match='empty'):
+        empty.center
+    with pytest.raises(ValueError, match='empty'):
+        empty.top_left
+
+
+def test_style_equality_and_hashing():
+    style = textdraw.Style('underline bold #FF00FF on blue')
+    assert style == textdraw.Style('bold underline #ff00ff on blue')
+    assert style != textdraw.Style('bold #ff00ff on blue')
+    assert hash(style) == hash(textdraw.Style('bold underline #ff00ff on blue'))
+    assert str(style) == 'Style(fg=#f0f, bg=blue, effects=[bold, underline])'
+
+
+def test_region_equality():
+    region = textdraw.Region([textdraw.BoundingBox(3, 4, 1, 2)])
+    split = textdraw.Region([textdraw.BoundingBox(3, 3, 1, 2), textdraw.BoundingBox(3, 4, 1, 4)])
+    assert region == split
+    assert region != textdraw.Region()
+
+
+def test_bounding_box_geometry():
+    bbox = textdraw.BoundingBox
+    a, b = bbox(3, 4, 0, 0), bbox(5, 6, 2, 3)
+    assert a.intersection(b) == bbox(3, 4, 2, 3)
+    assert a.intersects(b)
+    assert a.intersection((10, 10, 9, 9)).is_empty
+    assert not a.intersects((10, 10, 9, 9))
+    assert a.expand(1) == bbox(4, 5, -1, -1)
+    assert a.expand(-1) == bbox(2, 3, 1, 1)
+    assert a.expand(-2).is_empty
+    assert bbox.empty().expand(1).is_empty
+    assert a.clamp((10, -3)) == Point(4, 0)
+    assert a.clamp((2, 2)) == Point(2, 2)
+    with pytest.raises(ValueError, match='empty'):
+        bbox.empty().clamp((0, 0))
+    # Distances count the horizontal and vertical steps between the closest cells.
+    assert a.distance(b) == 0
+    assert a.distance((3, 10, 3, 8)) == 4
+    assert a.distance((10, 10, 10, 10)) == 13
+    with pytest.raises(ValueError, match='empty'):
+        bbox.empty().distance(a)
+    assert list(bbox(1, 1, 0, 0)) == [Point(0, 1), Point(1, 1), Point(0, 0), Point(1, 0)]
+    assert list(bbox.empty()) == []
+
+
+def test_region_operations():
+    bbox = textdraw.BoundingBox
+    a, b = bbox(3, 4, 0, 0), bbox(5, 6, 2, 3)
+    region = textdraw.Region([a])
+    assert region | b == region.union(textdraw.Region([b])) == textdraw.Region([a, b])
+    assert region & b == region.intersection(b) == textdraw.Region([bbox(3, 4, 2, 3)])
+    assert region - b == region.difference(b) == textdraw.Region([bbox(1, 4, 0, 0), bbox(3, 2, 2, 0)])
+    assert region.expand(1) == textdraw.Region([a.expand(1)])
+    # Overlapping cells are only counted and iterated over once.
+    union = region | b
+    assert len(union) == len(set(union)) == 32
+    assert (len(region & b), len(region - b)) == (4, 16)
+    assert not textdraw.Region()
+    assert len(textdraw.Region()) == 0
+    assert Point(1, 1) in region
+    assert (1, 1) in region
+    assert (9, 9) not in region
+    assert bbox(1, 1, 0, 0) in region
+    assert bbox(5, 5, 0, 0) not in region
+    hollow = [textdraw.Pixel('#', point) for point in region - bbox(2, 3, 1, 1)]
+    assert plain(hollow) == '#####\n#   #\n#   #\n#####'
+
+
+def test_repr_round_trip():
+    namespace = {name: getattr(textdraw, name) for name in textdraw.__all__}
+    objs = [
+        Point(1, -2),
+        textdraw.BoundingBox(3, 4, 1, 2),
+        textdraw.BoundingBox.empty(),
+        textdraw.Style('bold red on #102030'),
+        textdraw.Style(''),
+        textdraw.Region([textdraw.BoundingBox(3, 4, 1, 2), textdraw.BoundingBox(8, 9, 7, 6)]),
+    ]
+    for obj in objs:
+        assert eval(repr(obj), namespace) == obj
+    pixel = textdraw.Pixel("'", (1, 2), 'red', weight=3)
+    assert repr(pixel) == 'Pixel("\'", Point(1, 2), style=\'red\', weight=3)'
+    assert eval(repr(pixel), namespace) == pixel
+    assert pixel == textdraw.Pixel("'", (1, 2), 'red', weight=3)
+    assert pixel != textdraw.Pixel("'", (1, 2), 'blue', weight=3)
+    assert hash(pixel) == hash(textdraw.Pixel("'", (1, 2), 'red', weight=3))
+    assert textdraw.Pixel('x', (0, 0)) == textdraw.Pixel('x', (0, 0))
+    assert repr(textdraw.Box('hi', (1, 2))) == "Box('hi', Point(1, 2))"
+    drawables = [
+        textdraw.text('hi', (3, 4), 'bold'),
+        textdraw.Box(
+            'hello\nworld',
+            (1, 2),
+            width=12,
+            border_style='blue',
+            line_style='double',
+            padding=(0, 1, 0, 1),
+            justify='center',
+            title='T',
+            footer='F',
+            footer_style='green',
+            shadow='░',
+        ),
+        textdraw.Box('open', borders=(True, False, True, False), line_style=None),
+        textdraw.TextPath((0, 0), (5, 3), style='red', line_style='heavy', end_direction='up'),
+        textdraw.Table(
+            [['a', 'b'], ['c', 'd']], (2, 2), headers=['x', 'y'], justify=['right', 'center'], row_lines=True
+        ),
+        textdraw.Table([['a']], style='red', line_style='double', widths=[4]),
+    ]
+    for obj in drawables:
+        assert type(obj).__module__ == 'textdraw'
+        loaded = eval(repr(obj), namespace)
+        assert repr(loaded) == repr(obj)
+        assert textdraw.render([loaded]) == textdraw.render([obj])
+
+
+SNAPSHOTS = Path(__file__).parent / 'snapshots'
+EXAMPLES = Path(__file__).parents[2] / 'examples'
+
+
+def snapshot_diagram():
+    a = textdraw.Box('hello', (0, 0), title='A', border_style='bold red')
+    b = textdraw.Box('world', (16, 4), line_style='double')
+    path = textdraw.TextPath((7, 1), (15, 5), style='green')
+    return [a, b, path]
+
+
+def test_snapshot_format():
+    objs = [textdraw.text('Hi', (0, 1), 'bold red'), textdraw.Pixel('!', (1, 0))]
+    assert snapshot(objs) == 'textdraw snapshot 2x2\nHi\n !\n\nAA\n .\n\nA: bold red\n'
+    assert snapshot([]) == 'textdraw snapshot 0x0\n'
+
+
+def test_assert_snapshot():
+    assert_snapshot(snapshot_diagram(), SNAPSHOTS / 'two_boxes.txt')
+
+
+def test_snapshot_mismatch(tmp_path):
+    path = tmp_path / 'diagram.txt'
+    with pytest.raises(SnapshotMismatch, match='does not exist'):
+        assert_snapshot(snapshot_diagram(), path)
+    assert_snapshot(snapshot_diagram(), path, update=True)
+    assert_snapshot(snapshot_diagram(), path)
+    changed = snapshot_diagram()
+    changed[0].text = 'hullo'
+    changed[0].border_style = textdraw.Style('bold blue')
+    with pytest.raises(SnapshotMismatch) as info:
+        assert_snapshot(changed, path)
+    message = str(info.value)
+    assert "column 2, row 5: expected 'e', got 'u'" in message
+    assert "column 0, row 4: expected '┌' (bold red), got '┌' (bold blue)" in message
+
+
+def test_diff_snapshots_sizes():
+    small = snapshot([textdraw.Pixel('x', (0, 0))])
+    large = snapshot([textdraw.Pixel('x', (0, 0)), textdraw.Pixel('y', (1, 0))])
+    assert diff_snapshots(small, small) == []
+    assert diff_snapshots(small, large) == [
+        'size: expected 1x1, got 2x1',
+        "column 1, row 0: expected empty, got 'y'",
+    ]
+
+
+def test_textdraw_cycles():
+    class A:
+        def __textdraw__(self):
+            return B()
+
+    class B:
+        def __textdraw__(self):
+            return A()
+
+    class Itself:
+        def __textdraw__(self):
+            return [textdraw.Pixel('x', (0, 0)), self]
+
+    with pytest.raises(RecursionError):
+        textdraw.render([A()])
+    with pytest.raises(RecursionError, match='draws the object itself'):
+        textdraw.render([Itself()])
+
+
+def test_textdraw_nesting():
+    class Nested:
+        def __init__(self, depth):
+            self.depth = depth
+
+        def __textdraw__(self):
+            return Nested(self.depth - 1) if self.depth else textdraw.Pixel('x', (0, 0))
+
+    assert textdraw.render([Nested(10)]) == textdraw.render([textdraw.Pixel('x', (0, 0))])
+
+
+def test_textdraw_expanded_once_per_route():
+    class Node:
+        calls = 0
+
+        def __textdraw__(self):
+            Node.calls += 1
+            return textdraw.Box('n', (4, -1))
+
+    textdraw.TextPath((0, 0), (10, 0), environment=[Node()], barriers=[Node()], avoid_boxes=True)
+    assert Node.calls == 2
+    Node.calls = 0
+    textdraw.multipath([(0, 0), (0, 2)], [(10, 0), (10, 2)], environment=[Node()], avoid_boxes=True)
+    assert Node.calls == 1
+
+
+def test_constraints():
+    a, b, c = textdraw.Box('A', (0, 0)), textdraw.Box('BB\nBB'), textdraw.Box('C')
+    constraints = textdraw.Constraints()
+    constraints.fix(a)
+    constraints.right_of(b, a, gap=2)
+    constraints.below(c, b)
+    constraints.align_center_with(c, b, axis='x')
+    constraints.solve()
+    assert (a.position, b.position, c.position) == ((0, 0), (5, 0), (5, -4))
+    assert plain([a, b, c]) == (
+        '     ┌──┐\n┌─┐  │BB│\n│A│  │BB│\n└─┘  └──┘\n\n     ┌─┐\n     │C│\n     └─┘'
+    )
+
+    x, y = textdraw.Box('x'), textdraw.Box('y')
+    constraints = textdraw.Constraints()
+    constraints.align_top(x, y)
+    constraints.align_bottom(x, y)
+    constraints.align_right(x, y)
+    constraints.above(x, y, gap=0)
+    with pytest.raises(textdraw.ConstraintError) as info:
+        constraints.solve()
+    assert isinstance(info.value, ValueError)
+    assert (info.value.constraint, info.value.relation) == (3, 'above')
+    assert str(info.value) == (
+        'Constraint 3 (above) on object 1 conflicts with earlier constraints, '
+        'which already fix its vertical position 3 cell(s) away'
+    )
+
+    constraints = textdraw.Constraints()
+    constraints.fix(a, (0, 0))
+    constraints.fix(b, (5, 5))
+    constraints.align_left(b, a)
+    with pytest.raises(textdraw.ConstraintError, match='horizontal position 5 cell') as info:
+        constraints.solve()
+    assert (info.value.constraint, info.value.relation) == (2, 'align_left')
+
+
+def test_constraints_with_textdraw_objects():
+    class Widget:
+        def __init__(self):
+            self.position = Point(0, 0)
+
+        def __textdraw__(self):
+            return textdraw.Box('W', self.position)
+
+    class Fixed:
+        def __textdraw__(self):
+            return textdraw.Box('F', (0, 0))
+
+    anchor = textdraw.Box('A', (0, 0))
+    widget = Widget()
+    constraints = textdraw.Constraints()
+    constraints.right_of(widget, anchor, gap=2)
+    constraints.solve()
+    assert widget.position == (5, 0)
+    with pytest.raises(TypeError, match='settable position'):
+        constraints.below(Fixed(), anchor)
+    assert len(constraints) == 1
+
+
+def plain(objs):
+    """The characters of a rendering without styles or trailing spaces."""
+    lines = snapshot(objs).split('\n')
+    height = int(lines[0].rsplit('x', 1)[1])
+    return '\n'.join(lines[1 : 1 + height])
+
+
+def test_border_text_truncation():
+    def top(**kwargs):
+        return plain([textdraw.Box('hi', width=9, **kwargs)]).splitlines()[0]
+
+    assert top(title='Lon g') == '┌─ Lon ─┐'
+    assert top(title='ab cdef') == '┌─ ab ──┐'
+    assert top(title='ab cdef', truncate_string='…') == '┌─ ab… ─┐'
+    assert top(title='Long title', title_justify='right') == '┌─ Lon ─┐'
+
+
+def test_border_text():
+    def box(**kwargs):
+        return plain([textdraw.Box('hi', width=12, **kwargs)])
+
+    assert box(title='T', footer='F') == '┌─ T ──────┐\n│hi        │\n└─ F ──────┘'
+    assert box(title='T', title_justify='center', footer='F', footer_justify='right') == (
+        '┌─── T ────┐\n│hi        │\n└────── F ─┘'
+    )
+    assert box(title='T', borders=(False, True, True, True)) == '│  T       │\n│hi        │\n└──────────┘'
+    styled = textdraw.render(
+        [textdraw.Box('hi', width=12, title='T', title_style='red', footer='F', footer_style='bold blue')]
+    )
+    assert '\x1b[31;49mT\x1b[0m' in styled
+    assert '\x1b[34;49;1mF\x1b[0m' in styled
+
+
+def test_shadow_and_borders():
+    box = textdraw.Box('hi', (0, 0), shadow='░')
+    assert plain([box]) == '┌──┐\n│hi│░\n└──┘░\n ░░░░'
+    assert box.bbox == textdraw.BoundingBox(2, 4, -1, 0)
+    styled = textdraw.render([textdraw.Box('hi', shadow='░', shadow_style='red')])
+    assert styled.count('\x1b[31;49m░\x1b[0m') == 6
+    with pytest.raises(ValueError, match='single character'):
+        textdraw.Box('hi', shadow='ab')
+    # Paths route around the shadow as well as the box.
+    path = textdraw.TextPath((-1, -1), (6, -1), barriers=[box])
+    assert plain([box, path]) == ' ┌──┐\n │hi│░\n └──┘░\n─┐░░░░ │\n └─────┘'
+
+    rules = textdraw.Box('hi', (0, 0), shadow='░', borders=(True, False, True, False))
+    assert plain([rules]) == '────\n hi ░\n────░\n ░░░░'
+    bare = textdraw.Box('hi', (0, 0), borders=(False, False, False, False))
+    assert plain([bare]) == '\n hi\n'
+    assert bare.bbox == textdraw.BoundingBox(2, 3, 0, 0)
+
+
+def test_table():
+    rows = [['a', 1], ['bbb', 22.5]]
+    table = textdraw.Table(rows, (0, 0), headers=['Name', 'N'])
+    assert plain([table]) == (
+        '┌──────┬──────┐\n│ Name │ N    │\n├──────┼──────┤\n│ a    │ 1    │\n│ bbb  │ 22.5 │\n└──────┴──────┘'
+    )
+    assert table.bbox == textdraw.BoundingBox(5, 14, 0, 0)
+    assert table.header_bbox(1) == textdraw.BoundingBox(4, 13, 4, 8)
+    assert table.cell_bbox(0, 0) == textdraw.BoundingBox(2, 6, 2, 1)
+    assert table.cell_bbox(1, 1) == textdraw.BoundingBox(1, 13, 1, 8)
+    with pytest.raises(IndexError, match='Row 5 is out of range'):
+        table.cell_bbox(5, 0)
+
+    table = textdraw.Table(rows, justify=['left', 'right'], padding=0, row_lines=True)
+    assert plain([table]) == '┌───┬────┐\n│a  │   1│\n├───┼────┤\n│bbb│22.5│\n└───┴────┘'
+    table = textdraw.Table(rows, widths=[2, None], truncate_string='…')
+    assert plain([table]) == '┌────┬──────┐\n│ a  │ 1    │\n│ b… │ 22.5 │\n└────┴──────┘'
+    # Missing cells are left blank.
+    assert plain([textdraw.Table([['a\nb'], ['c', 'd']])]) == '┌───┬───┐\n│ a │   │\n│ b │   │\n│ c │ d │\n└───┴───┘'
+    heavy = textdraw.Table([['a', 'b'], ['c', 'd']], line_style='heavy', row_lines=True)
+    assert plain([heavy]) == '┏━━━┳━━━┓\n┃ a ┃ b ┃\n┣━━━╋━━━┫\n┃ c ┃ d ┃\n┗━━━┻━━━┛'
+
+
+def test_layouts():
+    a, b, wide = textdraw.Box('A'), textdraw.Box('B\nB\nB'), textdraw.Box('WIDE')
+
+    def layout(obj, bbox):
+        assert obj.bbox == textdraw.BoundingBox(*bbox)
+        return plain([obj])
+
+    assert layout(textdraw.HStack([a, b], (0, 0)), (4, 6, 0, 0)) == (
+        '┌─┐ ┌─┐\n│A│ │B│\n└─┘ │B│\n    │B│\n    └─┘'
+    )
+    assert layout(textdraw.HStack([a, b], (0, 0), gap=0, align='center'), (4, 5, 0, 0)) == (
+        '   ┌─┐\n┌─┐│B│\n│A││B│\n└─┘│B│\n   └─┘'
+    )
+    stack = textdraw.HStack([a, b], (0, 0), gap=2, padding=(1, 1, 1, 1), align='bottom')
+    assert layout(stack, (6, 9, 0, 0)) == '     ┌─┐\n     │B│\n┌─┐  │B│\n│A│  │B│\n└─┘  └─┘'
+    assert layout(textdraw.VStack([a, wide], (0, 0)), (6, 5, 0, 0)) == (
+        '┌─┐\n│A│\n└─┘\n\n┌────┐\n│WIDE│\n└────┘'
+    )
+    assert layout(textdraw.VStack([a, wide], (0, 0), gap=0, justify='right'), (5, 5, 0, 0)) == (
+        '   ┌─┐\n   │A│\n   └─┘\n┌────┐\n│WIDE│\n└────┘'
+    )
+    stack = textdraw.VStack([a, wide], (0, 0), padding=(1, 0, 0, 2), justify='center')
+    assert layout(stack, (7, 7, 0, 0)) == ' ┌─┐\n │A│\n └─┘\n\n┌────┐\n│WIDE│\n└────┘'
+    assert layout(textdraw.Grid([[a, wide], [b, a]], (0, 0)), (8, 9, 0, 0)) == (
+        '┌─┐ ┌────┐\n│A│ │WIDE│\n└─┘ └────┘\n\n┌─┐ ┌─┐\n│B│ │A│\n│B│ └─┘\n│B│\n└─┘'
+    )
+    grid = textdraw.Grid([[a, wide], [b, a]], (0, 0), gap=(0, 0), align='bottom', justify='center')
+    assert layout(grid, (7, 8, 0, 0)) == (
+        '┌─┐┌────┐\n│A││WIDE│\n└─┘└────┘\n┌─┐\n│B│\n│B│ ┌─┐\n│B│ │A│\n└─┘ └─┘'
+    )
+    # Padding is part of the bounding box, but not of the drawn pixels.
+    assert textdraw.BoundingBox.wrap([stack]) == textdraw.BoundingBox(6, 7, 0, 2)
+    # The children themselves are left where they are.
+    assert [child.position for child in textdraw.HStack([a, b], (0, 0)).arranged] == [(0, 2), (4, 0)]
+    assert a.position == b.position == (0, 0)
+
+
+def test_self_loop_sides():
+    box = textdraw.Box('A\nB', padding=(0, 1, 0, 1))
+
+    def loop(side, span=1):
+        return plain([box, textdraw.TextPath.self_loop(box, side, arrow='arrow', span=span)])
+
+    assert loop('right') == '┌───┐\n│ A │◀┐\n│ B │─┘\n└───┘'
+    assert loop('left') == '  ┌───┐\n┌▶│ A │\n└─│ B │\n  └───┘'
+    assert loop('up') == '  ┌┐\n  │▼\n┌───┐\n│ A │\n│ B │\n└───┘'
+    assert loop('down') == '┌───┐\n│ A │\n│ B │\n└───┘\n  │▲\n  └┘'
+    assert loop('up', span=2) == ' ┌─┐\n │ ▼\n┌───┐\n│ A │\n│ B │\n└───┘'
+    assert loop('down', span=2) == '┌───┐\n│ A │\n│ B │\n└───┘\n │ ▲\n └─┘'
+
+
+def test_self_loop_avoids_corners():
+    box = textdraw.Box('A', padding=(0, 1, 0, 1))
+    for side in ('right', 'left'):
+        with pytest.raises(ValueError, match='too short'):
+            textdraw.TextPath.self_loop(box, side)
+    with pytest.raises(ValueError, match='too short'):
+        textdraw.TextPath.self_loop(box, 'up', span=3)
+    tall = textdraw.Box('A\nB', padding=(0, 1, 0, 1))
+    with pytest.raises(ValueError, match='too short'):
+        textdraw.TextPath.self_loop(tall, 'right', span=2)
+
+
+def test_labels():
+    def label(end, **kwargs):
+        path = textdraw.TextPath((0, 0), end, label='lbl', **kwargs)
+        return plain([path, *kwargs.get('environment', [])])
+
+    assert label((8, 0)) == '───lbl───'
+    assert label((8, 0), label_position='beside') == '   lbl\n─────────'
+    assert label((0, 6)) == ' │\n │\n │\nlbl\n │\n │\n │'
+    assert label((0, 6), label_position='beside') == '│\n│\n│\n│lbl\n│\n│\n│'
+    # A label beside the path goes to the other side if the first one is taken.
+    above = textdraw.text('xxxxxxxxx', (0, 1))
+    assert label((8, 0), label_position='beside', environment=[above]) == 'xxxxxxxxx\n─────────\n   lbl'
+    styled = textdraw.render([textdraw.TextPath((0, 0), (8, 0), label='lbl', label_style='red')])
+    assert styled.count('\x1b[31;49m') == 3
+    with pytest.raises(ValueError, match='Invalid label position'):
+        textdraw.TextPath((0, 0), (8, 0), label='lbl', label_position='above')
+
+
+def test_label_fallback_avoids_objects():
+    box = textdraw.Box('XXXXXXX', (0, 1))
+    path = textdraw.TextPath((0, 0), (3, 0), label='toolong', environment=[box])
+    assert plain([box, path]) == ' ┌───────┐\n │XXXXXXX│\n └───────┘\n ────\ntoolong'
+
+
+def test_label_left_out_without_room():
+    bbox = textdraw.BoundingBox
+    walls = textdraw.Region(
+        [bbox(20, 30, 1, -30), bbox(-1, 30, -20, -30), bbox(0, -1, 0, -30), bbox(0, 30, 0, 4)]
+    )
+    path = textdraw.TextPath((0, 0), (3, 0), label='toolong', environment=[walls])
+    assert plain([path]) == '────'
+
+
+def test_example_multiple_connected_boxes():
+    diagram = runpy.run_path(str(EXAMPLES / 'multiple_connected_boxes.py'))['diagram']
+    assert_snapshot(diagram(), SNAPSHOTS / 'multiple_connected_boxes.txt')
+
+
+def test_parallel_paths_are_not_joined():
+    lower = textdraw.TextPath((0, 0), (6, 0))
+    upper = textdraw.TextPath((0, 1), (6, 1), paths=[lower])
+    assert plain([lower, upper]) == '───────\n───────'
+    crossing = textdraw.TextPath((3, -2), (3, 3), paths=[lower])
+    assert plain([lower, crossing]) == '   │\n   │\n   │\n───┼───\n   │\n   │'
+
+
+def test_crossings():
+    vertical = textdraw.TextPath((3, -2), (3, 2))
+
+    def cross(crossing):
+        return plain([vertical, textdraw.TextPath((0, 0), (6, 0), paths=[vertical], crossing=crossing)])
+
+    assert cross('join') == '   │\n   │\n───┼───\n   │\n   │'
+    assert cross('hop') == '   │\n   │\n───────\n   │\n   │'
+    assert cross('gap') == '   │\n\n───────\n\n   │'
+    with pytest.raises(ValueError):
+        textdraw.TextPath((0, 0), (6, 0), crossing='bridge')
+
+
+def test_lanes_keep_apart():
+    lanes = textdraw.multipath([(0, 0), (0, 0)], [(6, 0), (6, 0)], edge_mode='lanes')
+    assert plain(lanes) == '┌─────┐\n└─────┘'
+
+
+def test_lanes_can_cross():
+    starts, ends = [(0, 3), (3, 0)], [(6, 3), (3, 6)]
+    lanes = textdraw.multipath(starts, ends, edge_mode='lanes')
+    assert plain(lanes) == plain(textdraw.multipath(starts, ends, edge_mode='merge'))
+    assert plain(lanes) == '   │\n   │\n   │\n───┼───\n   │\n   │\n   │'
+
+
+def test_bundle_trunks():
+    fan_out = textdraw.multipath([(0, 0), (0, 0)], [(10, 6), (12, 2)], edge_mode='bundle')
+    assert plain(fan_out) == (
+        '          │\n          │\n┌─────────┴─┐\n│           │\n│           │\n│\n│'
+    )
+    fan_in = textdraw.multipath([(0, 3), (0, -3)], [(10, 0), (10, 0)], edge_mode='bundle')
+    assert plain(fan_in) == '──┐\n  │\n  │\n┌─┴────────\n│\n│\n│'
+
+
+def test_edge_modes():
+    def draw(ends, mode):
+        return plain(textdraw.multipath([(0, 0), (0, 0)], ends, edge_mode=mode))
+
+    parallel, fan_out = [(8, 3), (8, 3)], [(8, 2), (8, -2)]
+    for mode in ('merge', 'bundle'):
+        assert draw(parallel, mode) == '        │\n        │\n        │\n────────┘'
+        assert draw(fan_out, mode) == '        │\n        │\n────────┤\n        │\n        │'
+    assert draw(parallel, 'lanes') == '┌───────┐\n│       │\n│       │\n└───────┘'
+    assert draw(fan_out, 'lanes') == '        │\n        │\n┌───────┘\n│\n└────────'
+
+
+def test_channel_router():
+    a, b, c = textdraw.Box('A', (0, 0)), textdraw.Box('B', (12, 0)), textdraw.Box('C', (6, -1))
+    routes = {
+        router: textdraw.TextPath(
+            (2, 1), (12, 1), environment=[a, b, c], avoid_boxes=True, router=router
+        )
+        for router in ('grid', 'channel')
+    }
+    assert routes['channel'].cost == routes['grid'].cost
+    assert plain([a, b, c, routes['channel']]) == (
+        '┌─┐  ┌─────┐┌─┐\n│A───┘┌─┐  └─B│\n└─┘   │C│   └─┘\n      └─┘'
+    )
+    # Walls which are not boxes do not split the channels, but are still routed around.
+    walls = textdraw.Region([(1, 5, -1, 5)])
+    around = textdraw.TextPath((0, 0), (10, 0), barriers=[walls], router='channel')
+    assert plain([around]) == '────┐     │\n    │     │\n    └─────┘'
+
+
+def test_avoid_boxes():
+    a, b, c = textdraw.Box('A', (0, 0)), textdraw.Box('B', (12, 0)), textdraw.Box('C', (6, 0))
+
+    def route(**kwargs):
+        path = textdraw.TextPath((2, 1), (12, 1), environment=[a, b, c], avoid_boxes=True, **kwargs)
+        return plain([a, b, c, path])
+
+    assert route() == '     ┌─────┐\n┌─┐  │┌─┐  │┌─┐\n│A───┘│C│  └─B│\n└─┘   └─┘   └─┘'
+    # The clearance keeps the path a cell further from every box, including the ones it connects.
+    assert route(clearance=1) == (
+        '    ┌─────┐\n    │     │\n┌─┐ │ ┌─┐ │ ┌─┐\n│A──┘ │C│ └──B│\n└─┘   └─┘   └─┘'
+    )
+
+
+def test_render_canvas():
+    # Later pixels cover earlier ones, and the default style fills the empty cells.
+    assert plain([textdraw.Pixel('a', (0, 0)), textdraw.Pixel('b', (0, 0))]) == 'b'
+    rendered = textdraw.render([textdraw.Pixel('x', (1, 0))], 'on blue', bbox=(0, 1, 0, 0))
+    assert rendered == '\x1b[39;44m \x1b[0m\x1b[39;49mx\x1b[0m\n'
+    assert textdraw.render([]) == ''
+
+
+def test_render_viewport(monkeypatch):
+    def unstyled(objs, **kwargs):
+        return re.sub('\x1b\\[[0-9;]*m', '', textdraw.render(objs, **kwargs))
+
+    box = textdraw.Box('hi', (0, 0))
+
+    def lines(**kwargs):
+        return unstyled([box], **kwargs).split('\n')[:-1]
+
+    # A bbox pads the empty space around the content and clips what falls outside of it.
+    assert lines(bbox=(3, 5, -1, -1)) == ['       ', ' ┌──┐  ', ' │hi│  ', ' └──┘  ', '       ']
+    assert lines(bbox=(1, 2, 1, 1)) == ['hi']
+    assert textdraw.render([], bbox=(0, 1, 0, 0)) == '\x1b[39;49m \x1b[0m\x1b[39;49m \x1b[0m\n'
+    # A clip leaves out the cells outside of it.
+    assert lines(clip=textdraw.BoundingBox(1, 3, 1, 0)) == ['│hi│']
+    assert lines(clip=textdraw.Region([(2, 0, 2, 0), (0, 3, 0, 3)])) == ['┌   ', '    ', '   ┘']
+
+    grid = [textdraw.Pixel(str((x + y) % 10), (x, -y)) for x in range(10) for y in range(10)]
+    monkeypatch.setattr(shutil, 'get_terminal_size', lambda: os.terminal_size((4, 3)))
+    # The terminal view leaves a line for the prompt, and stops scrolling at the edges.
+    assert unstyled(grid, fit_terminal=True) == '0123\n1234\n'
+    assert unstyled(grid, fit_terminal=True, scroll=(2, 1)) == '3456\n4567\n'
+    assert unstyled(grid, fit_terminal=True, scroll=(100, 100)) == '4567\n5678\n'
+
+
+def test_route_many_boxes():
+    boxes = [textdraw.Box('x', (6 * i, 4 * j)) for i in range(20) for j in range(10)]
+    path = textdraw.TextPath((-2, -2), (119, 40), barriers=boxes)
+    # The path goes around every box, so drawing it under or over them makes no difference.
+    assert plain([path, *boxes]) == plain([*boxes, path])
+    assert plain([path, *boxes]).count('x') == 200
+    assert path.cost == textdraw.TextPath((-2, -2), (119, 40), barriers=boxes).cost
+
+
+def test_waypoints():
+    path = textdraw.TextPath((0, 0), (6, 0), waypoints=[(3, 3)])
+    assert plain([path]) == '┌─────┐\n│     │\n│     │\n│     │'
+    path = textdraw.TextPath((0, 0), (6, 0), waypoints=[((3, 3), 'down')])
+    assert plain([path]) == '┌──┐\n│  │\n│  │\n│  │\n│  └───'
+    path = textdraw.TextPath((0, 0), (6, 0), waypoints=[(2, 2), (4, -2)])
+    assert plain([path]) == '┌───┐\n│   │\n│   │ │\n    │ │\n    └─┘'
+
+
+def test_from_polyline():
+    path = textdraw.TextPath.from_polyline([(0, 0), (4, 0), (4, 2), (8, 2)])
+    assert plain([path]) == '    ┌────\n    │\n────┘'
+    with pytest.raises(ValueError, match='not horizontal or vertical'):
+        textdraw.TextPath.from_polyline([(0, 0), (3, 3)])
+
+
+def test_route_error():
+    ring = [textdraw.Pixel('#', (x, y)) for x in range(5, 8) for y in range(-1, 2) if (x, y) != (6, 0)]
+    with pytest.raises(textdraw.RouteError) as info:
+        textdraw.TextPath((0, 0), (6, 0), barriers=ring)
+    error = info.value
+    assert isinstance(error, ValueError)
+    assert str(error) == 'No path found from (0, 0) to (6, 0) (46 cells explored)'
+    assert (error.edge, error.start, error.end, error.waypoint) == (None, (0, 0), (6, 0), None)
+    assert not (error.start_in_barrier or error.end_in_barrier)
+    assert not (error.start_outside_bbox or error.end_outside_bbox)
+    assert error.explored == len(error.overlay.pixels) == 46
+
+    with pytest.raises(textdraw.RouteError, match='the end is inside a barrier') as info:
+        textdraw.TextPath((0, 0), (6, 0), barriers=[*ring, textdraw.Pixel('#', (6, 0))])
+    assert info.value.end_in_barrier
+    assert not info.value.start_in_barrier
+
+    wall = [textdraw.Pixel('#', (x, 0)) for x in range(1, 6)]
+    with pytest.raises(textdraw.RouteError) as info:
+        textdraw.TextPath((0, 0), (6, 0), bbox=(0, 5, 0, 1), barriers=wall)
+    assert info.value.start_outside_bbox
+    assert info.value.end_outside_bbox
+    assert 'the start is outside the bbox, the end is outside the bbox' in str(info.value)
+
+    with pytest.raises(textdraw.RouteError, match='through waypoint \\(6, 1\\) going up') as info:
+        textdraw.TextPath((0, 0), (6, 0), waypoints=[((6, 1), 'up')], barriers=[textdraw.Pixel('#', (6, 2))])
+    assert info.value.waypoint == (6, 1)
+
+    with pytest.raises(textdraw.RouteError, match='for edge 1 from') as info:
+        textdraw.multipath([(0, 0), (0, 3)], [(9, 0), (6, 0)], barriers=ring)
+    assert (info.value.edge, info.value.start, info.value.end) == (1, (0, 3), (6, 0))
+
+
+def test_route_stays_in_bbox():
+    wall = textdraw.Pixel('#', (3, 0))
+    with pytest.raises(textdraw.RouteError):
+        textdraw.TextPath((0, 0), (6, 0), bbox=textdraw.BoundingBox(0, 6, 0, 0), barriers=[wall])
+    assert plain([textdraw.TextPath((0, 0), (6, 0), barriers=[wall])]) == '──┐   │\n  └───┘'
+
+
+def test_transform_glyphs():
+    objs = [
+        textdraw.TextPath((0, 0), (3, 2), line_style='heavy'),
+        textdraw.Pixel(textdraw.arrow('right'), (4, 2)),
+    ]
+    assert plain(objs) == '┏━━━▶\n┃\n┃'
+    assert plain([textdraw.mirror(objs)]) == '◀━━━┓\n    ┃\n    ┃'
+    assert plain([textdraw.mirror(objs, 'vertical')]) == '┃\n┃\n┗━━━▶'
+    assert plain([textdraw.rotate(objs)]) == '▲\n┃\n┃\n┃\n┗━━'
+    assert plain([textdraw.rotate(objs, -1)]) == '━━┓\n  ┃\n  ┃\n  ┃\n  ▼'
+    assert plain([textdraw.transpose(objs)]) == '┏━━\n┃\n┃\n┃\n▼'
+
+
+def test_transform_keeps_text_readable():
+    box = textdraw.Box('one\ntwo', (0, 0), title='t', padding=(0, 1, 0, 1))
+    assert plain([textdraw.mirror([box])]) == plain([box])
+    assert plain([textdraw.mirror([box], 'vertical')]) == '┌─────┐\n│ one │\n│ two │\n└─ t ─┘'
+    assert plain([textdraw.rotate([box], 2)]) == '┌─────┐\n│ one │\n│ two │\n└─ t ─┘'
+    # Text is clipped to the inside of the box, and the title reads down the border.
+    assert plain([textdraw.rotate([box])]) == '┌──┐\n│  │\n│  │\ntne│\n│wo│\n│  │\n└──┘'
+    path = textdraw.TextPath((0, 0), (10, 0), label='go')
+    assert plain([textdraw.mirror([path])]) == '─────go────'
+    assert plain([textdraw.scale([textdraw.text('abc', (0, 0))], 3)]).strip() == 'abc'
+
+
+def test_transform_titled_box():
+    box = textdraw.Box('hello world', (0, 0), title='Title', footer='f', border_style='red')
+    objs = [textdraw.scale([box], 2), textdraw.rotate([box], position=(30, 0))]
+    assert_snapshot(objs, SNAPSHOTS / 'transformed_box.txt')
@@ -1,29 +1,61 @@
 from .textdraw import (
     BoundingBox,
     Box,
+    ConstraintError,
+    Constraints,
+    Grid,
+    HStack,
     Pixel,
     PixelGroup,
     Point,
+    Region,
+    RouteError,
     Style,
+    Table,
     TextPath,
+    VStack,
     arrow,
     duplicate_shifted,
+    from_json,
+    get_coordinates,
+    mirror,
     multipath,
     render,
+    rotate,
+    scale,
+    set_coordinates,
     text,
+    to_json,
+    transpose,
 )
 
 __all__ = [
     'BoundingBox',
     'Box',
+    'ConstraintError',
+    'Constraints',
+    'Grid',
+    'HStack',
     'Pixel',
     'PixelGroup',
     'Point',
+    'Region',
+    'RouteError',
     'Style',
+    'Table',
     'TextPath',
+    'VStack',
     'arrow',
     'duplicate_shifted',
+    'from_json',
+    'get_coordinates',
+    'mirror',
     'multipath',
     'render',
+    'rotate',
+    'scale',
+    'set_coordinates',
     'text',
+    'to_json',
+    'transpose',
 ]
@@ -1,5 +1,12 @@
-from collections.abc import Sequence
-from typing import Literal, Self
+from collections.abc import Iterator, Sequence
+from typing import Literal, Protocol, Self, TypeAlias
+
+
+class SupportsTextdraw(Protocol):
+    def __textdraw__(self) -> Drawable | Sequence[Drawable]: ...
+
+
+Drawable: TypeAlias = PixelGroup | Pixel | TextPath | Box | Table | HStack | VStack | Grid | Region | SupportsTextdraw
 
 
 class Point:
@@ -7,11 +14,27 @@ class Point:
     y: int
 
     def __init__(self, x: int, y: int): ...
-    def __add__(self, other: Self) -> Self: ...
-    def __radd__(self, other: Self) -> Self: ...
-    def __sub__(self, other: Self) -> Self: ...
-    def __rsub__(self, other: Self) -> Self: ...
-    def midpoint(self, other: Self) -> Self: ...
+    def __copy__(self) -> Self: ...
+    def __deepcopy__(self, memo: dict[int, object]) -> Self: ...
+    def __add__(self, other: Self | tuple[int, int]) -> Self: ...
+    def __radd__(self, other: Self | tuple[int, int]) -> Self: ...
+    def __sub__(self, other: Self | tuple[int, int]) -> Self: ...
+    def __rsub__(self, other: Self | tuple[int, int]) -> Self: ...
+    def __mul__(self, other: int) -> Self: ...
+    def __rmul__(self, other: int) -> Self: ...
+    def __neg__(self) -> Self: ...
+    def __eq__(self, other: object) -> bool: ...
+    def __hash__(self) -> int: ...
+    def __iter__(self) -> Iterator[int]: ...
+    def midpoint(self, other: Self | tuple[int, int]) -> Self: ...
+    def manhattan_distance(self, other: Self | tuple[int, int]) -> int: ...
+    def chebyshev_distance(self, other: Self | tuple[int, int]) -> int: ...
+    def neighbors(self, diagonal: bool = False) -> list[Point]: ...
+    def direction_to(
+        self, other: Self | tuple[int, int]
+    ) -> Literal['up', 'right', 'down', 'left'] | None: ...
+    def step(self, direction: Literal['up', 'right', 'down', 'left'], distance: int = 1) -> Self: ...
+    def line_to(self, other: Self | tuple[int, int]) -> list[Point]: ...
 
 
 class BoundingBox:
@@ -30,15 +53,48 @@ class BoundingBox:
     bottom_left: Point
 
     def __init__(self, top: int, right: int, bottom: int, left: int): ...
+    def __eq__(self, other: object) -> bool: ...
+    def __hash__(self) -> int: ...
+    def __copy__(self) -> Self: ...
+    def __deepcopy__(self, memo: dict[int, object]) -> Self: ...
     def __add__(self, other: Point | Self) -> Self: ...
     def __contains__(self, other: Point | Self) -> bool: ...
     @property
     def width(self) -> int: ...
     @property
     def height(self) -> int: ...
+    @property
+    def is_empty(self) -> bool: ...
+    @staticmethod
+    def wrap(objs: Sequence[Drawable]) -> Self: ...
     @staticmethod
-    def wrap(objs: Sequence[PixelGroup | Pixel | TextPath | Box]) -> Self: ...
+    def empty() -> BoundingBox: ...
     def duplicate_shifted(self, position: Point | tuple[int, int]) -> Self: ...
+    def intersection(self, other: Self | tuple[int, int, int, int]) -> Self: ...
+    def intersects(self, other: Self | tuple[int, int, int, int]) -> bool: ...
+    def expand(self, margin: int) -> Self: ...
+    def clamp(self, point: Point | tuple[int, int]) -> Point: ...
+    def distance(self, other: Self | tuple[int, int, int, int]) -> int: ...
+    def __iter__(self) -> Iterator[Point]: ...
+
+
+class Region:
+    rects: list[BoundingBox]
+    bbox: BoundingBox
+
+    def __init__(self, rects: Sequence[BoundingBox | tuple[int, int, int, int]] | None = None): ...
+    def __eq__(self, other: object) -> bool: ...
+    def __contains__(self, other: Point | tuple[int, int] | BoundingBox) -> bool: ...
+    def union(self, other: Self | BoundingBox) -> Self: ...
+    def intersection(self, other: Self | BoundingBox) -> Self: ...
+    def difference(self, other: Self | BoundingBox) -> Self: ...
+    def expand(self, margin: int) -> Self: ...
+    def __or__(self, other: Self | BoundingBox) -> Self: ...
+    def __and__(self, other: Self | BoundingBox) -> Self: ...
+    def __sub__(self, other: Self | BoundingBox) -> Self: ...
+    def __len__(self) -> int: ...
+    def __bool__(self) -> bool: ...
+    def __iter__(self) -> Iterator[Point]: ...
 
 
 class PixelGroup:
@@ -54,6 +110,8 @@ class PixelGroup:
         style: str | None = None,
         weight: int | None = None,
     ): ...
+    def __copy__(self) -> Self: ...
+    def __deepcopy__(self, memo: dict[int, object]) -> Self: ...
     def __getitem__(self, index: int) -> Pixel: ...
     def __setitem__(self, index: int, pixel: Pixel) -> None: ...
     @property
@@ -76,12 +134,20 @@ class Pixel:
         *,
         weight: int | None = None,
     ): ...
+    def __eq__(self, other: object) -> bool: ...
+    def __hash__(self) -> int: ...
+    def __copy__(self) -> Self: ...
+    def __deepcopy__(self, memo: dict[int, object]) -> Self: ...
     def duplicate(self, position: Point | tuple[int, int] | None = None) -> Self: ...
     def duplicate_shifted(self, position: Point | tuple[int, int]) -> Self: ...
 
 
 class Style:
     def __init__(self, style: str): ...
+    def __eq__(self, other: object) -> bool: ...
+    def __hash__(self) -> int: ...
+    def __copy__(self) -> Self: ...
+    def __deepcopy__(self, memo: dict[int, object]) -> Self: ...
     def __add__(self, other: str | Self) -> Self: ...
     def __call__(self, text: str) -> str: ...
     @property
@@ -91,10 +157,69 @@ class Style:
     def bg(self) -> str: ...
 
 
-def render(objs: Sequence[PixelGroup | Pixel | TextPath | Box], default_style: str | None = None) -> str: ...
+def render(
+    objs: Sequence[Drawable],
+    default_style: str | None = None,
+    *,
+    bbox: BoundingBox | tuple[int, int, int, int] | None = None,
+    fit_terminal: bool = False,
+    scroll: tuple[int, int] = (0, 0),
+    clip: Region | BoundingBox | None = None,
+) -> str: ...
 def duplicate_shifted(
-    objs: Sequence[PixelGroup | Pixel | TextPath | Box], delta: Point | tuple[int, int]
-) -> list[PixelGroup | Pixel | TextPath | Box]: ...
+    objs: Sequence[Drawable], delta: Point | tuple[int, int]
+) -> list[Drawable]: ...
+def to_json(
+    obj: Point | BoundingBox | Style | Drawable | Sequence[Point | BoundingBox | Style | Drawable],
+    *,
+    indent: int | None = None,
+) -> str: ...
+def from_json(
+    json: str,
+) -> (
+    Point
+    | BoundingBox
+    | Style
+    | Pixel
+    | PixelGroup
+    | TextPath
+    | Box
+    | Table
+    | HStack
+    | VStack
+    | Grid
+    | Region
+    | list[Point | BoundingBox | Style | Pixel | PixelGroup | TextPath | Box | Table | HStack | VStack | Grid | Region]
+): ...
+def mirror(
+    objs: Sequence[Drawable],
+    direction: Literal['horizontal', 'vertical'] = 'horizontal',
+    position: Point | tuple[int, int] | None = None,
+) -> PixelGroup: ...
+def rotate(
+    objs: Sequence[Drawable], turns: int = 1, position: Point | tuple[int, int] | None = None
+) -> PixelGroup: ...
+def transpose(
+    objs: Sequence[Drawable], position: Point | tuple[int, int] | None = None
+) -> PixelGroup: ...
+def scale(
+    objs: Sequence[Drawable],
+    factor: int | tuple[int, int],
+    position: Point | tuple[int, int] | None = None,
+) -> PixelGroup: ...
+
+
+class RouteError(ValueError):
+    edge: int | None
+    start: Point
+    end: Point
+    waypoint: Point | None
+    start_in_barrier: bool
+    end_in_barrier: bool
+    start_outside_bbox: bool
+    end_outside_bbox: bool
+    explored: int
+    overlay: PixelGroup
 
 
 class TextPath:
@@ -104,6 +229,8 @@ class TextPath:
     weight: int | None
     start_direction: Literal['up', 'right', 'down', 'left'] | None
     end_direction: Literal['up', 'right', 'down', 'left'] | None
+    crossing: Literal['join', 'hop', 'gap']
+    label_style: Style
 
     def __init__(
         self,
@@ -117,14 +244,56 @@ class TextPath:
         start_direction: Literal['up', 'right', 'down', 'left'] | None = None,
         end_direction: Literal['up', 'right', 'down', 'left'] | None = None,
         bend_penalty: int = 1,
-        environment: Sequence[PixelGroup | Pixel | TextPath | Box] | None = None,
-        barriers: Sequence[PixelGroup | Pixel | TextPath | Box] | None = None,
-        paths: Sequence[PixelGroup | Pixel | TextPath | Box] | None = None,
+        environment: Sequence[Drawable] | None = None,
+        barriers: Sequence[Drawable] | None = None,
+        paths: Sequence[Drawable] | None = None,
         bbox: BoundingBox | tuple[int, int, int, int] | None = None,
+        crossing: Literal['join', 'hop', 'gap'] = 'join',
+        waypoints: Sequence[
+            Point | tuple[int, int] | tuple[Point | tuple[int, int], Literal['up', 'right', 'down', 'left']]
+        ]
+        | None = None,
+        avoid_boxes: bool = False,
+        clearance: int = 0,
+        router: Literal['grid', 'channel'] = 'grid',
+        label: str | None = None,
+        label_style: str | None = None,
+        label_position: Literal['inline', 'beside'] = 'inline',
     ) -> Self: ...
+    def __copy__(self) -> Self: ...
+    def __deepcopy__(self, memo: dict[int, object]) -> Self: ...
+    @staticmethod
+    def from_polyline(
+        points: Sequence[Point | tuple[int, int]],
+        position: Point | tuple[int, int] | None = None,
+        style: str | None = None,
+        *,
+        line_style: Literal['light', 'heavy', 'double'] = 'light',
+        weight: int | None = None,
+        start_direction: Literal['up', 'right', 'down', 'left'] | None = None,
+        end_direction: Literal['up', 'right', 'down', 'left'] | None = None,
+        paths: Sequence[Drawable] | None = None,
+        crossing: Literal['join', 'hop', 'gap'] = 'join',
+    ) -> TextPath: ...
+    @staticmethod
+    def self_loop(
+        box: Box,
+        side: Literal['up', 'right', 'down', 'left'] = 'right',
+        style: str | None = None,
+        *,
+        line_style: Literal['light', 'heavy', 'double'] = 'light',
+        weight: int | None = None,
+        size: int = 1,
+        span: int = 1,
+        arrow: str | None = None,
+    ) -> TextPath: ...
     @property
     def cost(self) -> int: ...
     @property
+    def label(self) -> str | None: ...
+    @property
+    def label_position(self) -> Literal['inline', 'beside']: ...
+    @property
     def bbox(self) -> BoundingBox: ...
     def duplicate(self, position: Point | tuple[int, int] | None = None) -> Self: ...
     def duplicate_shifted(self, position: Point | tuple[int, int]) -> Self: ...
@@ -141,14 +310,24 @@ def multipath(
     start_directions: Sequence[Literal['up', 'right', 'down', 'left'] | None] | None = None,
     end_directions: Sequence[Literal['up', 'right', 'down', 'left'] | None] | None = None,
     bend_penalty: int = 1,
-    environment: Sequence[PixelGroup | Pixel | TextPath | Box] | None = None,
-    barriers: Sequence[PixelGroup | Pixel | TextPath | Box] | None = None,
-    paths: Sequence[PixelGroup | Pixel | TextPath | Box] | None = None,
+    environment: Sequence[Drawable] | None = None,
+    barriers: Sequence[Drawable] | None = None,
+    paths: Sequence[Drawable] | None = None,
     bbox: BoundingBox | tuple[int, int, int, int] | None = None,
     optimize: bool = False,
+    edge_mode: Literal['merge', 'lanes', 'bundle'] = 'merge',
+    crossing: Literal['join', 'hop', 'gap'] = 'join',
+    avoid_boxes: bool = False,
+    clearance: int = 0,
+    router: Literal['grid', 'channel'] = 'grid',
+    labels: Sequence[str | None] | None = None,
+    label_style: str | None = None,
+    label_position: Literal['inline', 'beside'] = 'inline',
 ) -> list[TextPath]: ...
 
 
+def set_coordinates(system: Literal['math', 'screen']) -> None: ...
+def get_coordinates() -> Literal['math', 'screen']: ...
 def arrow(fmt: str) -> str: ...
 def text(
     text: str, position: Point | tuple[int, int] | None = None, style: str | None = None, weight: int | None = None
@@ -171,6 +350,15 @@ class Box:
     truncate_string: str | None
     transparent: bool
     transparent_padding: bool
+    title: str | None
+    title_style: Style
+    title_justify: Literal['right', 'center', 'left']
+    footer: str | None
+    footer_style: Style
+    footer_justify: Literal['right', 'center', 'left']
+    borders: tuple[bool, bool, bool, bool]
+    shadow: str | None
+    shadow_style: Style
 
     def __init__(
         self,
@@ -190,7 +378,18 @@ class Box:
         truncate_string: str | None = None,
         transparent: bool = False,
         transparent_padding: bool = False,
+        title: str | None = None,
+        title_style: str | None = None,
+        title_justify: Literal['right', 'center', 'left'] = 'left',
+        footer: str | None = None,
+        footer_style: str | None = None,
+        footer_justify: Literal['right', 'center', 'left'] = 'left',
+        borders: tuple[bool, bool, bool, bool] = (True, True, True, True),
+        shadow: str | None = None,
+        shadow_style: str | None = None,
     ) -> Self: ...
+    def __copy__(self) -> Self: ...
+    def __deepcopy__(self, memo: dict[int, object]) -> Self: ...
     @property
     def bbox(self) -> BoundingBox: ...
     @property
@@ -198,18 +397,168 @@ class Box:
     def duplicate(self, position: Point | tuple[int, int] | None = None) -> Self: ...
     def duplicate_shifted(self, position: Point | tuple[int, int]) -> Self: ...
 
+class Table:
+    rows: list[list[str]]
+    position: Point
+    headers: list[str] | None
+    style: Style
+    header_style: Style
+    border_style: Style
+    line_style: Literal['light', 'heavy', 'double']
+    weight: int | None
+    justify: list[Literal['right', 'center', 'left']]
+    widths: list[int | None] | None
+    padding: int
+    row_lines: bool
+    truncate_string: str | None
+
+    def __init__(
+        self,
+        rows: Sequence[Sequence[object]],
+        position: Point | tuple[int, int] | None = None,
+        *,
+        headers: Sequence[str] | None = None,
+        style: str | None = None,
+        header_style: str | None = None,
+        border_style: str | None = None,
+        line_style: Literal['light', 'heavy', 'double'] = 'light',
+        weight: int | None = 1,
+        justify: Literal['right', 'center', 'left'] | Sequence[Literal['right', 'center', 'left']] | None = None,
+        widths: Sequence[int | None] | None = None,
+        padding: int = 1,
+        row_lines: bool = False,
+        truncate_string: str | None = None,
+    ) -> Self: ...
+    @property
+    def bbox(self) -> BoundingBox: ...
+    def cell_bbox(self, row: int, column: int) -> BoundingBox: ...
+    def header_bbox(self, column: int) -> BoundingBox: ...
+    def duplicate(self, position: Point | tuple[int, int] | None = None) -> Self: ...
+    def duplicate_shifted(self, position: Point | tuple[int, int]) -> Self: ...
+
+
+class HStack:
+    children: list[Drawable]
+    position: Point
+    gap: int
+    padding: tuple[int, int, int, int]
+    align: Literal['top', 'center', 'bottom']
+
+    def __init__(
+        self,
+        children: Sequence[Drawable],
+        position: Point | tuple[int, int] | None = None,
+        *,
+        gap: int = 1,
+        padding: tuple[int, int, int, int] = (0, 0, 0, 0),
+        align: Literal['top', 'center', 'bottom'] = 'top',
+    ) -> Self: ...
+    @property
+    def arranged(self) -> list[Drawable]: ...
+    @property
+    def bbox(self) -> BoundingBox: ...
+    def duplicate(self, position: Point | tuple[int, int] | None = None) -> Self: ...
+    def duplicate_shifted(self, position: Point | tuple[int, int]) -> Self: ...
+
+
+class VStack:
+    children: list[Drawable]
+    position: Point
+    gap: int
+    padding: tuple[int, int, int, int]
+    justify: Literal['right', 'center', 'left']
+
+    def __init__(
+        self,
+        children: Sequence[Drawable],
+        position: Point | tuple[int, int] | None = None,
+        *,
+        gap: int = 1,
+        padding: tuple[int, int, int, int] = (0, 0, 0, 0),
+        justify: Literal['right', 'center', 'left'] = 'left',
+    ) -> Self: ...
+    @property
+    def arranged(self) -> list[Drawable]: ...
+    @property
+    def bbox(self) -> BoundingBox: ...
+    def duplicate(self, position: Point | tuple[int, int] | None = None) -> Self: ...
+    def duplicate_shifted(self, position: Point | tuple[int, int]) -> Self: ...
+
+
+class Grid:
+    children: list[list[Drawable]]
+    position: Point
+    gap: tuple[int, int]
+    padding: tuple[int, int, int, int]
+    align: Literal['top', 'center', 'bottom']
+    justify: Literal['right', 'center', 'left']
+
+    def __init__(
+        self,
+        children: Sequence[Sequence[Drawable]],
+        position: Point | tuple[int, int] | None = None,
+        *,
+        gap: tuple[int, int] = (1, 1),
+        padding: tuple[int, int, int, int] = (0, 0, 0, 0),
+        align: Literal['top', 'center', 'bottom'] = 'top',
+        justify: Literal['right', 'center', 'left'] = 'left',
+    ) -> Self: ...
+    @property
+    def arranged(self) -> list[list[Drawable]]: ...
+    @property
+    def bbox(self) -> BoundingBox: ...
+    def duplicate(self, position: Point | tuple[int, int] | None = None) -> Self: ...
+    def duplicate_shifted(self, position: Point | tuple[int, int]) -> Self: ...
+
+
+class ConstraintError(ValueError):
+    constraint: int
+    relation: str
+
+
+class Constraints:
+    def __init__(self) -> Self: ...
+    def __len__(self) -> int: ...
+    def left_of(self, obj: Drawable, other: Drawable, gap: int = 1) -> None: ...
+    def right_of(self, obj: Drawable, other: Drawable, gap: int = 1) -> None: ...
+    def above(self, obj: Drawable, other: Drawable, gap: int = 1) -> None: ...
+    def below(self, obj: Drawable, other: Drawable, gap: int = 1) -> None: ...
+    def align_left(self, obj: Drawable, other: Drawable) -> None: ...
+    def align_right(self, obj: Drawable, other: Drawable) -> None: ...
+    def align_top(self, obj: Drawable, other: Drawable) -> None: ...
+    def align_bottom(self, obj: Drawable, other: Drawable) -> None: ...
+    def align_center_with(self, obj: Drawable, other: Drawable, axis: Literal['x', 'y', 'both'] = 'both') -> None: ...
+    def fix(self, obj: Drawable, position: Point | tuple[int, int] | None = None) -> None: ...
+    def solve(self) -> None: ...
+
 
 __all__ = [
     'BoundingBox',
     'Box',
+    'ConstraintError',
+    'Constraints',
+    'Grid',
+    'HStack',
     'Pixel',
     'PixelGroup',
     'Point',
+    'Region',
+    'RouteError',
     'Style',
+    'Table',
     'TextPath',
+    'VStack',
     'arrow',
     'duplicate_shifted',
+    'from_json',
+    'get_coordinates',
+    'mirror',
     'multipath',
     'render',
+    'rotate',
+    'scale',
+    'set_coordinates',
     'text',
+    'to_json',
+    'transpose',
 ]
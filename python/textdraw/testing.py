@@ -0,0 +1,170 @@
+"""Helpers for snapshot testing rendered diagrams.
+
+A snapshot is a plain form of a rendered diagram (see `snapshot`) which holds the character and
+the style of every cell without any ANSI escape codes. Snapshots are stored in text files and
+compared cell by cell, so a failing test reports exactly which cells changed.
+
+Examples
+--------
+>>> from textdraw import Box
+>>> from textdraw.testing import assert_snapshot
+>>> def test_box():
+...     assert_snapshot([Box('hello', (0, 0))], 'tests/snapshots/box.txt')
+
+Run the tests with the environment variable ``TEXTDRAW_UPDATE_SNAPSHOTS=1`` to write (or
+overwrite) the snapshot files instead of comparing against them.
+"""
+
+from __future__ import annotations
+
+import os
+from collections.abc import Sequence
+from dataclasses import dataclass
+from pathlib import Path
+
+from .textdraw import render_snapshot as snapshot
+
+__all__ = ['SnapshotMismatch', 'assert_snapshot', 'diff_snapshots', 'snapshot']
+
+UPDATE_VARIABLE = 'TEXTDRAW_UPDATE_SNAPSHOTS'
+MAX_REPORTED_CELLS = 20
+
+
+class SnapshotMismatch(AssertionError):
+    """Raised by `assert_snapshot` when a rendering does not match its stored snapshot."""
+
+
+@dataclass(frozen=True)
+class _Snapshot:
+    width: int
+    height: int
+    text: list[str]
+    styles: list[str]
+    legend: dict[str, str]
+
+    @classmethod
+    def parse(cls, source: str) -> _Snapshot:
+        lines = source.replace('\r\n', '\n').split('\n')
+        header = lines[0].split()
+        if len(header) != 3 or header[:2] != ['textdraw', 'snapshot'] or 'x' not in header[2]:
+            msg = f'Invalid snapshot header: {lines[0]!r}'
+            raise ValueError(msg)
+        width, height = (int(n) for n in header[2].split('x'))
+        text = lines[1 : 1 + height]
+        styles = lines[2 + height : 2 + 2 * height]
+        legend = {}
+        for line in lines[3 + 2 * height :]:
+            if line:
+                key, _, spec = line.partition(': ')
+                legend[key] = spec
+        return cls(width, height, text, styles, legend)
+
+    def cell(self, column: int, row: int) -> tuple[str, str] | None:
+        """The character and style of a cell, or None if nothing is drawn there."""
+        if row >= self.height or column >= self.width:
+            return None
+        character = _get(self.text, column, row)
+        key = _get(self.styles, column, row)
+        if key == ' ':
+            return None
+        return character, '' if key == '.' else self.legend.get(key, f'<unknown style {key!r}>')
+
+
+def _get(lines: list[str], column: int, row: int) -> str:
+    line = lines[row] if row < len(lines) else ''
+    return line[column] if column < len(line) else ' '
+
+
+def _describe(cell: tuple[str, str] | None) -> str:
+    if cell is None:
+        return 'empty'
+    character, style = cell
+    return f'{character!r} ({style})' if style else f'{character!r}'
+
+
+def diff_snapshots(expected: str, actual: str) -> list[str]:
+    """Compares two snapshots cell by cell.
+
+    Parameters
+    ----------
+    expected : str
+        The stored snapshot.
+    actual : str
+        The snapshot of the current rendering.
+
+    Returns
+    -------
+    list of str
+        A line describing each difference, or an empty list if the snapshots match. Cells are
+        given by their column and row from the top-left corner of the snapshot.
+    """
+    old = _Snapshot.parse(expected)
+    new = _Snapshot.parse(actual)
+    differences = []
+    if (old.width, old.height) != (new.width, new.height):
+        differences.append(f'size: expected {old.width}x{old.height}, got {new.width}x{new.height}')
+    for row in range(max(old.height, new.height)):
+        for column in range(max(old.width, new.width)):
+            before = old.cell(column, row)
+            after = new.cell(column, row)
+            if before != after:
+                differences.append(
+                    f'column {column}, row {row}: '
+                    f'expected {_describe(before)}, got {_describe(after)}'
+                )
+    return differences
+
+
+def assert_snapshot(
+    objs: Sequence[object],
+    path: str | os.PathLike[str],
+    *,
+    bbox: object = None,
+    clip: object = None,
+    update: bool | None = None,
+) -> None:
+    """Checks that a list of objects renders the same as a stored snapshot.
+
+    Parameters
+    ----------
+    objs : list
+        A list of objects to render, as for `textdraw.render`.
+    path : str or PathLike
+        The snapshot file.
+    bbox : BoundingBox or tuple of ints, optional
+        The region to render, as for `textdraw.render`.
+    clip : Region or BoundingBox, optional
+        If provided, only the cells inside this region are drawn.
+    update : bool, optional
+        If True, the snapshot file is written instead of compared. Defaults to True if the
+        ``TEXTDRAW_UPDATE_SNAPSHOTS`` environment variable is set to anything but "" or "0".
+
+    Raises
+    ------
+    SnapshotMismatch
+        If the snapshot file is missing or the rendering does not match it. The message lists
+        the cells which differ, followed by the plain text of the new rendering.
+    """
+    if update is None:
+        update = os.environ.get(UPDATE_VARIABLE, '') not in ('', '0')
+    path = Path(path)
+    actual = snapshot(objs, bbox=bbox, clip=clip)
+    if update:
+        path.parent.mkdir(parents=True, exist_ok=True)
+        path.write_text(actual, encoding='utf-8')
+        return
+    if not path.exists():
+        msg = f'Snapshot {path} does not exist (set {UPDATE_VARIABLE}=1 to create it)'
+        raise SnapshotMismatch(msg)
+    differences = diff_snapshots(path.read_text(encoding='utf-8'), actual)
+    if not differences:
+        return
+    count = f'{len(differences)} difference{"s" if len(differences) != 1 else ""}'
+    lines = [f'Snapshot {path} does not match ({count}):']
+    lines.extend(f'  {difference}' for difference in differences[:MAX_REPORTED_CELLS])
+    if len(differences) > MAX_REPORTED_CELLS:
+        lines.append(f'  ... and {len(differences) - MAX_REPORTED_CELLS} more')
+    lines.append('Rendered:')
+    lines.extend(_Snapshot.parse(actual).text)
+    lines.append(f'(set {UPDATE_VARIABLE}=1 to update the snapshot)')
+    raise SnapshotMismatch('\n'.join(lines))
@@ -1,22 +1,141 @@
 #![allow(clippy::too_many_arguments)]
 #![allow(dead_code)]
 use std::{
+    borrow::Cow,
+    cell::RefCell,
     cmp::Ordering,
     collections::{BinaryHeap, HashMap, HashSet},
-    fmt::{Debug, Display},
+    fmt::{Debug, Display, Write},
+    hash::{Hash, Hasher},
     ops::{Add, AddAssign},
     str::FromStr,
+    sync::atomic::{AtomicBool, Ordering as AtomicOrdering},
 };
 
 use auto_ops::{impl_op_ex, impl_op_ex_commutative};
 use itertools::Itertools;
 use owo_colors::{AnsiColors, Effect, OwoColorize, Style};
 use pyo3::{
-    exceptions::{PyTypeError, PyValueError},
+    create_exception,
+    exceptions::{PyIndexError, PyRecursionError, PyTypeError, PyValueError},
     prelude::*,
-    types::{PyList, PyTuple},
+    sync::GILOnceCell,
+    types::{PyIterator, PyList, PyString, PyTuple},
+    BoundObject,
 };
 use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Whether coordinates passed to and from Python use screen coordinates (y increases downwards).
+static SCREEN_COORDINATES: AtomicBool = AtomicBool::new(false);
+
+/// The convention for the y-axis used by every `Point` and `BoundingBox` seen from Python.
+///
+/// Internally y always increases upwards. In screen coordinates the y-coordinate is negated
+/// whenever a point crosses into or out of Python, and objects which are positioned by a corner
+/// are positioned by their top-left corner rather than their bottom-left corner.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Coordinates {
+    #[default]
+    Math,
+    Screen,
+}
+impl FromStr for Coordinates {
+    type Err = PyErr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "math" => Ok(Coordinates::Math),
+            "screen" => Ok(Coordinates::Screen),
+            _ => Err(PyValueError::new_err(format!(
+                "Invalid coordinate system [{}]",
+                s
+            ))),
+        }
+    }
+}
+impl Display for Coordinates {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Coordinates::Math => "math",
+                Coordinates::Screen => "screen",
+            }
+        )
+    }
+}
+impl Coordinates {
+    fn current() -> Self {
+        if SCREEN_COORDINATES.load(AtomicOrdering::Relaxed) {
+            Coordinates::Screen
+        } else {
+            Coordinates::Math
+        }
+    }
+    /// Converts a y-coordinate between Python and the internal convention (in either direction).
+    fn y(y: isize) -> isize {
+        match Coordinates::current() {
+            Coordinates::Math => y,
+            Coordinates::Screen => -y,
+        }
+    }
+    /// The bottom-left corner of an object of the given height positioned at `position`.
+    fn bottom_left(position: Point, height: usize) -> Point {
+        match Coordinates::current() {
+            Coordinates::Math => position,
+            Coordinates::Screen => Point(position.0, position.1 - height as isize + 1),
+        }
+    }
+    /// The corner of a bounding box which objects positioned by a corner are positioned by.
+    fn corner(bbox: BoundingBox) -> Point {
+        match Coordinates::current() {
+            Coordinates::Math => Point(bbox.left, bbox.bottom),
+            Coordinates::Screen => Point(bbox.left, bbox.top),
+        }
+    }
+}
+
+/// Sets the coordinate system used by every Point and BoundingBox.
+///
+/// This should be set before any objects are created, since existing objects keep their positions
+/// in the coordinate system they were created in.
+///
+/// Parameters
+/// ----------
+/// system : {'math', 'screen'}
+///     In 'math' coordinates (the default), y increases upwards and objects such as Boxes are
+///     positioned by their bottom-left corner. In 'screen' coordinates, y increases downwards and
+///     they are positioned by their top-left corner. Directions like 'up' always refer to the
+///     rendered output.
+///
+/// Examples
+/// --------
+/// >>> from textdraw import Box, set_coordinates
+/// >>> set_coordinates('screen')
+/// >>> Box('A', (0, 0)).bbox
+/// BoundingBox(top=0, right=2, bottom=2, left=0)
+///
+#[pyfunction]
+fn set_coordinates(system: &str) -> PyResult<()> {
+    let system: Coordinates = system.parse()?;
+    SCREEN_COORDINATES.store(system == Coordinates::Screen, AtomicOrdering::Relaxed);
+    Ok(())
+}
+
+/// Gets the coordinate system used by every Point and BoundingBox.
+///
+/// Returns
+/// -------
+/// {'math', 'screen'}
+///     The current coordinate system.
+///
+#[pyfunction]
+fn get_coordinates() -> String {
+    Coordinates::current().to_string()
+}
 
 /// Represents a 2D point with integer coordinates.
 ///
@@ -42,16 +161,21 @@ use regex::Regex;
 /// >>> p.y
 /// 2
 /// >>> p + Point(3, 4)
-/// (4, 6)
+/// Point(4, 6)
+/// >>> p - (1, 1)
+/// Point(0, 1)
+/// >>> 2 * p
+/// Point(2, 4)
+/// >>> x, y = p
 ///
-#[pyclass]
-#[derive(Default, Clone, Copy, Eq, PartialEq, Hash)]
+#[pyclass(module = "textdraw")]
+#[derive(Default, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
 struct Point(isize, isize);
 #[pymethods]
 impl Point {
     #[new]
-    fn new(x: isize, y: isize) -> Self {
-        Self(x, y)
+    fn py_new(x: isize, y: isize) -> Self {
+        Self(x, Coordinates::y(y))
     }
     #[getter]
     fn x(&self) -> isize {
@@ -59,26 +183,54 @@ impl Point {
     }
     #[getter]
     fn y(&self) -> isize {
-        self.1
+        Coordinates::y(self.1)
     }
     fn __add__(&self, rhs: Bound<PyAny>) -> PyResult<Point> {
-        Ok(self + rhs.extract::<Point>()?)
+        Ok(self + Point::extract_bound(&rhs)?)
     }
     fn __radd__(&self, rhs: Bound<PyAny>) -> PyResult<Point> {
-        Ok(rhs.extract::<Point>()? + self)
+        Ok(Point::extract_bound(&rhs)? + self)
     }
     fn __sub__(&self, rhs: Bound<PyAny>) -> PyResult<Point> {
-        Ok(self - rhs.extract::<Point>()?)
+        Ok(self - Point::extract_bound(&rhs)?)
     }
     fn __rsub__(&self, rhs: Bound<PyAny>) -> PyResult<Point> {
-        Ok(rhs.extract::<Point>()? - self)
+        Ok(Point::extract_bound(&rhs)? - self)
+    }
+    fn __mul__(&self, rhs: isize) -> Point {
+        self * rhs
+    }
+    fn __rmul__(&self, lhs: isize) -> Point {
+        self * lhs
+    }
+    fn __neg__(&self) -> Point {
+        -self
+    }
+    fn __eq__(&self, other: Bound<PyAny>) -> bool {
+        Point::extract_bound(&other).is_ok_and(|other| other == *self)
+    }
+    /// Points hash like the equivalent `(x, y)` tuple, since they compare equal to it.
+    fn __hash__(&self, py: Python<'_>) -> PyResult<isize> {
+        PyTuple::new(py, [self.x(), self.y()])?.hash()
+    }
+    fn __iter__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyIterator>> {
+        PyTuple::new(py, [self.x(), self.y()])?.as_any().try_iter()
     }
     fn __repr__(&self) -> String {
-        self.to_string()
+        format!("Point({}, {})", self.x(), self.y())
     }
     fn __str__(&self) -> String {
         self.to_string()
     }
+    fn __reduce__<'py>(&self, py: Python<'py>) -> PyResult<(Bound<'py, PyAny>, (String,))> {
+        reduce(py, Record::Point(*self))
+    }
+    fn __copy__(&self) -> Self {
+        *self
+    }
+    fn __deepcopy__(&self, _memo: Bound<PyAny>) -> Self {
+        *self
+    }
     /// Calculates the midpoint between this point and another point.
     ///
     /// Parameters
@@ -96,17 +248,202 @@ impl Point {
     /// >>> p1 = Point(0, 0)
     /// >>> p2 = Point(2, 2)
     /// >>> p1.midpoint(p2)
-    /// (1, 1)
+    /// Point(1, 1)
     ///
     #[pyo3(name = "midpoint")]
     fn py_midpoint(&self, other: Bound<PyAny>) -> PyResult<Point> {
         Ok(self.midpoint(&Point::extract_bound(&other)?))
     }
+    /// Calculates the Manhattan (taxicab) distance to another point.
+    ///
+    /// Parameters
+    /// ----------
+    /// other : Point or tuple of ints
+    ///     The other point.
+    ///
+    /// Returns
+    /// -------
+    /// int
+    ///     The number of horizontal and vertical steps between the points.
+    ///
+    /// Examples
+    /// --------
+    /// >>> Point(0, 0).manhattan_distance((3, -4))
+    /// 7
+    ///
+    #[pyo3(name = "manhattan_distance")]
+    fn py_manhattan_distance(&self, other: Bound<PyAny>) -> PyResult<usize> {
+        Ok(self.manhattan_distance(&Point::extract_bound(&other)?))
+    }
+    /// Calculates the Chebyshev (chessboard) distance to another point.
+    ///
+    /// Parameters
+    /// ----------
+    /// other : Point or tuple of ints
+    ///     The other point.
+    ///
+    /// Returns
+    /// -------
+    /// int
+    ///     The number of steps between the points when diagonal steps are allowed.
+    ///
+    /// Examples
+    /// --------
+    /// >>> Point(0, 0).chebyshev_distance((3, -4))
+    /// 4
+    ///
+    #[pyo3(name = "chebyshev_distance")]
+    fn py_chebyshev_distance(&self, other: Bound<PyAny>) -> PyResult<usize> {
+        Ok(self.chebyshev_distance(&Point::extract_bound(&other)?))
+    }
+    /// Lists the points next to this point, clockwise from the one above it.
+    ///
+    /// Parameters
+    /// ----------
+    /// diagonal : bool, default=False
+    ///     If True, the four diagonal neighbors are included.
+    ///
+    /// Returns
+    /// -------
+    /// list of Point
+    ///     The neighboring points.
+    ///
+    /// Examples
+    /// --------
+    /// >>> Point(0, 0).neighbors()
+    /// [Point(0, 1), Point(1, 0), Point(0, -1), Point(-1, 0)]
+    ///
+    #[pyo3(signature = (diagonal = false))]
+    fn neighbors(&self, diagonal: bool) -> Vec<Point> {
+        let directions = Direction::all();
+        directions
+            .into_iter()
+            .flat_map(|direction| {
+                let next = directions[(direction.index() + 1) % 4];
+                let corner = direction.delta() + next.delta();
+                [
+                    Some(self + direction.delta()),
+                    diagonal.then(|| self + corner),
+                ]
+            })
+            .flatten()
+            .collect()
+    }
+    /// Finds the direction of another point in the same row or column.
+    ///
+    /// Parameters
+    /// ----------
+    /// other : Point or tuple of ints
+    ///     The other point.
+    ///
+    /// Returns
+    /// -------
+    /// {'up', 'right', 'down', 'left'} or None
+    ///     The direction to move in to reach the other point, or None if it is this point or is
+    ///     not in the same row or column.
+    ///
+    /// Examples
+    /// --------
+    /// >>> Point(0, 0).direction_to((0, 5))
+    /// 'up'
+    /// >>> Point(0, 0).direction_to((1, 5)) is None
+    /// True
+    ///
+    #[pyo3(name = "direction_to")]
+    fn py_direction_to(&self, other: Bound<PyAny>) -> PyResult<Option<String>> {
+        Ok(self
+            .direction_to(&Point::extract_bound(&other)?)
+            .map(|direction| direction.to_string()))
+    }
+    /// Moves a point a number of steps in a direction.
+    ///
+    /// Parameters
+    /// ----------
+    /// direction : {'up', 'right', 'down', 'left'}
+    ///     The direction to move in.
+    /// distance : int, default=1
+    ///     The number of steps to take.
+    ///
+    /// Returns
+    /// -------
+    /// Point
+    ///     The moved point.
+    ///
+    /// Examples
+    /// --------
+    /// >>> Point(0, 0).step('right', 3)
+    /// Point(3, 0)
+    ///
+    #[pyo3(signature = (direction, distance = 1))]
+    fn step(&self, direction: &str, distance: isize) -> PyResult<Point> {
+        let direction: Direction = direction.parse()?;
+        Ok(self + direction.delta() * distance)
+    }
+    /// Lists the points on the straight line from this point to another point.
+    ///
+    /// The line is drawn with Bresenham's algorithm, so each step moves to one of the eight
+    /// neighboring cells.
+    ///
+    /// Parameters
+    /// ----------
+    /// other : Point or tuple of ints
+    ///     The end of the line.
+    ///
+    /// Returns
+    /// -------
+    /// list of Point
+    ///     The points on the line, including both ends.
+    ///
+    /// Examples
+    /// --------
+    /// >>> Point(0, 0).line_to((4, 2))
+    /// [Point(0, 0), Point(1, 1), Point(2, 1), Point(3, 2), Point(4, 2)]
+    ///
+    #[pyo3(name = "line_to")]
+    fn py_line_to(&self, other: Bound<PyAny>) -> PyResult<Vec<Point>> {
+        Ok(self.line_to(&Point::extract_bound(&other)?))
+    }
 }
 impl Point {
     fn midpoint(&self, other: &Point) -> Point {
         Point((self.0 + other.0) / 2, (self.1 + other.1) / 2)
     }
+    fn manhattan_distance(&self, other: &Point) -> usize {
+        self.0.abs_diff(other.0) + self.1.abs_diff(other.1)
+    }
+    fn chebyshev_distance(&self, other: &Point) -> usize {
+        self.0.abs_diff(other.0).max(self.1.abs_diff(other.1))
+    }
+    /// The direction of `other` if it is a different point in the same row or column.
+    fn direction_to(&self, other: &Point) -> Option<Direction> {
+        let delta = other - self;
+        if (delta.0 == 0) == (delta.1 == 0) {
+            return None;
+        }
+        Direction::between(&Point(0, 0), &Point(delta.0.signum(), delta.1.signum()))
+    }
+    /// The cells on the line from this point to `other` (inclusive), using Bresenham's algorithm.
+    fn line_to(&self, other: &Point) -> Vec<Point> {
+        let dx = (other.0 - self.0).abs();
+        let dy = -(other.1 - self.1).abs();
+        let step = Point((other.0 - self.0).signum(), (other.1 - self.1).signum());
+        let mut error = dx + dy;
+        let mut current = *self;
+        let mut points = vec![current];
+        while current != *other {
+            let doubled = 2 * error;
+            if doubled >= dy {
+                error += dy;
+                current.0 += step.0;
+            }
+            if doubled <= dx {
+                error += dx;
+                current.1 += step.1;
+            }
+            points.push(current);
+        }
+        points
+    }
 }
 impl Debug for Point {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -114,14 +451,18 @@ impl Debug for Point {
     }
 }
 impl Display for Point {
+    /// Formats the point as it is seen from Python.
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?}", self)
+        write!(f, "({}, {})", self.0, Coordinates::y(self.1))
     }
 }
 impl Point {
+    fn new(x: isize, y: isize) -> Self {
+        Self(x, y)
+    }
     fn extract_bound<'py>(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
-        if let Ok(tup) = ob.extract::<(isize, isize)>() {
-            Ok(tup.into())
+        if let Ok((x, y)) = ob.extract::<(isize, isize)>() {
+            Ok(Point::py_new(x, y))
         } else {
             Ok(ob.extract::<Point>()?)
         }
@@ -143,11 +484,19 @@ impl_op_ex!(-= |a: &mut Point, b: &Point| { a.0 -= b.0; a.1 -= b.1; });
 #[rustfmt::skip]
 impl_op_ex_commutative!(+ |a: &Point, b: &(isize, isize)| -> Point { Point(a.0 + b.0, a.1 + b.1) });
 #[rustfmt::skip]
-impl_op_ex_commutative!(- |a: &Point, b: &(isize, isize)| -> Point { Point(a.0 + b.0, a.1 + b.1) });
+impl_op_ex!(- |a: &Point, b: &(isize, isize)| -> Point { Point(a.0 - b.0, a.1 - b.1) });
+#[rustfmt::skip]
+impl_op_ex!(- |a: &(isize, isize), b: &Point| -> Point { Point(a.0 - b.0, a.1 - b.1) });
 #[rustfmt::skip]
 impl_op_ex!(+= |a: &mut Point, b: &(isize, isize)| { a.0 += b.0; a.1 += b.1; });
 #[rustfmt::skip]
 impl_op_ex!(-= |a: &mut Point, b: &(isize, isize)| { a.0 -= b.0; a.1 -= b.1; });
+#[rustfmt::skip]
+impl_op_ex_commutative!(* |a: &Point, b: &isize| -> Point { Point(a.0 * b, a.1 * b) });
+#[rustfmt::skip]
+impl_op_ex!(*= |a: &mut Point, b: &isize| { a.0 *= b; a.1 *= b; });
+#[rustfmt::skip]
+impl_op_ex!(- |a: &Point| -> Point { Point(-a.0, -a.1) });
 
 /// Represents a rectangular bounding box with integer coordinates.
 ///
@@ -173,11 +522,12 @@ impl_op_ex!(-= |a: &mut Point, b: &(isize, isize)| { a.0 -= b.0; a.1 -= b.1; });
 /// left : int
 ///     The left x-coordinate.
 /// width : int
-///     The width of the bounding box.
+///     The number of columns the bounding box covers, or 0 if it is empty.
 /// height : int
-///     The height of the bounding box.
+///     The number of rows the bounding box covers, or 0 if it is empty.
 /// center : Point
-///     The center point of the bounding box.
+///     The center point of the bounding box. This and the other points raise a ValueError if the
+///     box is empty.
 /// top_left : Point
 ///     The top-left corner point.
 /// top_center : Point
@@ -199,34 +549,234 @@ impl_op_ex!(-= |a: &mut Point, b: &(isize, isize)| { a.0 -= b.0; a.1 -= b.1; });
 /// --------
 /// >>> bbox = BoundingBox(top=10, right=20, bottom=0, left=0)
 /// >>> bbox.width
-/// 20
+/// 21
 /// >>> bbox.height
-/// 10
+/// 11
 /// >>> bbox.center
-/// (10, 5)
+/// Point(10, 5)
+/// >>> BoundingBox.wrap([]).is_empty
+/// True
 ///
-#[pyclass]
-#[derive(Default, Copy, Clone, Debug)]
+#[pyclass(module = "textdraw")]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
 struct BoundingBox {
-    #[pyo3(get, set)]
     top: isize,
     #[pyo3(get, set)]
     right: isize,
-    #[pyo3(get, set)]
     bottom: isize,
     #[pyo3(get, set)]
     left: isize,
 }
+/// Bounding boxes are equal if they cover the same cells, so all empty boxes are equal.
+impl PartialEq for BoundingBox {
+    fn eq(&self, other: &Self) -> bool {
+        (self.is_empty() && other.is_empty())
+            || (self.top == other.top
+                && self.right == other.right
+                && self.bottom == other.bottom
+                && self.left == other.left)
+    }
+}
+impl Eq for BoundingBox {}
+/// The default bounding box is empty.
+impl Default for BoundingBox {
+    fn default() -> Self {
+        Self::EMPTY
+    }
+}
 impl BoundingBox {
+    /// A bounding box with no cells. Any box whose bottom is above its top or whose left is right
+    /// of its right is also empty, but this is the one produced by operations on boxes.
+    const EMPTY: Self = Self {
+        top: -1,
+        right: -1,
+        bottom: 0,
+        left: 0,
+    };
+    fn new(top: isize, right: isize, bottom: isize, left: isize) -> Self {
+        Self {
+            top,
+            right,
+            bottom,
+            left,
+        }
+    }
+    fn is_empty(&self) -> bool {
+        self.left > self.right || self.bottom > self.top
+    }
+    /// The number of columns the box covers.
+    fn width(&self) -> usize {
+        if self.is_empty() {
+            0
+        } else {
+            (self.right - self.left + 1) as usize
+        }
+    }
+    /// The number of rows the box covers.
+    fn height(&self) -> usize {
+        if self.is_empty() {
+            0
+        } else {
+            (self.top - self.bottom + 1) as usize
+        }
+    }
+    fn center(&self) -> Point {
+        Point((self.left + self.right) / 2, (self.bottom + self.top) / 2)
+    }
+    /// Empty boxes have no cells, so they have no corners or centre either.
+    fn non_empty(&self) -> PyResult<&Self> {
+        if self.is_empty() {
+            Err(PyValueError::new_err("An empty BoundingBox has no points"))
+        } else {
+            Ok(self)
+        }
+    }
+    /// The smallest box containing both boxes. Empty boxes add nothing.
+    fn union(&self, other: &BoundingBox) -> Self {
+        if self.is_empty() {
+            *other
+        } else if other.is_empty() {
+            *self
+        } else {
+            BoundingBox::new(
+                self.top.max(other.top),
+                self.right.max(other.right),
+                self.bottom.min(other.bottom),
+                self.left.min(other.left),
+            )
+        }
+    }
+    fn extract_bound(ob: &Bound<'_, PyAny>) -> PyResult<Self> {
+        if let Ok(boundingbox) = ob.extract::<BoundingBox>() {
+            Ok(boundingbox)
+        } else if let Ok((top, right, bottom, left)) = ob.extract::<(isize, isize, isize, isize)>()
+        {
+            Ok(BoundingBox::py_new(top, right, bottom, left))
+        } else {
+            Err(PyValueError::new_err(
+                "bbox must be a BoundingBox or a tuple[int, int, int, int]",
+            ))
+        }
+    }
+    /// A window of at most `columns` by `rows` cells into this box, scrolled right and down from
+    /// the top-left corner by `scroll` (clamped so the window stays inside the box).
+    fn viewport(&self, columns: usize, rows: usize, scroll: (usize, usize)) -> Self {
+        if self.is_empty() {
+            return Self::EMPTY;
+        }
+        let full_width = (self.right - self.left + 1).max(0);
+        let full_height = (self.top - self.bottom + 1).max(0);
+        let width = (columns as isize).min(full_width);
+        let height = (rows as isize).min(full_height);
+        let left = self.left + (scroll.0 as isize).min(full_width - width);
+        let top = self.top - (scroll.1 as isize).min(full_height - height);
+        BoundingBox::new(top, left + width - 1, top - height + 1, left)
+    }
     fn contains_point(&self, p: &Point) -> bool {
         p.0 >= self.left && p.0 <= self.right && p.1 >= self.bottom && p.1 <= self.top
     }
+    /// The bounding box grown by `margin` cells on every side.
+    fn expanded(&self, margin: usize) -> Self {
+        self.inflated(margin as isize)
+    }
+    /// The bounding box grown by `margin` cells on every side, or shrunk if `margin` is negative.
+    /// Empty boxes stay empty, and boxes shrunk past their center become empty.
+    fn inflated(&self, margin: isize) -> Self {
+        if self.is_empty() {
+            return Self::EMPTY;
+        }
+        let bbox = Self {
+            top: self.top + margin,
+            right: self.right + margin,
+            bottom: self.bottom - margin,
+            left: self.left - margin,
+        };
+        if bbox.is_empty() {
+            Self::EMPTY
+        } else {
+            bbox
+        }
+    }
+    /// Whether every cell of `bbox` is in this box (always true if `bbox` is empty).
     fn contains_bounding_box(&self, bbox: BoundingBox) -> bool {
-        bbox.left >= self.left
-            && bbox.right <= self.right
-            && bbox.bottom >= self.bottom
-            && bbox.top <= self.top
+        bbox.is_empty()
+            || bbox.left >= self.left
+                && bbox.right <= self.right
+                && bbox.bottom >= self.bottom
+                && bbox.top <= self.top
+    }
+    /// The cells shared by both boxes, which is empty if they do not overlap.
+    fn intersection(&self, other: &BoundingBox) -> Self {
+        let bbox = BoundingBox::new(
+            self.top.min(other.top),
+            self.right.min(other.right),
+            self.bottom.max(other.bottom),
+            self.left.max(other.left),
+        );
+        if bbox.is_empty() {
+            Self::EMPTY
+        } else {
+            bbox
+        }
+    }
+    /// The cells of this box which are not in `other`, as at most four disjoint boxes.
+    fn difference(&self, other: &BoundingBox) -> Vec<Self> {
+        if self.is_empty() {
+            return Vec::new();
+        }
+        let overlap = self.intersection(other);
+        if overlap.is_empty() {
+            return vec![*self];
+        }
+        [
+            BoundingBox::new(self.top, self.right, overlap.top + 1, self.left),
+            BoundingBox::new(overlap.bottom - 1, self.right, self.bottom, self.left),
+            BoundingBox::new(overlap.top, overlap.left - 1, overlap.bottom, self.left),
+            BoundingBox::new(overlap.top, self.right, overlap.bottom, overlap.right + 1),
+        ]
+        .into_iter()
+        .filter(|bbox| !bbox.is_empty())
+        .collect()
+    }
+    /// The closest cell of the box to `p`, or `None` if the box is empty.
+    fn clamp(&self, p: &Point) -> Option<Point> {
+        (!self.is_empty()).then(|| {
+            Point(
+                p.0.clamp(self.left, self.right),
+                p.1.clamp(self.bottom, self.top),
+            )
+        })
+    }
+    /// The number of horizontal and vertical steps between the closest cells of two boxes, or
+    /// `None` if either box is empty.
+    fn distance(&self, other: &BoundingBox) -> Option<usize> {
+        if self.is_empty() || other.is_empty() {
+            return None;
+        }
+        let dx = (other.left - self.right)
+            .max(self.left - other.right)
+            .max(0);
+        let dy = (other.bottom - self.top)
+            .max(self.bottom - other.top)
+            .max(0);
+        Some((dx + dy) as usize)
+    }
+    /// Every cell of the box in reading order, from the top row down and left to right.
+    fn cells(&self) -> impl Iterator<Item = Point> {
+        let BoundingBox {
+            top,
+            right,
+            bottom,
+            left,
+        } = *self;
+        (bottom..=top)
+            .rev()
+            .flat_map(move |y| (left..=right).map(move |x| Point(x, y)))
     }
+    /// Draws the border of the bounding box, filling the inside unless it is `transparent`.
+    ///
+    /// `sides` toggles the (top, right, bottom, left) rules. A rule whose neighboring rule is
+    /// hidden runs straight through the shared corner instead of turning.
     fn as_map(
         &self,
         border_style: &TextStyle,
@@ -234,83 +784,49 @@ impl BoundingBox {
         line_style: Option<LineStyle>,
         weight: Option<usize>,
         transparent: bool,
+        sides: (bool, bool, bool, bool),
     ) -> HashMap<Point, Pixel> {
+        let (top, right, bottom, left) = sides;
         let mut pixels = HashMap::default();
-        for i in self.left + 1..self.right {
-            pixels.insert(
-                Point(i, self.top),
-                Pixel {
-                    character: line_style.map_or(' ', |ls| ls.get_char((false, true, false, true))),
-                    position: Point(i, self.top),
-                    style: border_style.clone(),
-                    weight,
-                },
-            );
+        let mut insert = |position: Point, links: (bool, bool, bool, bool)| {
+            let character = if links == (false, false, false, false) {
+                ' '
+            } else {
+                line_style.map_or(' ', |ls| ls.get_char(links))
+            };
             pixels.insert(
-                Point(i, self.bottom),
+                position,
                 Pixel {
-                    character: line_style.map_or(' ', |ls| ls.get_char((false, true, false, true))),
-                    position: Point(i, self.bottom),
+                    character,
+                    position,
                     style: border_style.clone(),
                     weight,
                 },
             );
+        };
+        for i in self.left + 1..self.right {
+            insert(Point(i, self.top), (false, top, false, top));
+            insert(Point(i, self.bottom), (false, bottom, false, bottom));
         }
         for j in self.bottom + 1..self.top {
-            pixels.insert(
-                Point(self.left, j),
-                Pixel {
-                    character: line_style.map_or(' ', |ls| ls.get_char((true, false, true, false))),
-                    position: Point(self.left, j),
-                    style: border_style.clone(),
-                    weight,
-                },
-            );
-            pixels.insert(
-                Point(self.right, j),
-                Pixel {
-                    character: line_style.map_or(' ', |ls| ls.get_char((true, false, true, false))),
-                    position: Point(self.right, j),
-                    style: border_style.clone(),
-                    weight,
-                },
-            );
+            insert(Point(self.left, j), (left, false, left, false));
+            insert(Point(self.right, j), (right, false, right, false));
         }
-        pixels.insert(
+        insert(
             Point(self.right, self.top),
-            Pixel {
-                character: line_style.map_or(' ', |ls| ls.get_char((false, false, true, true))),
-                position: Point(self.right, self.top),
-                style: border_style.clone(),
-                weight,
-            },
+            (right && !top, top && !right, right, top),
         );
-        pixels.insert(
+        insert(
             Point(self.right, self.bottom),
-            Pixel {
-                character: line_style.map_or(' ', |ls| ls.get_char((true, false, false, true))),
-                position: Point(self.right, self.bottom),
-                style: border_style.clone(),
-                weight,
-            },
+            (right, bottom && !right, right && !bottom, bottom),
         );
-        pixels.insert(
+        insert(
             Point(self.left, self.top),
-            Pixel {
-                character: line_style.map_or(' ', |ls| ls.get_char((false, true, true, false))),
-                position: Point(self.left, self.top),
-                style: border_style.clone(),
-                weight,
-            },
+            (left && !top, top, left, top && !left),
         );
-        pixels.insert(
+        insert(
             Point(self.left, self.bottom),
-            Pixel {
-                character: line_style.map_or(' ', |ls| ls.get_char((true, true, false, false))),
-                position: Point(self.left, self.bottom),
-                style: border_style.clone(),
-                weight,
-            },
+            (left, bottom, left && !bottom, bottom && !left),
         );
         if !transparent {
             for i in self.left + 1..self.right {
@@ -330,6 +846,9 @@ impl BoundingBox {
         pixels
     }
     fn duplicate_shifted(&self, position: Point) -> Self {
+        if self.is_empty() {
+            return Self::EMPTY;
+        }
         Self {
             top: self.top + position.1,
             right: self.right + position.0,
@@ -341,20 +860,31 @@ impl BoundingBox {
 #[pymethods]
 impl BoundingBox {
     #[new]
-    fn new(top: isize, right: isize, bottom: isize, left: isize) -> Self {
-        Self {
-            top,
-            right,
-            bottom,
-            left,
-        }
+    fn py_new(top: isize, right: isize, bottom: isize, left: isize) -> Self {
+        Self::new(Coordinates::y(top), right, Coordinates::y(bottom), left)
     }
-    /// Creates a BoundingBox that wraps around a collection of objects.
-    ///
-    /// Parameters
-    /// ----------
-    /// objs : list
-    ///     A list of objects to fill the BoundingBox. Each object must be an instance of TextPath, Box, Pixel, or PixelGroup.
+    #[getter]
+    fn get_top(&self) -> isize {
+        Coordinates::y(self.top)
+    }
+    #[setter]
+    fn set_top(&mut self, top: isize) {
+        self.top = Coordinates::y(top);
+    }
+    #[getter]
+    fn get_bottom(&self) -> isize {
+        Coordinates::y(self.bottom)
+    }
+    #[setter]
+    fn set_bottom(&mut self, bottom: isize) {
+        self.bottom = Coordinates::y(bottom);
+    }
+    /// Creates a BoundingBox that wraps around a collection of objects.
+    ///
+    /// Parameters
+    /// ----------
+    /// objs : list
+    ///     A list of objects to fill the BoundingBox. Each object must be an instance of TextPath, Box, Table, HStack, VStack, Grid, Pixel, or PixelGroup.
     ///
     /// Returns
     /// -------
@@ -367,12 +897,37 @@ impl BoundingBox {
     /// BoundingBox(top=10, right=10, bottom=0, left=0)
     /// >>> BoundingBox.wrap(BoundingBox(0,0,0,0), BoundingBox(10,10,10,10))
     /// BoundingBox(top=10, right=10, bottom=0, left=0)
+    /// >>> BoundingBox.wrap([])
+    /// BoundingBox.empty()
     ///
     #[staticmethod]
     fn wrap(objs: Bound<'_, PyAny>) -> PyResult<BoundingBox> {
-        let map = objs_to_map(&objs)?;
-        let bbox = map_to_bounding_box(&map);
-        Ok(bbox)
+        Ok(pixels_to_bounding_box(&objs_to_pixels(&objs)?))
+    }
+    /// Creates a BoundingBox with no cells.
+    ///
+    /// An empty box contains no points, has a width and height of 0, and leaves other boxes
+    /// unchanged when added to them. Boxes whose bottom is above their top or whose left is right
+    /// of their right are also empty.
+    ///
+    /// Returns
+    /// -------
+    /// BoundingBox
+    ///     An empty bounding box.
+    ///
+    /// Examples
+    /// --------
+    /// >>> BoundingBox.empty() + BoundingBox(10, 10, 0, 0)
+    /// BoundingBox(top=10, right=10, bottom=0, left=0)
+    ///
+    #[staticmethod]
+    fn empty() -> BoundingBox {
+        Self::EMPTY
+    }
+    #[getter]
+    #[pyo3(name = "is_empty")]
+    fn get_is_empty(&self) -> bool {
+        self.is_empty()
     }
     fn __contains__(&self, other: Bound<PyAny>) -> PyResult<bool> {
         if let Ok(point) = other.extract::<Point>() {
@@ -397,54 +952,98 @@ impl BoundingBox {
         }
     }
     fn __str__(&self) -> String {
+        if self.is_empty() {
+            return "BoundingBox.empty()".to_string();
+        }
         format!(
             "BoundingBox(top={}, right={}, bottom={}, left={})",
-            self.top, self.right, self.bottom, self.left
+            Coordinates::y(self.top),
+            self.right,
+            Coordinates::y(self.bottom),
+            self.left
         )
     }
-    #[getter]
-    fn width(&self) -> usize {
-        (self.right - self.left) as usize
-    }
-    #[getter]
-    fn height(&self) -> usize {
-        (self.top - self.bottom) as usize
-    }
-    #[getter]
-    fn center(&self) -> Point {
-        Point((self.left + self.right) / 2, (self.bottom + self.top) / 2)
+    fn __repr__(&self) -> String {
+        self.__str__()
+    }
+    fn __eq__(&self, other: Bound<PyAny>) -> bool {
+        BoundingBox::extract_bound(&other).is_ok_and(|other| other == *self)
+    }
+    /// Bounding boxes hash like the equivalent `(top, right, bottom, left)` tuple. All empty boxes
+    /// hash alike, since they are all equal.
+    fn __hash__(&self, py: Python<'_>) -> PyResult<isize> {
+        let bbox = if self.is_empty() { Self::EMPTY } else { *self };
+        PyTuple::new(
+            py,
+            [
+                Coordinates::y(bbox.top),
+                bbox.right,
+                Coordinates::y(bbox.bottom),
+                bbox.left,
+            ],
+        )?
+        .hash()
+    }
+    fn __reduce__<'py>(&self, py: Python<'py>) -> PyResult<(Bound<'py, PyAny>, (String,))> {
+        reduce(py, Record::BoundingBox(*self))
+    }
+    fn __copy__(&self) -> Self {
+        *self
+    }
+    fn __deepcopy__(&self, _memo: Bound<PyAny>) -> Self {
+        *self
+    }
+    #[getter(width)]
+    fn py_width(&self) -> usize {
+        self.width()
+    }
+    #[getter(height)]
+    fn py_height(&self) -> usize {
+        self.height()
+    }
+    #[getter(center)]
+    fn py_center(&self) -> PyResult<Point> {
+        Ok(self.non_empty()?.center())
     }
     #[getter]
-    fn top_left(&self) -> Point {
-        Point(self.left, self.top)
+    fn top_left(&self) -> PyResult<Point> {
+        let bbox = self.non_empty()?;
+        Ok(Point(bbox.left, bbox.top))
     }
     #[getter]
-    fn top_center(&self) -> Point {
-        Point((self.left + self.right) / 2, self.top)
+    fn top_center(&self) -> PyResult<Point> {
+        let bbox = self.non_empty()?;
+        Ok(Point((bbox.left + bbox.right) / 2, bbox.top))
     }
     #[getter]
-    fn top_right(&self) -> Point {
-        Point(self.right, self.top)
+    fn top_right(&self) -> PyResult<Point> {
+        let bbox = self.non_empty()?;
+        Ok(Point(bbox.right, bbox.top))
     }
     #[getter]
-    fn bottom_left(&self) -> Point {
-        Point(self.left, self.bottom)
+    fn bottom_left(&self) -> PyResult<Point> {
+        let bbox = self.non_empty()?;
+        Ok(Point(bbox.left, bbox.bottom))
     }
     #[getter]
-    fn bottom_center(&self) -> Point {
-        Point((self.left + self.right) / 2, self.bottom)
+    fn bottom_center(&self) -> PyResult<Point> {
+        let bbox = self.non_empty()?;
+        Ok(Point((bbox.left + bbox.right) / 2, bbox.bottom))
     }
     #[getter]
-    fn bottom_right(&self) -> Point {
-        Point(self.right, self.bottom)
+    fn bottom_right(&self) -> PyResult<Point> {
+        let bbox = self.non_empty()?;
+        Ok(Point(bbox.right, bbox.bottom))
     }
     #[getter]
-    fn center_left(&self) -> Point {
-        Point(self.left, (self.bottom + self.top) / 2)
+    fn center_left(&self) -> PyResult<Point> {
+        let bbox = self.non_empty()?;
+        Ok(Point(bbox.left, (bbox.bottom + bbox.top) / 2))
     }
     #[getter]
-    fn center_right(&self) -> Point {
-        Point(self.right, (self.bottom + self.top) / 2)
+    fn center_right(&self) -> PyResult<Point> {
+        let bbox = self.non_empty()?;
+        Ok(Point(bbox.right, (bbox.bottom + bbox.top) / 2))
     }
     /// Duplicate a BoundingBox shifted by the given delta.
     ///
@@ -462,33 +1061,134 @@ impl BoundingBox {
     fn py_duplicate_shifted(&self, delta: Bound<PyAny>) -> PyResult<Self> {
         Ok(self.duplicate_shifted(Point::extract_bound(&delta)?))
     }
+    /// Finds the overlap between two BoundingBoxes.
+    ///
+    /// Parameters
+    /// ----------
+    /// other : BoundingBox or tuple of ints
+    ///     The other bounding box.
+    ///
+    /// Returns
+    /// -------
+    /// BoundingBox
+    ///     The cells shared by both boxes, which is empty if they do not overlap.
+    ///
+    /// Examples
+    /// --------
+    /// >>> BoundingBox(10, 10, 0, 0).intersection(BoundingBox(15, 15, 5, 5))
+    /// BoundingBox(top=10, right=10, bottom=5, left=5)
+    /// >>> BoundingBox(10, 10, 0, 0).intersection(BoundingBox(15, 15, 11, 11))
+    /// BoundingBox.empty()
+    ///
+    #[pyo3(name = "intersection")]
+    fn py_intersection(&self, other: Bound<PyAny>) -> PyResult<Self> {
+        Ok(self.intersection(&BoundingBox::extract_bound(&other)?))
+    }
+    /// Checks whether two BoundingBoxes share any cells.
+    ///
+    /// Parameters
+    /// ----------
+    /// other : BoundingBox or tuple of ints
+    ///     The other bounding box.
+    ///
+    /// Returns
+    /// -------
+    /// bool
+    ///     True if the boxes overlap.
+    ///
+    fn intersects(&self, other: Bound<PyAny>) -> PyResult<bool> {
+        Ok(!self
+            .intersection(&BoundingBox::extract_bound(&other)?)
+            .is_empty())
+    }
+    /// Grows (or shrinks) a BoundingBox on every side.
+    ///
+    /// Parameters
+    /// ----------
+    /// margin : int
+    ///     The number of cells to add to each side. Negative margins shrink the box.
+    ///
+    /// Returns
+    /// -------
+    /// BoundingBox
+    ///     The resized bounding box, which is empty if it was shrunk past its center.
+    ///
+    /// Examples
+    /// --------
+    /// >>> BoundingBox(10, 10, 0, 0).expand(2)
+    /// BoundingBox(top=12, right=12, bottom=-2, left=-2)
+    /// >>> BoundingBox(10, 10, 0, 0).expand(-6)
+    /// BoundingBox.empty()
+    ///
+    fn expand(&self, margin: isize) -> Self {
+        self.inflated(margin)
+    }
+    /// Finds the cell of a BoundingBox closest to a point.
+    ///
+    /// Parameters
+    /// ----------
+    /// point : Point or tuple of ints
+    ///     The point to clamp.
+    ///
+    /// Returns
+    /// -------
+    /// Point
+    ///     The point itself if it is inside the box, otherwise the nearest point on the edge.
+    ///
+    /// Raises
+    /// ------
+    /// ValueError
+    ///     If the box is empty.
+    ///
+    /// Examples
+    /// --------
+    /// >>> BoundingBox(10, 10, 0, 0).clamp((15, 5))
+    /// Point(10, 5)
+    ///
+    #[pyo3(name = "clamp")]
+    fn py_clamp(&self, point: Bound<PyAny>) -> PyResult<Point> {
+        self.clamp(&Point::extract_bound(&point)?)
+            .ok_or_else(|| PyValueError::new_err("Cannot clamp to an empty BoundingBox"))
+    }
+    /// Counts the steps between the closest cells of two BoundingBoxes.
+    ///
+    /// Parameters
+    /// ----------
+    /// other : BoundingBox or tuple of ints
+    ///     The other bounding box.
+    ///
+    /// Returns
+    /// -------
+    /// int
+    ///     The Manhattan distance between the boxes, or 0 if they overlap.
+    ///
+    /// Raises
+    /// ------
+    /// ValueError
+    ///     If either box is empty.
+    ///
+    #[pyo3(name = "distance")]
+    fn py_distance(&self, other: Bound<PyAny>) -> PyResult<usize> {
+        self.distance(&BoundingBox::extract_bound(&other)?)
+            .ok_or_else(|| {
+                PyValueError::new_err("Cannot measure the distance to an empty BoundingBox")
+            })
+    }
+    /// Iterates over every cell in the box, row by row from the top-left corner.
+    fn __iter__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyIterator>> {
+        PyList::new(py, self.cells().collect::<Vec<_>>())?
+            .as_any()
+            .try_iter()
+    }
 }
 #[rustfmt::skip]
-impl_op_ex!(+ |a: &BoundingBox, b: &BoundingBox| -> BoundingBox { BoundingBox { top: a.top.max(b.top), right: a.right.max(b.right), bottom: a.bottom.min(b.bottom), left: a.left.min(b.left) } });
+impl_op_ex!(+ |a: &BoundingBox, b: &BoundingBox| -> BoundingBox { a.union(b) });
 #[rustfmt::skip]
-impl_op_ex!(+= |a: &mut BoundingBox, b: &BoundingBox| {
-        a.top =a.top.max(b.top);
-        a.right =a.right.max(b.right);
-        a.bottom =a.bottom.min(b.bottom);
-        a.left =a.left.min(b.left);
-
-});
+impl_op_ex!(+= |a: &mut BoundingBox, b: &BoundingBox| { *a = a.union(b); });
 #[rustfmt::skip]
-impl_op_ex_commutative!(+ |a: &BoundingBox, b: &Point| -> BoundingBox {
-        BoundingBox {
-            top:a.top.max(b.1),
-            right:a.right.max(b.0),
-            bottom:a.bottom.min(b.1),
-            left:a.left.min(b.0),
-        }
-});
+impl_op_ex_commutative!(+ |a: &BoundingBox, b: &Point| -> BoundingBox { a.union(&BoundingBox::new(b.1, b.0, b.1, b.0)) });
 #[rustfmt::skip]
-impl_op_ex!(+= |a: &mut BoundingBox, b: &Point| {
-        a.top =a.top.max(b.1);
-        a.right =a.right.max(b.0);
-        a.bottom =a.bottom.min(b.1);
-        a.left =a.left.min(b.0);
-});
+impl_op_ex!(+= |a: &mut BoundingBox, b: &Point| { *a = a.union(&BoundingBox::new(b.1, b.0, b.1, b.0)); });
 impl From<(isize, isize, isize, isize)> for BoundingBox {
     fn from(value: (isize, isize, isize, isize)) -> Self {
         Self {
@@ -505,23 +1205,287 @@ impl From<BoundingBox> for (isize, isize, isize, isize) {
     }
 }
 
+/// A set of cells made up of a union of rectangles.
+///
+/// Regions can be used as masks, either as `barriers` for pathfinding or as the `clip` region of
+/// `render`.
+///
+/// Parameters
+/// ----------
+/// rects : list of BoundingBox or tuple of ints, optional
+///     The rectangles which make up the region.
+///
+/// Attributes
+/// ----------
+/// rects : list of BoundingBox
+///     The rectangles which make up the region (these may overlap).
+/// bbox : BoundingBox
+///     The smallest bounding box containing the region (empty if the region is empty).
+///
+/// Examples
+/// --------
+/// >>> region = Region([BoundingBox(2, 2, 0, 0)]) | BoundingBox(1, 4, 1, 0)
+/// >>> len(region)
+/// 11
+/// >>> (4, 1) in region
+/// True
+/// >>> region - BoundingBox(2, 2, 0, 0)
+/// Region([BoundingBox(top=1, right=4, bottom=1, left=3)])
+///
+#[pyclass(module = "textdraw")]
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+struct Region {
+    rects: Vec<BoundingBox>,
+}
+impl Region {
+    fn extract_bound(ob: &Bound<'_, PyAny>) -> PyResult<Self> {
+        if let Ok(region) = ob.extract::<Region>() {
+            Ok(region)
+        } else if let Ok(bbox) = BoundingBox::extract_bound(ob) {
+            Ok(Region { rects: vec![bbox] })
+        } else {
+            Err(PyTypeError::new_err(
+                "Expected either a Region or a BoundingBox",
+            ))
+        }
+    }
+    fn contains_point(&self, p: &Point) -> bool {
+        self.rects.iter().any(|rect| rect.contains_point(p))
+    }
+    fn bbox(&self) -> BoundingBox {
+        self.rects.iter().fold(BoundingBox::EMPTY, |a, b| a + b)
+    }
+    /// Every cell of the region once, in reading order.
+    fn cells(&self) -> Vec<Point> {
+        let mut cells: Vec<Point> = self
+            .rects
+            .iter()
+            .flat_map(BoundingBox::cells)
+            .collect::<HashSet<Point>>()
+            .into_iter()
+            .collect();
+        cells.sort_by_key(|p| (-p.1, p.0));
+        cells
+    }
+    fn union(&self, other: &Region) -> Region {
+        Region {
+            rects: self.rects.iter().chain(&other.rects).copied().collect(),
+        }
+    }
+    fn intersection(&self, other: &Region) -> Region {
+        Region {
+            rects: self
+                .rects
+                .iter()
+                .flat_map(|a| other.rects.iter().map(|b| a.intersection(b)))
+                .filter(|rect| !rect.is_empty())
+                .collect(),
+        }
+    }
+    fn difference(&self, other: &Region) -> Region {
+        Region {
+            rects: other.rects.iter().fold(self.rects.clone(), |rects, b| {
+                rects.iter().flat_map(|a| a.difference(b)).collect()
+            }),
+        }
+    }
+}
+#[pymethods]
+impl Region {
+    #[new]
+    #[pyo3(signature = (rects = None))]
+    fn py_new(rects: Option<Vec<Bound<PyAny>>>) -> PyResult<Self> {
+        Ok(Region {
+            rects: rects
+                .unwrap_or_default()
+                .iter()
+                .map(BoundingBox::extract_bound)
+                .collect::<PyResult<_>>()?,
+        })
+    }
+    #[getter]
+    fn get_rects(&self) -> Vec<BoundingBox> {
+        self.rects.clone()
+    }
+    #[getter]
+    #[pyo3(name = "bbox")]
+    fn get_bbox(&self) -> BoundingBox {
+        self.bbox()
+    }
+    fn __contains__(&self, other: Bound<PyAny>) -> PyResult<bool> {
+        if let Ok(point) = Point::extract_bound(&other) {
+            Ok(self.contains_point(&point))
+        } else if let Ok(bbox) = BoundingBox::extract_bound(&other) {
+            Ok(Region { rects: vec![bbox] }
+                .difference(self)
+                .rects
+                .is_empty())
+        } else {
+            Err(PyTypeError::new_err(
+                "Expected either a Point or a BoundingBox",
+            ))
+        }
+    }
+    /// Combines the cells of two regions.
+    ///
+    /// Parameters
+    /// ----------
+    /// other : Region or BoundingBox
+    ///     The other region.
+    ///
+    /// Returns
+    /// -------
+    /// Region
+    ///     The cells in either region (also available as `region | other`).
+    ///
+    #[pyo3(name = "union")]
+    fn py_union(&self, other: Bound<PyAny>) -> PyResult<Region> {
+        Ok(self.union(&Region::extract_bound(&other)?))
+    }
+    /// Finds the cells shared by two regions.
+    ///
+    /// Parameters
+    /// ----------
+    /// other : Region or BoundingBox
+    ///     The other region.
+    ///
+    /// Returns
+    /// -------
+    /// Region
+    ///     The cells in both regions (also available as `region & other`).
+    ///
+    #[pyo3(name = "intersection")]
+    fn py_intersection(&self, other: Bound<PyAny>) -> PyResult<Region> {
+        Ok(self.intersection(&Region::extract_bound(&other)?))
+    }
+    /// Removes the cells of another region from this one.
+    ///
+    /// Parameters
+    /// ----------
+    /// other : Region or BoundingBox
+    ///     The region to remove.
+    ///
+    /// Returns
+    /// -------
+    /// Region
+    ///     The cells in this region but not the other (also available as `region - other`).
+    ///
+    #[pyo3(name = "difference")]
+    fn py_difference(&self, other: Bound<PyAny>) -> PyResult<Region> {
+        Ok(self.difference(&Region::extract_bound(&other)?))
+    }
+    /// Grows every rectangle of the region on every side.
+    ///
+    /// Parameters
+    /// ----------
+    /// margin : int
+    ///     The number of cells to add to each side.
+    ///
+    /// Returns
+    /// -------
+    /// Region
+    ///     The grown region.
+    ///
+    fn expand(&self, margin: usize) -> Region {
+        Region {
+            rects: self
+                .rects
+                .iter()
+                .map(|rect| rect.expanded(margin))
+                .collect(),
+        }
+    }
+    fn __or__(&self, other: Bound<PyAny>) -> PyResult<Region> {
+        self.py_union(other)
+    }
+    fn __and__(&self, other: Bound<PyAny>) -> PyResult<Region> {
+        self.py_intersection(other)
+    }
+    fn __sub__(&self, other: Bound<PyAny>) -> PyResult<Region> {
+        self.py_difference(other)
+    }
+    fn __len__(&self) -> usize {
+        self.cells().len()
+    }
+    fn __bool__(&self) -> bool {
+        self.rects.iter().any(|rect| !rect.is_empty())
+    }
+    /// Iterates over every cell in the region once, row by row from the top-left corner.
+    fn __iter__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyIterator>> {
+        PyList::new(py, self.cells())?.as_any().try_iter()
+    }
+    /// Regions are equal if they cover the same cells, however they are split into rectangles.
+    fn __eq__(&self, other: Bound<PyAny>) -> bool {
+        Region::extract_bound(&other).is_ok_and(|other| other.cells() == self.cells())
+    }
+    fn __reduce__<'py>(&self, py: Python<'py>) -> PyResult<(Bound<'py, PyAny>, (String,))> {
+        reduce(py, Record::Region(self.clone()))
+    }
+    fn __copy__(&self) -> Self {
+        self.clone()
+    }
+    fn __deepcopy__(&self, _memo: Bound<PyAny>) -> Self {
+        self.clone()
+    }
+    fn __repr__(&self) -> String {
+        format!(
+            "Region([{}])",
+            self.rects
+                .iter()
+                .map(BoundingBox::__repr__)
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 enum Color {
     Ansi(AnsiColors),
     Rgb(color_art::Color),
 }
+impl PartialEq for Color {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+impl Eq for Color {}
+impl Hash for Color {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state)
+    }
+}
 impl Default for Color {
     fn default() -> Self {
         Self::Ansi(AnsiColors::Default)
     }
 }
 impl Color {
+    /// A compact value identifying the color, used for equality and hashing.
+    fn key(&self) -> (bool, u8, u8, u8) {
+        match self {
+            Color::Ansi(ansi_colors) => (false, *ansi_colors as u8, 0, 0),
+            Color::Rgb(color) => (true, color.red(), color.green(), color.blue()),
+        }
+    }
     fn is_default(&self) -> bool {
         match self {
             Color::Ansi(ansi_colors) => ansi_colors == &AnsiColors::Default,
             Color::Rgb(_) => false,
         }
     }
+    /// The color as it is written in a style string (hex colors always have six digits).
+    fn spec(&self) -> String {
+        match self {
+            Color::Ansi(_) => self.to_string(),
+            Color::Rgb(color) => format!(
+                "#{:02x}{:02x}{:02x}",
+                color.red(),
+                color.green(),
+                color.blue()
+            ),
+        }
+    }
     fn or(self, other: Self) -> Self {
         if self.is_default() {
             other
@@ -638,6 +1602,59 @@ impl Display for Color {
         )
     }
 }
+/// The names of the supported text effects, in canonical order.
+const EFFECT_NAMES: [&str; 9] = [
+    "bold",
+    "dimmed",
+    "italic",
+    "underline",
+    "blink",
+    "blinkfast",
+    "reversed",
+    "hidden",
+    "strikethrough",
+];
+
+/// A set of text effects stored as bit flags (indexed by `EFFECT_NAMES`).
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct Effects(u16);
+impl Effects {
+    fn insert(&mut self, name: &str) -> PyResult<()> {
+        let index = EFFECT_NAMES
+            .iter()
+            .position(|e| *e == name)
+            .ok_or_else(|| PyValueError::new_err(format!("Invalid effect [{}]", name)))?;
+        self.0 |= 1 << index;
+        Ok(())
+    }
+    fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        EFFECT_NAMES
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| self.0 & (1 << i) != 0)
+            .map(|(_, name)| *name)
+    }
+    fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+    fn to_owo_effects(self) -> Vec<Effect> {
+        self.names()
+            .map(|name| match name {
+                "bold" => Effect::Bold,
+                "dimmed" => Effect::Dimmed,
+                "italic" => Effect::Italic,
+                "underline" => Effect::Underline,
+                "blink" => Effect::Blink,
+                "blinkfast" => Effect::BlinkFast,
+                "reversed" => Effect::Reversed,
+                "hidden" => Effect::Hidden,
+                "strikethrough" => Effect::Strikethrough,
+                _ => unreachable!(),
+            })
+            .collect()
+    }
+}
+
 /// Represents a text style with foreground and background colors and text effects.
 ///
 /// Parameters
@@ -668,10 +1685,11 @@ impl Display for Color {
 /// >>> style.effects
 /// {'underline'}
 ///
-#[pyclass(name = "Style")]
-#[derive(Default, Clone, Debug)]
+#[pyclass(module = "textdraw", name = "Style")]
+#[derive(Default, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
 struct TextStyle {
-    effects: HashSet<String>,
+    effects: Effects,
     fg: Color,
     bg: Color,
 }
@@ -693,16 +1711,34 @@ impl TextStyle {
             "Style(fg={}, bg={}, effects=[{}])",
             self.fg,
             self.bg,
-            self.effects
-                .clone()
-                .into_iter()
-                .collect::<Vec<String>>()
-                .join(", ")
+            self.effects.names().collect::<Vec<_>>().join(", ")
         )
     }
+    fn __repr__(&self) -> String {
+        format!("Style('{}')", self.spec())
+    }
+    fn __eq__(&self, other: Bound<PyAny>) -> bool {
+        other
+            .extract::<TextStyle>()
+            .is_ok_and(|other| other == *self)
+    }
+    /// Styles hash like their specification string (see `__repr__`), which is the same for all
+    /// equal styles.
+    fn __hash__(&self, py: Python<'_>) -> PyResult<isize> {
+        PyString::new(py, &self.spec()).hash()
+    }
+    fn __reduce__<'py>(&self, py: Python<'py>) -> PyResult<(Bound<'py, PyAny>, (String,))> {
+        reduce(py, Record::Style(self.clone()))
+    }
+    fn __copy__(&self) -> Self {
+        self.clone()
+    }
+    fn __deepcopy__(&self, _memo: Bound<PyAny>) -> Self {
+        self.clone()
+    }
     #[getter]
-    fn get_effects(&self) -> HashSet<String> {
-        self.effects.clone()
+    fn get_effects(&self) -> HashSet<&'static str> {
+        self.effects.names().collect()
     }
     #[getter]
     fn get_fg(&self) -> String {
@@ -714,27 +1750,26 @@ impl TextStyle {
     }
 }
 impl TextStyle {
+    /// The style as a string which parses back into the same style, like "bold red on blue".
+    fn spec(&self) -> String {
+        let mut parts: Vec<String> = self.effects.names().map(str::to_string).collect();
+        if !self.fg.is_default() {
+            parts.push(self.fg.spec());
+        }
+        if !self.bg.is_default() {
+            parts.push(format!("on {}", self.bg.spec()));
+        }
+        parts.join(" ")
+    }
     fn render(&self, text: &str) -> PyResult<String> {
-        let effects = self
-            .effects
-            .iter()
-            .map(|style| match style.as_str() {
-                "bold" => Ok(Effect::Bold),
-                "dimmed" => Ok(Effect::Dimmed),
-                "italic" => Ok(Effect::Italic),
-                "underline" => Ok(Effect::Underline),
-                "blink" => Ok(Effect::Blink),
-                "blinkfast" => Ok(Effect::BlinkFast),
-                "reversed" => Ok(Effect::Reversed),
-                "hidden" => Ok(Effect::Hidden),
-                "strikethrough" => Ok(Effect::Strikethrough),
-                _ => unreachable!(),
-            })
-            .collect::<PyResult<Vec<_>>>()?;
+        Ok(text.style(self.to_owo_style()?).to_string())
+    }
+    fn to_owo_style(&self) -> PyResult<Style> {
+        let effects = self.effects.to_owo_effects();
         let mut style = Style::new().effects(&effects);
         style = self.fg.update_style_fg(style);
         style = self.bg.update_style_bg(style);
-        Ok(text.style(style).to_string())
+        Ok(style)
     }
 }
 impl<'py> TryFrom<Bound<'py, PyAny>> for TextStyle {
@@ -763,12 +1798,23 @@ impl<'a, 'py> TryFrom<&'a Bound<'py, PyAny>> for TextStyle {
         }
     }
 }
+impl From<TextStyle> for String {
+    fn from(value: TextStyle) -> Self {
+        value.spec()
+    }
+}
+impl TryFrom<String> for TextStyle {
+    type Error = PyErr;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
 impl Add for TextStyle {
     type Output = TextStyle;
 
     fn add(self, rhs: Self) -> Self::Output {
-        let mut effects = self.effects;
-        effects.extend(rhs.effects);
+        let effects = self.effects.union(rhs.effects);
         let fg = rhs.fg.or(self.fg);
         let bg = rhs.bg.or(self.bg);
         Self { effects, fg, bg }
@@ -776,7 +1822,7 @@ impl Add for TextStyle {
 }
 impl AddAssign for TextStyle {
     fn add_assign(&mut self, rhs: Self) {
-        self.effects.extend(rhs.effects);
+        self.effects = self.effects.union(rhs.effects);
         self.fg = rhs.fg.or(self.fg);
         self.bg = rhs.bg.or(self.bg);
     }
@@ -800,14 +1846,12 @@ impl FromStr for TextStyle {
         let re = Regex::new(&format!(
             r"^(?P<styles>(?:({effects_re})\s*)*)?(?P<fg>#[\da-f]{{6}}|\w+)?(?:\s*on\s+(?P<bg>#[\da-f]{{6}}|\w+))?$"
 )).unwrap();
-        let mut effects = HashSet::new();
+        let mut effects = Effects::default();
         if let Some(captures) = re.captures(s.to_lowercase().trim()) {
             if let Some(matched_effects) = captures.name("styles") {
-                effects = matched_effects
-                    .as_str()
-                    .split_whitespace()
-                    .map(|s| s.to_string())
-                    .collect::<HashSet<String>>();
+                for effect in matched_effects.as_str().split_whitespace() {
+                    effects.insert(effect)?;
+                }
             }
             let mut fg = Color::default();
             if let Some(fg_str) = captures.name("fg").map(|m| m.as_str()) {
@@ -853,8 +1897,8 @@ impl FromStr for TextStyle {
 /// >>> pixel.character
 /// 'A'
 ///
-#[pyclass]
-#[derive(Clone, Debug)]
+#[pyclass(module = "textdraw")]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 struct Pixel {
     #[pyo3(get, set)]
     character: char,
@@ -925,6 +1969,35 @@ impl Pixel {
     fn __str__(&self) -> PyResult<String> {
         self.render()
     }
+    fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+        Ok(constructor_repr(
+            "Pixel",
+            &[py_repr(py, self.character)?, py_repr(py, self.position)?],
+            &[
+                (
+                    "style",
+                    keyword(py, self.style.spec(), self.style == TextStyle::default())?,
+                ),
+                ("weight", keyword(py, self.weight, self.weight.is_none())?),
+            ],
+        ))
+    }
+    fn __eq__(&self, other: Bound<PyAny>) -> bool {
+        other.extract::<Pixel>().is_ok_and(|other| other == *self)
+    }
+    /// Pixels hash like their repr, which is the same for all equal pixels.
+    fn __hash__(&self, py: Python<'_>) -> PyResult<isize> {
+        PyString::new(py, &self.__repr__(py)?).hash()
+    }
+    fn __reduce__<'py>(&self, py: Python<'py>) -> PyResult<(Bound<'py, PyAny>, (String,))> {
+        reduce(py, Record::Pixel(self.clone()))
+    }
+    fn __copy__(&self) -> Self {
+        self.clone()
+    }
+    fn __deepcopy__(&self, _memo: Bound<PyAny>) -> Self {
+        self.clone()
+    }
     #[setter]
     fn set_position(&mut self, point: Bound<PyAny>) -> PyResult<()> {
         self.position = Point::extract_bound(&point)?;
@@ -940,16 +2013,6 @@ impl Pixel {
         new_pixel.weight = weight;
         new_pixel
     }
-    fn duplicate(&self, position: Point) -> Self {
-        let mut new_obj = self.clone();
-        new_obj.position = position;
-        new_obj
-    }
-    fn duplicate_shifted(&self, delta: Point) -> Self {
-        let mut new_obj = self.clone();
-        new_obj.position += delta;
-        new_obj
-    }
 }
 
 /// A group of pixels that can be rendered together.
@@ -979,8 +2042,8 @@ impl Pixel {
 /// >>> pg.bbox
 /// BoundingBox(top=2, right=0, bottom=0, left=0)
 ///
-#[pyclass(sequence)]
-#[derive(Clone)]
+#[pyclass(module = "textdraw", sequence)]
+#[derive(Clone, Serialize, Deserialize)]
 struct PixelGroup {
     #[pyo3(get, set)]
     pixels: Vec<Pixel>,
@@ -1017,6 +2080,31 @@ impl PixelGroup {
     fn __setitem__(&mut self, index: usize, value: Pixel) {
         self.pixels[index] = value;
     }
+    fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+        Ok(constructor_repr(
+            "PixelGroup",
+            &[
+                py_repr(py, self.pixels.clone())?,
+                py_repr(py, self.position)?,
+            ],
+            &[
+                (
+                    "style",
+                    keyword(py, self.style.spec(), self.style == TextStyle::default())?,
+                ),
+                ("weight", keyword(py, self.weight, self.weight == Some(0))?),
+            ],
+        ))
+    }
+    fn __reduce__<'py>(&self, py: Python<'py>) -> PyResult<(Bound<'py, PyAny>, (String,))> {
+        reduce(py, Record::PixelGroup(self.clone()))
+    }
+    fn __copy__(&self) -> Self {
+        self.clone()
+    }
+    fn __deepcopy__(&self, _memo: Bound<PyAny>) -> Self {
+        self.clone()
+    }
     #[getter]
     fn bbox(&self) -> BoundingBox {
         pixels_to_bounding_box(&self.pixels)
@@ -1060,15 +2148,18 @@ impl PixelGroup {
     }
 }
 impl PixelGroup {
-    fn duplicate(&self, position: Point) -> Self {
-        let mut new_obj = self.clone();
-        new_obj.position = position;
-        new_obj
-    }
-    fn duplicate_shifted(&self, delta: Point) -> Self {
-        let mut new_obj = self.clone();
-        new_obj.position += delta;
-        new_obj
+    /// Resolves the group's offset, style, and weight into each of its pixels.
+    fn flattened(&self) -> impl Iterator<Item = Pixel> + '_ {
+        self.pixels.iter().map(|p| {
+            let mut new_pixel = p.clone();
+            new_pixel.position += self.position;
+            new_pixel.style += self.style.clone();
+            new_pixel.weight = match (new_pixel.weight, self.weight) {
+                (None, _) | (_, None) => None,
+                (Some(w1), Some(w2)) => Some(w1 + w2),
+            };
+            new_pixel
+        })
     }
 }
 
@@ -1106,7 +2197,7 @@ fn text(
                     character: c,
                     position: Point(i as isize, 0),
                     style: style.clone().unwrap_or_default().parse()?,
-                    weight: weight.clone(),
+                    weight,
                 })
             })
             .collect::<PyResult<Vec<_>>>()?,
@@ -1119,715 +2210,5480 @@ fn text(
     })
 }
 
-fn pixels_to_map(pixels: &[Pixel]) -> HashMap<Point, Pixel> {
-    pixels.iter().map(|p| (p.position, p.clone())).collect()
+/// The Python repr of a value.
+fn py_repr<'py, T: IntoPyObject<'py>>(py: Python<'py>, value: T) -> PyResult<String>
+where
+    PyErr: From<T::Error>,
+{
+    Ok(value
+        .into_pyobject(py)?
+        .into_bound()
+        .into_any()
+        .repr()?
+        .to_string())
 }
 
-fn objs_to_map(args: &Bound<'_, PyAny>) -> PyResult<HashMap<Point, Pixel>> {
-    let mut map: HashMap<Point, Pixel> = HashMap::new();
-    let objs: Vec<Bound<PyAny>> = if let Ok(it) = args.downcast::<PyTuple>() {
-        it.iter().collect()
-    } else if let Ok(it) = args.downcast::<PyList>() {
-        it.iter().collect()
-    } else {
-        return Err(PyTypeError::new_err("Expected either a list or a tuple"));
-    };
-    for obj in objs {
-        if let Ok(pixel) = obj.extract::<Pixel>() {
-            map.insert(pixel.position, pixel);
-        } else if let Ok(group) = obj.extract::<PixelGroup>() {
-            for p in &group.pixels {
-                let mut new_pixel = p.clone();
-                new_pixel.position = new_pixel.position + group.position;
-                new_pixel.style += group.style.clone();
-                new_pixel.weight = match (new_pixel.weight, group.weight) {
-                    (None, _) | (_, None) => None,
-                    (Some(w1), Some(w2)) => Some(w1 + w2),
-                };
-                map.insert(new_pixel.position, new_pixel);
-            }
-        } else if let Ok(textpath) = obj.extract::<TextPath>() {
-            let group = textpath.as_group()?;
-            for p in &group.pixels {
-                let mut new_pixel = p.clone();
-                new_pixel.position = new_pixel.position + group.position;
-                new_pixel.style += group.style.clone();
-                new_pixel.weight = match (new_pixel.weight, group.weight) {
-                    (None, _) | (_, None) => None,
-                    (Some(w1), Some(w2)) => Some(w1 + w2),
-                };
-                map.insert(new_pixel.position, new_pixel);
-            }
-        } else if let Ok(textbox) = obj.extract::<Box>() {
-            let group = textbox.as_group();
-            for p in &group.pixels {
-                let mut new_pixel = p.clone();
-                new_pixel.position = new_pixel.position + group.position;
-                new_pixel.style += group.style.clone();
-                new_pixel.weight = match (new_pixel.weight, group.weight) {
-                    (None, _) | (_, None) => None,
-                    (Some(w1), Some(w2)) => Some(w1 + w2),
-                };
-                map.insert(new_pixel.position, new_pixel);
-            }
-        } else {
-            return Err(PyTypeError::new_err(
-                "Expected either Pixels, PixelGroups, TextPaths, or Boxes as arguments",
-            ));
-        }
-    }
-    Ok(map)
+/// The repr of a keyword argument for `constructor_repr`, or None if it has its default value.
+fn keyword<'py, T: IntoPyObject<'py>>(
+    py: Python<'py>,
+    value: T,
+    is_default: bool,
+) -> PyResult<Option<String>>
+where
+    PyErr: From<T::Error>,
+{
+    (!is_default).then(|| py_repr(py, value)).transpose()
+}
+
+/// Formats a constructor call which creates an equivalent object, for `__repr__`. Keyword
+/// arguments which are None are left out.
+fn constructor_repr(name: &str, args: &[String], kwargs: &[(&str, Option<String>)]) -> String {
+    let kwargs = kwargs
+        .iter()
+        .filter_map(|(key, value)| value.as_ref().map(|value| format!("{}={}", key, value)));
+    format!(
+        "{}({})",
+        name,
+        args.iter().cloned().chain(kwargs).join(", ")
+    )
 }
 
-fn map_to_bounding_box(map: &HashMap<Point, Pixel>) -> BoundingBox {
-    let min_x = map.keys().map(|p| p.0).min().unwrap_or_default();
-    let min_y = map.keys().map(|p| p.1).min().unwrap_or_default();
-    let max_x = map.keys().map(|p| p.0).max().unwrap_or_default();
-    let max_y = map.keys().map(|p| p.1).max().unwrap_or_default();
-    BoundingBox {
-        top: max_y,
-        right: max_x,
-        bottom: min_y,
-        left: min_x,
+/// An object which can be drawn onto a canvas, routed around, and moved.
+trait Drawable: Sized + Clone {
+    /// The pixels of the object. If `labels` is false, the labels of TextPaths are left out so
+    /// that only their lines can be followed by other paths.
+    fn pixels(&self, labels: bool) -> PyResult<Vec<Pixel>>;
+    /// The space the object takes up when arranged or positioned relative to other objects.
+    fn bbox(&self) -> PyResult<BoundingBox> {
+        Ok(pixels_to_bounding_box(&self.pixels(true)?))
+    }
+    /// The bounding boxes of every `Box` or `Table` in the object, which paths can avoid.
+    fn box_bounds(&self) -> PyResult<Vec<BoundingBox>> {
+        Ok(Vec::new())
+    }
+    /// The point the object is drawn relative to, if moving the object is a matter of changing it.
+    /// Objects without one override `duplicate` and `duplicate_shifted` instead.
+    fn position_mut(&mut self) -> Option<&mut Point> {
+        None
+    }
+    fn duplicate(&self, position: Point) -> Self {
+        let mut new_obj = self.clone();
+        if let Some(p) = new_obj.position_mut() {
+            *p = position;
+        }
+        new_obj
+    }
+    fn duplicate_shifted(&self, delta: Point) -> Self {
+        let mut new_obj = self.clone();
+        if let Some(p) = new_obj.position_mut() {
+            *p += delta;
+        }
+        new_obj
     }
 }
-fn pixels_to_bounding_box(pixels: &[Pixel]) -> BoundingBox {
-    BoundingBox {
-        top: pixels
-            .iter()
-            .map(|p| p.position.1)
-            .max()
-            .unwrap_or_default(),
-        right: pixels
-            .iter()
-            .map(|p| p.position.0)
-            .max()
-            .unwrap_or_default(),
-        bottom: pixels
-            .iter()
-            .map(|p| p.position.1)
-            .min()
-            .unwrap_or_default(),
-        left: pixels
-            .iter()
-            .map(|p| p.position.0)
-            .min()
-            .unwrap_or_default(),
+impl Drawable for Pixel {
+    fn pixels(&self, _labels: bool) -> PyResult<Vec<Pixel>> {
+        Ok(vec![self.clone()])
+    }
+    fn position_mut(&mut self) -> Option<&mut Point> {
+        Some(&mut self.position)
     }
 }
-
-/// Renders a list of objects (TextPath, Box, Pixel, or PixelGroup) into a single string.
-///
-/// Parameters
-/// ----------
-/// objs: list
-///     A list of objects to render. Each object must be an instance of TextPath, Box, Pixel, or PixelGroup.
-/// default_style: str, optional
-///     A style to apply to empty space.
-///
-/// Returns
-/// -------
-/// str
-///     The rendered string with ANSI escape codes.
-///
-/// Raises
-/// ------
-/// TypeError
-///     If an object in the list is not a TextPath, Box, Pixel, or PixelGroup.
-///
-/// Examples
-/// --------
-/// >>> from textdraw import render, text, Style
-/// >>> text = text("Hello", (0,0), "green")
-/// >>> render([text])
-/// '\x1b[32mHello\x1b[39m'
-///
-#[pyfunction(signature = (objs, default_style = None))]
-fn render(objs: Bound<'_, PyAny>, default_style: Option<String>) -> PyResult<String> {
-    let map = objs_to_map(&objs)?;
-    let bb = map_to_bounding_box(&map);
-    let empty_style: TextStyle = default_style
-        .map(|s| s.parse())
-        .transpose()?
-        .unwrap_or_default();
-    let empty_space = empty_style.render(" ")?;
-    let mut output = String::new();
-    for y in (bb.bottom..=bb.top).rev() {
-        for x in bb.left..=bb.right {
-            if let Some(p) = map.get(&Point(x, y)) {
-                output.push_str(&p.render()?);
-            } else {
-                output.push_str(&empty_space);
-            }
-        }
-        output.push('\n')
+impl Drawable for PixelGroup {
+    fn pixels(&self, _labels: bool) -> PyResult<Vec<Pixel>> {
+        Ok(self.flattened().collect())
+    }
+    fn position_mut(&mut self) -> Option<&mut Point> {
+        Some(&mut self.position)
     }
-    Ok(output)
 }
-/// Takes a list of objects (TextPath, Box, Pixel, or PixelGroup) and duplicates them shifted by a
-/// given delta amount.
-///
-/// Parameters
-/// ----------
-/// objects : list
-///     A list of objects to duplicate. Each object must be an instance of TextPath, Box, Pixel, or PixelGroup.
-/// delta : Point or tuple of ints, optional
-///     The (x, y) shift difference.
-///
-/// Returns
-/// -------
-/// objects : list
-///     Duplicated objects shifted by the given delta.
-///
-/// Raises
-/// ------
-/// TypeError
-///     If an object in the list is not a TextPath, Box, Pixel, or PixelGroup.
-///
-#[pyfunction]
-fn duplicate_shifted<'py>(
-    py: Python<'py>,
-    objects: Vec<Bound<'py, PyAny>>,
-    delta: Bound<PyAny>,
-) -> PyResult<Vec<Bound<'py, PyAny>>> {
-    let mut res = Vec::with_capacity(objects.len());
-    for obj in objects {
-        if let Ok(obj) = obj.extract::<Pixel>() {
-            res.push(
-                Bound::new(py, obj.py_duplicate_shifted(delta.clone())?)?
-                    .as_any()
-                    .clone(),
-            );
-        } else if let Ok(obj) = obj.extract::<PixelGroup>() {
-            res.push(
-                Bound::new(py, obj.py_duplicate_shifted(delta.clone())?)?
-                    .as_any()
-                    .clone(),
-            );
-        } else if let Ok(obj) = obj.extract::<TextPath>() {
-            res.push(
-                Bound::new(py, obj.py_duplicate_shifted(delta.clone())?)?
-                    .as_any()
-                    .clone(),
-            );
-        } else if let Ok(obj) = obj.extract::<Box>() {
-            res.push(
-                Bound::new(py, obj.py_duplicate_shifted(delta.clone())?)?
-                    .as_any()
-                    .clone(),
-            );
+impl Drawable for Region {
+    /// Blank, impassable pixels covering the region.
+    fn pixels(&self, _labels: bool) -> PyResult<Vec<Pixel>> {
+        Ok(self
+            .cells()
+            .into_iter()
+            .map(|position| Pixel {
+                character: ' ',
+                position,
+                style: TextStyle::default(),
+                weight: None,
+            })
+            .collect())
+    }
+    fn duplicate(&self, position: Point) -> Self {
+        let bbox = self.bbox();
+        if bbox.is_empty() {
+            self.clone()
         } else {
-            return Err(PyTypeError::new_err(
-                "Expected objects to be a list of Pixels, PixelGroups, TextPaths, and/or Boxes",
-            ));
+            self.duplicate_shifted(position - Coordinates::corner(bbox))
+        }
+    }
+    fn duplicate_shifted(&self, delta: Point) -> Self {
+        Region {
+            rects: self
+                .rects
+                .iter()
+                .map(|rect| rect.duplicate_shifted(delta))
+                .collect(),
         }
     }
-    Ok(res)
 }
-
-/// Takes a list of objects (TextPath, Box, Pixel, or PixelGroup) and duplicates them at a new
-/// location.
-///
-/// Parameters
-/// ----------
-/// objects : list
-///     A list of objects to duplicate. Each object must be an instance of TextPath, Box, Pixel, or PixelGroup.
-///
-/// Returns
-/// -------
-/// objects : list
-///     Duplicated objects at the specified location.
-///
-/// Raises
-/// ------
-/// TypeError
-///     If an object in the list is not a TextPath, Box, Pixel, or PixelGroup.
-///
-#[pyfunction]
-fn duplicate<'py>(
-    py: Python<'py>,
-    objects: Vec<Bound<'py, PyAny>>,
-    position: Option<Bound<PyAny>>,
-) -> PyResult<Vec<Bound<'py, PyAny>>> {
-    let mut res = Vec::with_capacity(objects.len());
-    for obj in objects {
-        if let Ok(obj) = obj.extract::<Pixel>() {
-            res.push(
-                Bound::new(py, obj.py_duplicate(position.clone())?)?
-                    .as_any()
-                    .clone(),
-            );
-        } else if let Ok(obj) = obj.extract::<PixelGroup>() {
-            res.push(
-                Bound::new(py, obj.py_duplicate(position.clone())?)?
-                    .as_any()
-                    .clone(),
-            );
-        } else if let Ok(obj) = obj.extract::<TextPath>() {
-            res.push(
-                Bound::new(py, obj.py_duplicate(position.clone())?)?
-                    .as_any()
-                    .clone(),
-            );
-        } else if let Ok(obj) = obj.extract::<Box>() {
-            res.push(
-                Bound::new(py, obj.py_duplicate(position.clone())?)?
-                    .as_any()
-                    .clone(),
-            );
+impl Drawable for TextPath {
+    fn pixels(&self, labels: bool) -> PyResult<Vec<Pixel>> {
+        let group = if labels {
+            self.as_group()?
         } else {
-            return Err(PyTypeError::new_err(
-                "Expected objects to be a list of Pixels, PixelGroups, TextPaths, and/or Boxes",
-            ));
-        }
+            self.as_line_group()?
+        };
+        Ok(group.flattened().collect())
+    }
+    fn position_mut(&mut self) -> Option<&mut Point> {
+        Some(&mut self.position)
     }
-    Ok(res)
 }
-
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
-enum Direction {
-    #[default]
-    Up,
-    Right,
-    Down,
-    Left,
+impl Drawable for Box {
+    fn pixels(&self, _labels: bool) -> PyResult<Vec<Pixel>> {
+        Ok(self.as_group().flattened().collect())
+    }
+    fn bbox(&self) -> PyResult<BoundingBox> {
+        Ok(self.get_bbox())
+    }
+    fn box_bounds(&self) -> PyResult<Vec<BoundingBox>> {
+        Ok(vec![self.get_bbox()])
+    }
+    fn position_mut(&mut self) -> Option<&mut Point> {
+        Some(&mut self.position)
+    }
 }
-impl Direction {
-    fn delta(self) -> Point {
-        match self {
-            Direction::Up => (0, 1),
-            Direction::Right => (1, 0),
-            Direction::Down => (0, -1),
-            Direction::Left => (-1, 0),
-        }
-        .into()
+impl Drawable for Table {
+    fn pixels(&self, _labels: bool) -> PyResult<Vec<Pixel>> {
+        Ok(self.as_group().flattened().collect())
     }
-
-    fn all() -> [Direction; 4] {
-        [
-            Direction::Up,
-            Direction::Right,
-            Direction::Down,
-            Direction::Left,
-        ]
+    fn bbox(&self) -> PyResult<BoundingBox> {
+        Ok(self.get_bbox())
+    }
+    fn box_bounds(&self) -> PyResult<Vec<BoundingBox>> {
+        Ok(vec![self.get_bbox()])
+    }
+    fn position_mut(&mut self) -> Option<&mut Point> {
+        Some(&mut self.position)
     }
 }
-impl Display for Direction {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                Direction::Up => "up",
-                Direction::Right => "right",
-                Direction::Down => "down",
-                Direction::Left => "left",
-            }
-        )
+impl Drawable for HStack {
+    fn pixels(&self, labels: bool) -> PyResult<Vec<Pixel>> {
+        self.layout()?.pixels(labels)
+    }
+    fn bbox(&self) -> PyResult<BoundingBox> {
+        Ok(self.layout()?.bbox)
+    }
+    fn box_bounds(&self) -> PyResult<Vec<BoundingBox>> {
+        self.layout()?.box_bounds()
+    }
+    fn position_mut(&mut self) -> Option<&mut Point> {
+        Some(&mut self.position)
     }
 }
-impl FromStr for Direction {
-    type Err = PyErr;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_lowercase().as_str() {
-            "up" => Ok(Direction::Up),
-            "right" => Ok(Direction::Right),
-            "down" => Ok(Direction::Down),
-            "left" => Ok(Direction::Left),
-            _ => Err(PyValueError::new_err("Invalid direction")),
-        }
+impl Drawable for VStack {
+    fn pixels(&self, labels: bool) -> PyResult<Vec<Pixel>> {
+        self.layout()?.pixels(labels)
+    }
+    fn bbox(&self) -> PyResult<BoundingBox> {
+        Ok(self.layout()?.bbox)
+    }
+    fn box_bounds(&self) -> PyResult<Vec<BoundingBox>> {
+        self.layout()?.box_bounds()
+    }
+    fn position_mut(&mut self) -> Option<&mut Point> {
+        Some(&mut self.position)
+    }
+}
+impl Drawable for GridLayout {
+    fn pixels(&self, labels: bool) -> PyResult<Vec<Pixel>> {
+        self.layout()?.pixels(labels)
+    }
+    fn bbox(&self) -> PyResult<BoundingBox> {
+        Ok(self.layout()?.bbox)
+    }
+    fn box_bounds(&self) -> PyResult<Vec<BoundingBox>> {
+        self.layout()?.box_bounds()
+    }
+    fn position_mut(&mut self) -> Option<&mut Point> {
+        Some(&mut self.position)
     }
 }
 
-#[derive(Clone, Copy, Default)]
-enum LineStyle {
-    #[default]
-    Light,
-    Heavy,
-    Double,
+/// A Python object which draws itself through a `__textdraw__` method returning a textdraw object
+/// (or a list of them).
+struct Custom {
+    object: Py<PyAny>,
+    content: Vec<Element>,
+    /// The corner of the content as the object drew it (see `Coordinates::corner`).
+    origin: Point,
+    /// How far the content has been moved since the object drew it.
+    delta: Point,
 }
-impl LineStyle {
-    fn get_char(&self, neighbors: (bool, bool, bool, bool)) -> char {
-        let chars: Vec<char> = match self {
-            LineStyle::Light => " ─│┐──┌┬│┘│┤└┴├┼",
-            LineStyle::Heavy => " ━┃┓━━┏┳┃┛┃┫┗┻┣╋",
-            LineStyle::Double => " ═║╗══╔╦║╝║╣╚╩╠╬",
-        }
-        .to_string()
-        .chars()
-        .collect();
-        let index = (neighbors.0 as usize) << 3
-            | (neighbors.1 as usize) << 2
-            | (neighbors.2 as usize) << 1
-            | (neighbors.3 as usize);
-        chars[index]
+impl Clone for Custom {
+    fn clone(&self) -> Self {
+        Python::with_gil(|py| Self {
+            object: self.object.clone_ref(py),
+            content: self.content.clone(),
+            origin: self.origin,
+            delta: self.delta,
+        })
     }
 }
-impl Display for LineStyle {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                LineStyle::Light => "light",
-                LineStyle::Heavy => "heavy",
-                LineStyle::Double => "double",
-            }
-        )
-    }
+/// The deepest nesting of objects drawn through one another's `__textdraw__` methods. Each level is
+/// expanded on the Rust stack, so deeper nesting is refused rather than risking an overflow.
+const MAX_TEXTDRAW_DEPTH: usize = 64;
+
+thread_local! {
+    /// The objects whose `__textdraw__` methods are being expanded, outermost first.
+    static EXPANDING: RefCell<Vec<usize>> = const { RefCell::new(Vec::new()) };
 }
-impl FromStr for LineStyle {
-    type Err = PyErr;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_lowercase().as_str() {
-            "light" => Ok(LineStyle::Light),
-            "heavy" => Ok(LineStyle::Heavy),
-            "double" => Ok(LineStyle::Double),
-            other => Err(PyValueError::new_err(format!(
-                "Invalid line style [{}]",
-                other
-            ))),
+/// Marks an object as being expanded for as long as it is alive.
+struct Expansion;
+impl Expansion {
+    /// Raises a RecursionError if the object is already being expanded (so it draws itself, perhaps
+    /// through other objects), or if the nesting is too deep.
+    fn enter(obj: &Bound<PyAny>) -> PyResult<Self> {
+        let id = obj.as_ptr() as usize;
+        EXPANDING.with_borrow_mut(|expanding| {
+            if expanding.contains(&id) {
+                return Err(PyRecursionError::new_err(format!(
+                    "__textdraw__ of a {} object draws the object itself",
+                    obj.get_type().name()?
+                )));
+            }
+            if expanding.len() >= MAX_TEXTDRAW_DEPTH {
+                return Err(PyRecursionError::new_err(format!(
+                    "__textdraw__ methods are nested more than {} deep",
+                    MAX_TEXTDRAW_DEPTH
+                )));
+            }
+            expanding.push(id);
+            Ok(Expansion)
+        })
+    }
+}
+impl Drop for Expansion {
+    fn drop(&mut self) {
+        EXPANDING.with_borrow_mut(|expanding| expanding.pop());
+    }
+}
+
+impl Custom {
+    fn new(obj: &Bound<PyAny>) -> PyResult<Self> {
+        let _expansion = Expansion::enter(obj)?;
+        let drawn = obj.call_method0("__textdraw__")?;
+        if drawn.is(obj) {
+            return Err(PyTypeError::new_err(
+                "__textdraw__ must return textdraw objects rather than the object itself",
+            ));
+        }
+        let content = if drawn.downcast::<PyList>().is_ok() || drawn.downcast::<PyTuple>().is_ok() {
+            objs_to_elements(&drawn)?
+        } else {
+            vec![drawn.extract()?]
+        };
+        let mut custom = Self {
+            object: obj.clone().unbind(),
+            content,
+            origin: Point::default(),
+            delta: Point::default(),
+        };
+        custom.origin = Coordinates::corner(custom.bbox()?);
+        Ok(custom)
+    }
+    /// The object itself if it has not been moved, otherwise a moved copy made with its own
+    /// `duplicate_shifted` method, or failing that, a PixelGroup of its moved content.
+    fn into_object<'py>(self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let object = self.object.into_bound(py);
+        if self.delta == Point::default() {
+            Ok(object)
+        } else if object.hasattr("duplicate_shifted")? {
+            object.call_method1("duplicate_shifted", (self.delta,))
+        } else {
+            let mut pixels = Vec::new();
+            for element in &self.content {
+                pixels.extend(element.pixels(true)?);
+            }
+            Ok(Bound::new(
+                py,
+                PixelGroup {
+                    pixels,
+                    position: Point::default(),
+                    style: TextStyle::default(),
+                    weight: Some(0),
+                },
+            )?
+            .into_any())
+        }
+    }
+}
+impl Drawable for Custom {
+    fn pixels(&self, labels: bool) -> PyResult<Vec<Pixel>> {
+        let mut pixels = Vec::new();
+        for element in &self.content {
+            pixels.extend(element.pixels(labels)?);
+        }
+        Ok(pixels)
+    }
+    fn box_bounds(&self) -> PyResult<Vec<BoundingBox>> {
+        let mut bounds = Vec::new();
+        for element in &self.content {
+            bounds.extend(element.box_bounds()?);
         }
+        Ok(bounds)
+    }
+    fn duplicate(&self, position: Point) -> Self {
+        self.duplicate_shifted(position - self.origin - self.delta)
+    }
+    fn duplicate_shifted(&self, delta: Point) -> Self {
+        let mut new_obj = self.clone();
+        new_obj.content = self
+            .content
+            .iter()
+            .map(|element| element.duplicate_shifted(delta))
+            .collect();
+        new_obj.delta += delta;
+        new_obj
     }
 }
 
-#[derive(Clone, Copy, Default)]
-enum ArrowType {
-    #[default]
-    Arrow,
-    OpenArrow,
-    Custom {
-        up: char,
-        right: char,
-        down: char,
-        left: char,
-    },
+/// Any object which can be drawn: the Python classes accepted by `render`, `duplicate_shifted` and
+/// `BoundingBox.wrap`, along with Python objects implementing the `__textdraw__` protocol.
+#[derive(Clone)]
+#[allow(clippy::large_enum_variant)]
+enum Element {
+    Pixel(Pixel),
+    PixelGroup(PixelGroup),
+    TextPath(TextPath),
+    Box(Box),
+    Table(Table),
+    HStack(HStack),
+    VStack(VStack),
+    Grid(GridLayout),
+    Region(Region),
+    Custom(Custom),
 }
-impl ArrowType {
-    fn render(&self, direction: &Direction) -> String {
+impl<'py> FromPyObject<'py> for Element {
+    fn extract_bound(obj: &Bound<'py, PyAny>) -> PyResult<Self> {
+        if let Ok(pixel) = obj.extract::<Pixel>() {
+            Ok(Element::Pixel(pixel))
+        } else if let Ok(group) = obj.extract::<PixelGroup>() {
+            Ok(Element::PixelGroup(group))
+        } else if let Ok(textpath) = obj.extract::<TextPath>() {
+            Ok(Element::TextPath(textpath))
+        } else if let Ok(textbox) = obj.extract::<Box>() {
+            Ok(Element::Box(textbox))
+        } else if let Ok(table) = obj.extract::<Table>() {
+            Ok(Element::Table(table))
+        } else if let Ok(stack) = obj.extract::<HStack>() {
+            Ok(Element::HStack(stack))
+        } else if let Ok(stack) = obj.extract::<VStack>() {
+            Ok(Element::VStack(stack))
+        } else if let Ok(grid) = obj.extract::<GridLayout>() {
+            Ok(Element::Grid(grid))
+        } else if let Ok(region) = obj.extract::<Region>() {
+            Ok(Element::Region(region))
+        } else if obj.hasattr("__textdraw__")? {
+            Ok(Element::Custom(Custom::new(obj)?))
+        } else {
+            Err(PyTypeError::new_err(
+                "Expected either Pixels, PixelGroups, TextPaths, Boxes, Tables, HStacks, VStacks, \
+                 Grids, Regions, or objects with a __textdraw__ method as arguments",
+            ))
+        }
+    }
+}
+impl<'py> IntoPyObject<'py> for Element {
+    type Target = PyAny;
+    type Output = Bound<'py, PyAny>;
+    type Error = PyErr;
+
+    fn into_pyobject(self, py: Python<'py>) -> PyResult<Self::Output> {
+        Ok(match self {
+            Element::Pixel(obj) => Bound::new(py, obj)?.into_any(),
+            Element::PixelGroup(obj) => Bound::new(py, obj)?.into_any(),
+            Element::TextPath(obj) => Bound::new(py, obj)?.into_any(),
+            Element::Box(obj) => Bound::new(py, obj)?.into_any(),
+            Element::Table(obj) => Bound::new(py, obj)?.into_any(),
+            Element::HStack(obj) => Bound::new(py, obj)?.into_any(),
+            Element::VStack(obj) => Bound::new(py, obj)?.into_any(),
+            Element::Grid(obj) => Bound::new(py, obj)?.into_any(),
+            Element::Region(obj) => Bound::new(py, obj)?.into_any(),
+            Element::Custom(obj) => obj.into_object(py)?,
+        })
+    }
+}
+impl Drawable for Element {
+    fn pixels(&self, labels: bool) -> PyResult<Vec<Pixel>> {
         match self {
-            ArrowType::Arrow => match direction {
-                Direction::Up => "▲",
-                Direction::Right => "▶",
-                Direction::Down => "▼",
-                Direction::Left => "◀",
+            Element::Pixel(obj) => obj.pixels(labels),
+            Element::PixelGroup(obj) => obj.pixels(labels),
+            Element::TextPath(obj) => obj.pixels(labels),
+            Element::Box(obj) => obj.pixels(labels),
+            Element::Table(obj) => obj.pixels(labels),
+            Element::HStack(obj) => obj.pixels(labels),
+            Element::VStack(obj) => obj.pixels(labels),
+            Element::Grid(obj) => obj.pixels(labels),
+            Element::Region(obj) => obj.pixels(labels),
+            Element::Custom(obj) => obj.pixels(labels),
+        }
+    }
+    fn bbox(&self) -> PyResult<BoundingBox> {
+        match self {
+            Element::Pixel(obj) => Drawable::bbox(obj),
+            Element::PixelGroup(obj) => Drawable::bbox(obj),
+            Element::TextPath(obj) => Drawable::bbox(obj),
+            Element::Box(obj) => Drawable::bbox(obj),
+            Element::Table(obj) => Drawable::bbox(obj),
+            Element::HStack(obj) => Drawable::bbox(obj),
+            Element::VStack(obj) => Drawable::bbox(obj),
+            Element::Grid(obj) => Drawable::bbox(obj),
+            Element::Region(obj) => Drawable::bbox(obj),
+            Element::Custom(obj) => Drawable::bbox(obj),
+        }
+    }
+    fn box_bounds(&self) -> PyResult<Vec<BoundingBox>> {
+        match self {
+            Element::Pixel(obj) => obj.box_bounds(),
+            Element::PixelGroup(obj) => obj.box_bounds(),
+            Element::TextPath(obj) => obj.box_bounds(),
+            Element::Box(obj) => obj.box_bounds(),
+            Element::Table(obj) => obj.box_bounds(),
+            Element::HStack(obj) => obj.box_bounds(),
+            Element::VStack(obj) => obj.box_bounds(),
+            Element::Grid(obj) => obj.box_bounds(),
+            Element::Region(obj) => obj.box_bounds(),
+            Element::Custom(obj) => obj.box_bounds(),
+        }
+    }
+    fn duplicate(&self, position: Point) -> Self {
+        match self {
+            Element::Pixel(obj) => Element::Pixel(obj.duplicate(position)),
+            Element::PixelGroup(obj) => Element::PixelGroup(obj.duplicate(position)),
+            Element::TextPath(obj) => Element::TextPath(obj.duplicate(position)),
+            Element::Box(obj) => Element::Box(obj.duplicate(position)),
+            Element::Table(obj) => Element::Table(obj.duplicate(position)),
+            Element::HStack(obj) => Element::HStack(obj.duplicate(position)),
+            Element::VStack(obj) => Element::VStack(obj.duplicate(position)),
+            Element::Grid(obj) => Element::Grid(obj.duplicate(position)),
+            Element::Region(obj) => Element::Region(obj.duplicate(position)),
+            Element::Custom(obj) => Element::Custom(obj.duplicate(position)),
+        }
+    }
+    fn duplicate_shifted(&self, delta: Point) -> Self {
+        match self {
+            Element::Pixel(obj) => Element::Pixel(obj.duplicate_shifted(delta)),
+            Element::PixelGroup(obj) => Element::PixelGroup(obj.duplicate_shifted(delta)),
+            Element::TextPath(obj) => Element::TextPath(obj.duplicate_shifted(delta)),
+            Element::Box(obj) => Element::Box(obj.duplicate_shifted(delta)),
+            Element::Table(obj) => Element::Table(obj.duplicate_shifted(delta)),
+            Element::HStack(obj) => Element::HStack(obj.duplicate_shifted(delta)),
+            Element::VStack(obj) => Element::VStack(obj.duplicate_shifted(delta)),
+            Element::Grid(obj) => Element::Grid(obj.duplicate_shifted(delta)),
+            Element::Region(obj) => Element::Region(obj.duplicate_shifted(delta)),
+            Element::Custom(obj) => Element::Custom(obj.duplicate_shifted(delta)),
+        }
+    }
+}
+
+fn objs_to_elements(args: &Bound<'_, PyAny>) -> PyResult<Vec<Element>> {
+    let objs: Vec<Bound<PyAny>> = if let Ok(it) = args.downcast::<PyTuple>() {
+        it.iter().collect()
+    } else if let Ok(it) = args.downcast::<PyList>() {
+        it.iter().collect()
+    } else {
+        return Err(PyTypeError::new_err("Expected either a list or a tuple"));
+    };
+    objs.iter().map(|obj| obj.extract()).collect()
+}
+
+/// Flattens each object in a list or tuple into pixels, keeping the pixels of each object together.
+///
+/// If `labels` is false, the labels of TextPaths are left out so that only their lines can be
+/// followed by other paths.
+fn objs_to_pixel_sets(args: &Bound<'_, PyAny>, labels: bool) -> PyResult<Vec<Vec<Pixel>>> {
+    objs_to_elements(args)?
+        .iter()
+        .map(|element| element.pixels(labels))
+        .collect()
+}
+
+/// Collects the bounding boxes of every `Box` or `Table` among some objects, including those
+/// inside containers, ignoring other objects.
+fn elements_to_box_bounds(elements: &[Element]) -> PyResult<Vec<BoundingBox>> {
+    Ok(elements
+        .iter()
+        .map(|element| element.box_bounds())
+        .collect::<PyResult<Vec<_>>>()?
+        .into_iter()
+        .flatten()
+        .collect())
+}
+
+fn objs_to_pixels(args: &Bound<'_, PyAny>) -> PyResult<Vec<Pixel>> {
+    elements_to_pixels(&objs_to_elements(args)?)
+}
+
+fn elements_to_pixels(elements: &[Element]) -> PyResult<Vec<Pixel>> {
+    Ok(elements
+        .iter()
+        .map(|element| element.pixels(true))
+        .collect::<PyResult<Vec<_>>>()?
+        .into_iter()
+        .flatten()
+        .collect())
+}
+
+fn pixels_to_bounding_box<'a>(pixels: impl IntoIterator<Item = &'a Pixel>) -> BoundingBox {
+    pixels
+        .into_iter()
+        .fold(BoundingBox::EMPTY, |bb, p| bb + p.position)
+}
+
+/// A dense, row-major store of one value per cell of a bounding box.
+///
+/// Lookups are a bounds check and an index computation, which is much cheaper than hashing a
+/// `Point` for every cell visited during routing or rendering.
+#[derive(Clone)]
+struct Grid<T> {
+    bbox: BoundingBox,
+    width: usize,
+    cells: Vec<T>,
+}
+impl<T: Clone> Grid<T> {
+    fn new(bbox: BoundingBox, fill: T) -> Self {
+        let width = (bbox.right - bbox.left + 1).max(0) as usize;
+        let height = (bbox.top - bbox.bottom + 1).max(0) as usize;
+        Self {
+            bbox,
+            width,
+            cells: vec![fill; width * height],
+        }
+    }
+}
+impl<T> Grid<T> {
+    fn index(&self, p: &Point) -> Option<usize> {
+        if self.bbox.contains_point(p) {
+            Some((self.bbox.top - p.1) as usize * self.width + (p.0 - self.bbox.left) as usize)
+        } else {
+            None
+        }
+    }
+    fn get(&self, p: &Point) -> Option<&T> {
+        self.index(p).map(|i| &self.cells[i])
+    }
+    fn get_mut(&mut self, p: &Point) -> Option<&mut T> {
+        self.index(p).map(|i| &mut self.cells[i])
+    }
+    fn set(&mut self, p: &Point, value: T) {
+        if let Some(cell) = self.get_mut(p) {
+            *cell = value;
+        }
+    }
+    /// Iterates over the rows of the grid from top to bottom.
+    fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.cells.chunks(self.width.max(1))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+struct StyleId(usize);
+
+/// Interns `TextStyle`s so each distinct style is stored (and converted for rendering) once.
+#[derive(Default, Clone)]
+struct StyleTable {
+    styles: Vec<TextStyle>,
+    ids: HashMap<TextStyle, StyleId>,
+}
+impl StyleTable {
+    fn intern(&mut self, style: &TextStyle) -> StyleId {
+        if let Some(id) = self.ids.get(style) {
+            return *id;
+        }
+        let id = StyleId(self.styles.len());
+        self.styles.push(style.clone());
+        self.ids.insert(style.clone(), id);
+        id
+    }
+    fn get(&self, id: StyleId) -> &TextStyle {
+        &self.styles[id.0]
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Cell {
+    character: char,
+    style: StyleId,
+    weight: Option<usize>,
+}
+
+/// The flattened contents of a set of objects, stored as a dense grid over their bounding box.
+///
+/// Later pixels overwrite earlier pixels at the same position, matching the draw order of the
+/// objects passed to `render`.
+#[derive(Clone)]
+struct Canvas {
+    grid: Grid<Option<Cell>>,
+    styles: StyleTable,
+}
+impl Canvas {
+    /// Draws the pixels which fall inside `bbox`, leaving the rest of the box empty.
+    fn from_pixels(pixels: &[Pixel], bbox: BoundingBox) -> Self {
+        let mut canvas = Self {
+            grid: Grid::new(bbox, None),
+            styles: StyleTable::default(),
+        };
+        for pixel in pixels {
+            let style = canvas.styles.intern(&pixel.style);
+            canvas.grid.set(
+                &pixel.position,
+                Some(Cell {
+                    character: pixel.character,
+                    style,
+                    weight: pixel.weight,
+                }),
+            );
+        }
+        canvas
+    }
+    fn bbox(&self) -> BoundingBox {
+        self.grid.bbox
+    }
+    fn render(&self, empty_style: &TextStyle) -> PyResult<String> {
+        let empty_space = empty_style.render(" ")?;
+        let styles = self
+            .styles
+            .styles
+            .iter()
+            .map(|s| s.to_owo_style())
+            .collect::<PyResult<Vec<_>>>()?;
+        let mut output = String::new();
+        for row in self.grid.rows() {
+            for cell in row {
+                if let Some(cell) = cell {
+                    write!(output, "{}", cell.character.style(styles[cell.style.0]))
+                        .expect("writing to a String cannot fail");
+                } else {
+                    output.push_str(&empty_space);
+                }
             }
-            .to_string(),
-            ArrowType::OpenArrow => match direction {
-                Direction::Up => "△",
-                Direction::Right => "▷",
-                Direction::Down => "▽",
-                Direction::Left => "◁",
+            output.push('\n')
+        }
+        Ok(output)
+    }
+    /// Writes the canvas in the plain form used by `render_snapshot`: a header with the size of
+    /// the canvas, its characters, a map giving the style of each cell by a key, and a legend of
+    /// the keys. Trailing spaces are trimmed from every line.
+    fn snapshot(&self) -> String {
+        const KEYS: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+        let height = self.grid.cells.len() / self.grid.width.max(1);
+        let mut keys: HashMap<StyleId, char> = HashMap::new();
+        let mut legend = Vec::new();
+        let mut text = Vec::new();
+        let mut styles = Vec::new();
+        for row in self.grid.rows() {
+            let mut text_row = String::new();
+            let mut style_row = String::new();
+            for cell in row {
+                let Some(cell) = cell else {
+                    text_row.push(' ');
+                    style_row.push(' ');
+                    continue;
+                };
+                text_row.push(cell.character);
+                let style = self.styles.get(cell.style);
+                if *style == TextStyle::default() {
+                    style_row.push('.');
+                    continue;
+                }
+                let key = *keys.entry(cell.style).or_insert_with(|| {
+                    let index = legend.len();
+                    let key = KEYS.chars().nth(index).unwrap_or_else(|| {
+                        char::from_u32(0xC0 + (index - KEYS.len()) as u32)
+                            .expect("there are fewer styles than Latin-1 and later characters")
+                    });
+                    legend.push(format!("{}: {}", key, style.spec()));
+                    key
+                });
+                style_row.push(key);
             }
-            .to_string(),
-            ArrowType::Custom {
-                up,
-                right,
-                down,
-                left,
-            } => match direction {
-                Direction::Up => up,
-                Direction::Right => right,
-                Direction::Down => down,
-                Direction::Left => left,
+            text.push(text_row.trim_end().to_string());
+            styles.push(style_row.trim_end().to_string());
+        }
+        let mut output = format!("textdraw snapshot {}x{}\n", self.grid.width, height);
+        for section in [text, styles, legend] {
+            for line in section {
+                output.push_str(&line);
+                output.push('\n');
             }
-            .to_string(),
+            output.push('\n');
         }
+        output.truncate(output.len() - 1);
+        output
     }
 }
 
-/// Parses a string to generate an arrow-like character
+/// Draws the pixels of a list of objects, keeping only those inside `clip` if it is given.
+fn clipped_pixels(objs: &Bound<'_, PyAny>, clip: Option<Bound<'_, PyAny>>) -> PyResult<Vec<Pixel>> {
+    let mut pixels = objs_to_pixels(objs)?;
+    if let Some(clip) = clip {
+        let clip = Region::extract_bound(&clip)?;
+        pixels.retain(|pixel| clip.contains_point(&pixel.position));
+    }
+    Ok(pixels)
+}
+
+/// Renders a list of objects (TextPath, Box, Table, HStack, VStack, Grid, Pixel, or PixelGroup)
+/// into a single string.
 ///
-/// This is intended to be used with the Pixel class to render a
-/// styled arrow. Eventually the TextPath class will support arrow
-/// endings in a more convenient manner, but for now, they must be
-/// placed manually.
+/// Parameters
+/// ----------
+/// objs: list
+///     A list of objects to render. Each object must be an instance of TextPath, Box, Table, HStack, VStack, Grid, Pixel, or PixelGroup.
+///     Other objects can be rendered by giving them a `__textdraw__()` method which returns one of
+///     these objects or a list of them.
+/// default_style: str, optional
+///     A style to apply to empty space.
+/// bbox : BoundingBox or tuple of ints, optional
+///     The region to render (top, right, bottom, left). Pixels outside of it are clipped and empty
+///     space inside of it is padded, so the output has the same size wherever the content is.
+///     Defaults to the bounding box of all of the pixels.
+/// fit_terminal : bool, default=False
+///     If True, the output is clipped to the size of the terminal (leaving one line free for the
+///     prompt), starting from the top-left corner of the rendered region.
+/// scroll : tuple of ints, default=(0, 0)
+///     The number of columns and rows to scroll the terminal view right and down when
+///     `fit_terminal` is True. The view stops scrolling at the edges of the region.
+/// clip : Region or BoundingBox, optional
+///     If provided, only the cells inside this region are drawn.
 ///
-/// The arrow format is a direction ('up', 'right', 'down', or 'left')
-/// followed by an arrow type ('arrow', 'openarrow', or 'custom[<chars>]'),
-/// where the first of these lists will be used if either part is ommitted.
+/// Returns
+/// -------
+/// str
+///     The rendered string with ANSI escape codes. Rendering nothing without a `bbox` gives an
+///     empty string.
 ///
-/// Custom characters can be used by specifying them inside the 'custom[<chars>]'
-/// brackets, so 'custom[urdl]' would give unique letters for each direction and
-/// 'custom[X]' would use 'X' for every direction. Right now, this is not very
-/// useful, but it is the format which will be used in the TextPath class in a
-/// future update.
+/// Raises
+/// ------
+/// TypeError
+///     If an object in the list is not a TextPath, Box, Table, HStack, VStack, Grid, Pixel, or
+///     PixelGroup, and has no `__textdraw__` method.
+/// RecursionError
+///     If an object's `__textdraw__` method draws the object itself, directly or through other
+///     objects, or if `__textdraw__` methods are nested more than 64 deep.
+///
+/// Examples
+/// --------
+/// >>> from textdraw import render, text, Style
+/// >>> text = text("Hello", (0,0), "green")
+/// >>> render([text])
+/// '\x1b[32mHello\x1b[39m'
+/// >>> print(render([text], bbox=(0, 2, 0, 0)))
+/// Hel
+///
+#[pyfunction(signature = (objs, default_style = None, *, bbox = None, fit_terminal = false, scroll = (0, 0), clip = None))]
+fn render(
+    py: Python<'_>,
+    objs: Bound<'_, PyAny>,
+    default_style: Option<String>,
+    bbox: Option<Bound<'_, PyAny>>,
+    fit_terminal: bool,
+    scroll: (usize, usize),
+    clip: Option<Bound<'_, PyAny>>,
+) -> PyResult<String> {
+    let pixels = clipped_pixels(&objs, clip)?;
+    let mut region = match bbox {
+        Some(bb) => BoundingBox::extract_bound(&bb)?,
+        None if pixels.is_empty() => return Ok(String::new()),
+        None => pixels_to_bounding_box(&pixels),
+    };
+    if fit_terminal {
+        let (columns, lines): (usize, usize) = py
+            .import("shutil")?
+            .call_method0("get_terminal_size")?
+            .extract()?;
+        region = region.viewport(columns, lines.saturating_sub(1), scroll);
+    }
+    if region.is_empty() {
+        return Ok(String::new());
+    }
+    let canvas = Canvas::from_pixels(&pixels, region);
+    let empty_style: TextStyle = default_style
+        .map(|s| s.parse())
+        .transpose()?
+        .unwrap_or_default();
+    canvas.render(&empty_style)
+}
+
+/// Renders a list of objects into a plain form which is stable and easy to compare, for snapshot
+/// tests (see `textdraw.testing`).
+///
+/// The first line gives the size of the rendered region as "textdraw snapshot WIDTHxHEIGHT". It is
+/// followed by the characters of the region, a blank line, a map with the same layout giving the
+/// style of each cell, another blank line, and a legend of the style keys used in the map.
+/// Unstyled cells are marked with "." and empty cells with a space. Keys are assigned in reading
+/// order, and trailing spaces are trimmed from every line.
 ///
 /// Parameters
 /// ----------
-/// fmt : str
-///     The arrow format string.
+/// objs : list
+///     A list of objects to render, as for `render`.
+/// bbox : BoundingBox or tuple of ints, optional
+///     The region to render (top, right, bottom, left). Defaults to the bounding box of all of the
+///     pixels.
+/// clip : Region or BoundingBox, optional
+///     If provided, only the cells inside this region are drawn.
 ///
 /// Returns
 /// -------
 /// str
-///     The parsed arrow character.
+///     The snapshot.
+///
+/// Raises
+/// ------
+/// TypeError
+///     If an object in the list cannot be rendered.
 ///
 /// Examples
 /// --------
-/// >>> from textdraw import arrow
-/// >>> print(arrow("up arrow"))
-/// '▲'
+/// >>> from textdraw import Pixel, text
+/// >>> from textdraw.testing import snapshot
+/// >>> print(snapshot([text("Hi", (0, 1), "bold red"), Pixel("!", (1, 0))]))
+/// textdraw snapshot 2x2
+/// Hi
+///  !
+/// <BLANKLINE>
+/// AA
+///  .
+/// <BLANKLINE>
+/// A: bold red
 ///
-#[pyfunction]
-fn arrow(fmt: &str) -> PyResult<String> {
-    let all_arrow_types = ["openarrow", "arrow", "custom"];
-    let arrow_types_re = all_arrow_types.join("|");
-    let re = Regex::new(&format!(
-            r"^(?P<direction>(?:(up|right|down|left))\s*)?(?P<arrows>(?:({arrow_types_re}))|(custom\[(?P<customs>.{{4}})\])|(custom\[(?P<custom>.)\])\s*)?$"
-)).unwrap();
-    let mut direction = Direction::default();
-    let mut arrow = ArrowType::default();
-    if let Some(captures) = re.captures(fmt.to_lowercase().trim()) {
-        if let Some(matched_direction) = captures.name("direction") {
-            match matched_direction.as_str() {
-                "up" => direction = Direction::Up,
-                "right" => direction = Direction::Right,
-                "down" => direction = Direction::Down,
-                "left" => direction = Direction::Left,
-                _ => unreachable!(),
+#[pyfunction(signature = (objs, *, bbox = None, clip = None))]
+fn render_snapshot(
+    objs: Bound<'_, PyAny>,
+    bbox: Option<Bound<'_, PyAny>>,
+    clip: Option<Bound<'_, PyAny>>,
+) -> PyResult<String> {
+    let pixels = clipped_pixels(&objs, clip)?;
+    let region = match bbox {
+        Some(bb) => BoundingBox::extract_bound(&bb)?,
+        None => pixels_to_bounding_box(&pixels),
+    };
+    if region.is_empty() {
+        return Ok("textdraw snapshot 0x0\n".to_string());
+    }
+    Ok(Canvas::from_pixels(&pixels, region).snapshot())
+}
+/// Takes a list of objects (TextPath, Box, Table, HStack, VStack, Grid, Pixel, or PixelGroup) and
+/// duplicates them shifted by a given delta amount.
+///
+/// Parameters
+/// ----------
+/// objects : list
+///     A list of objects to duplicate. Each object must be an instance of TextPath, Box, Table, HStack, VStack, Grid, Pixel, or PixelGroup.
+/// delta : Point or tuple of ints, optional
+///     The (x, y) shift difference.
+///
+/// Returns
+/// -------
+/// objects : list
+///     Duplicated objects shifted by the given delta.
+///
+/// Raises
+/// ------
+/// TypeError
+///     If an object in the list is not a TextPath, Box, Table, HStack, VStack, Grid, Pixel, or
+///     PixelGroup.
+///
+#[pyfunction]
+fn duplicate_shifted(objects: Vec<Element>, delta: Bound<PyAny>) -> PyResult<Vec<Element>> {
+    let delta = Point::extract_bound(&delta)?;
+    Ok(objects
+        .iter()
+        .map(|obj| obj.duplicate_shifted(delta))
+        .collect())
+}
+
+/// Takes a list of objects (TextPath, Box, Table, HStack, VStack, Grid, Pixel, or PixelGroup) and
+/// duplicates them at a new location.
+///
+/// Parameters
+/// ----------
+/// objects : list
+///     A list of objects to duplicate. Each object must be an instance of TextPath, Box, Table, HStack, VStack, Grid, Pixel, or PixelGroup.
+///
+/// Returns
+/// -------
+/// objects : list
+///     Duplicated objects at the specified location.
+///
+/// Raises
+/// ------
+/// TypeError
+///     If an object in the list is not a TextPath, Box, Table, HStack, VStack, Grid, Pixel, or
+///     PixelGroup.
+///
+#[pyfunction(signature = (objects, position = None))]
+fn duplicate(objects: Vec<Element>, position: Option<Bound<PyAny>>) -> PyResult<Vec<Element>> {
+    let position = position
+        .map(|p| Point::extract_bound(&p))
+        .transpose()?
+        .unwrap_or_default();
+    Ok(objects.iter().map(|obj| obj.duplicate(position)).collect())
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Direction {
+    #[default]
+    Up,
+    Right,
+    Down,
+    Left,
+}
+impl Direction {
+    fn delta(self) -> Point {
+        match self {
+            Direction::Up => (0, 1),
+            Direction::Right => (1, 0),
+            Direction::Down => (0, -1),
+            Direction::Left => (-1, 0),
+        }
+        .into()
+    }
+
+    /// The direction of the step from `a` to an adjacent point `b`.
+    fn between(a: &Point, b: &Point) -> Option<Direction> {
+        Direction::all().into_iter().find(|d| a + d.delta() == *b)
+    }
+
+    fn opposite(self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Right => Direction::Left,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+        }
+    }
+
+    fn index(self) -> usize {
+        match self {
+            Direction::Up => 0,
+            Direction::Right => 1,
+            Direction::Down => 2,
+            Direction::Left => 3,
+        }
+    }
+
+    fn all() -> [Direction; 4] {
+        [
+            Direction::Up,
+            Direction::Right,
+            Direction::Down,
+            Direction::Left,
+        ]
+    }
+}
+impl Display for Direction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Direction::Up => "up",
+                Direction::Right => "right",
+                Direction::Down => "down",
+                Direction::Left => "left",
             }
+        )
+    }
+}
+impl FromStr for Direction {
+    type Err = PyErr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "up" => Ok(Direction::Up),
+            "right" => Ok(Direction::Right),
+            "down" => Ok(Direction::Down),
+            "left" => Ok(Direction::Left),
+            _ => Err(PyValueError::new_err("Invalid direction")),
         }
-        if let Some(matched_arrow) = captures.name("arrows") {
-            match matched_arrow.as_str() {
-                "arrow" => arrow = ArrowType::Arrow,
-                "openarrow" => arrow = ArrowType::OpenArrow,
-                _ => {
-                    if let Some(matched_custom) = captures.name("custom") {
-                        let custom_char = matched_custom.as_str().chars().next().unwrap();
-                        arrow = ArrowType::Custom {
-                            up: custom_char,
-                            right: custom_char,
-                            down: custom_char,
-                            left: custom_char,
-                        }
-                    } else if let Some(matched_customs) = captures.name("customs") {
-                        let custom_chars = matched_customs.as_str().chars().collect::<Vec<char>>();
-                        arrow = ArrowType::Custom {
-                            up: custom_chars[0],
-                            right: custom_chars[1],
-                            down: custom_chars[2],
-                            left: custom_chars[3],
-                        }
-                    } else {
-                        unreachable!()
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum LineStyle {
+    #[default]
+    Light,
+    Heavy,
+    Double,
+}
+impl LineStyle {
+    fn chars(&self) -> &'static str {
+        match self {
+            LineStyle::Light => " ─│┐──┌┬│┘│┤└┴├┼",
+            LineStyle::Heavy => " ━┃┓━━┏┳┃┛┃┫┗┻┣╋",
+            LineStyle::Double => " ═║╗══╔╦║╝║╣╚╩╠╬",
+        }
+    }
+    fn get_char(&self, neighbors: (bool, bool, bool, bool)) -> char {
+        let chars: Vec<char> = self.chars().chars().collect();
+        let index = (neighbors.0 as usize) << 3
+            | (neighbors.1 as usize) << 2
+            | (neighbors.2 as usize) << 1
+            | (neighbors.3 as usize);
+        chars[index]
+    }
+    /// Finds the line style and neighbors (up, right, down, left) of a line-drawing character.
+    ///
+    /// Lines which end in a cell use the same character as lines passing straight through it, so
+    /// the neighbors with the most links are returned.
+    fn decode(character: char) -> Option<(LineStyle, [bool; 4])> {
+        [LineStyle::Light, LineStyle::Heavy, LineStyle::Double]
+            .into_iter()
+            .flat_map(|style| {
+                style
+                    .chars()
+                    .chars()
+                    .enumerate()
+                    .skip(1)
+                    .filter(move |&(_, c)| c == character)
+                    .map(move |(index, _)| (style, index))
+            })
+            .max_by_key(|(_, index)| index.count_ones())
+            .map(|(style, index)| (style, [3, 2, 1, 0].map(|bit| index >> bit & 1 == 1)))
+    }
+}
+impl Display for LineStyle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                LineStyle::Light => "light",
+                LineStyle::Heavy => "heavy",
+                LineStyle::Double => "double",
+            }
+        )
+    }
+}
+impl FromStr for LineStyle {
+    type Err = PyErr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "light" => Ok(LineStyle::Light),
+            "heavy" => Ok(LineStyle::Heavy),
+            "double" => Ok(LineStyle::Double),
+            other => Err(PyValueError::new_err(format!(
+                "Invalid line style [{}]",
+                other
+            ))),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Crossing {
+    #[default]
+    Join,
+    Hop,
+    Gap,
+}
+impl Crossing {
+    /// Checks whether a path running straight through a cell crosses another path which also
+    /// runs straight through it without sharing any direction.
+    fn is_crossing(own: [bool; 4], other: [bool; 4]) -> bool {
+        let vertical = [true, false, true, false];
+        let horizontal = [false, true, false, true];
+        (own == vertical && other == horizontal) || (own == horizontal && other == vertical)
+    }
+}
+impl Display for Crossing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Crossing::Join => "join",
+                Crossing::Hop => "hop",
+                Crossing::Gap => "gap",
+            }
+        )
+    }
+}
+impl FromStr for Crossing {
+    type Err = PyErr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "join" => Ok(Crossing::Join),
+            "hop" => Ok(Crossing::Hop),
+            "gap" => Ok(Crossing::Gap),
+            other => Err(PyValueError::new_err(format!(
+                "Invalid crossing style [{}]",
+                other
+            ))),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum LabelPosition {
+    #[default]
+    Inline,
+    Beside,
+}
+impl Display for LabelPosition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                LabelPosition::Inline => "inline",
+                LabelPosition::Beside => "beside",
+            }
+        )
+    }
+}
+impl FromStr for LabelPosition {
+    type Err = PyErr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "inline" => Ok(LabelPosition::Inline),
+            "beside" => Ok(LabelPosition::Beside),
+            other => Err(PyValueError::new_err(format!(
+                "Invalid label position [{}]",
+                other
+            ))),
+        }
+    }
+}
+
+/// Finds the cells for a label along the straight segments of a path.
+///
+/// Segments are tried from longest to shortest, and positions within a segment from its middle
+/// outwards. An inline label replaces cells of the line (leaving at least one line cell on either
+/// side of it on horizontal segments), while a label beside the line sits above horizontal
+/// segments and to the right of vertical ones. A position is rejected if any of its cells is
+/// `occupied` or, unless it is breaking the line inline, covered by the path itself, or if the cells
+/// on either side of it are `occupied`. If there is no free position along the path, the label
+/// goes in the nearest free spot within its own length of the middle of the path, and if there is
+/// none, it is left out rather than drawn over other objects.
+fn place_label(
+    path: &[Point],
+    label: &str,
+    position: LabelPosition,
+    occupied: &HashSet<Point>,
+) -> Vec<(Point, char)> {
+    let chars: Vec<char> = label.chars().collect();
+    let n = chars.len() as isize;
+    if n == 0 || path.is_empty() {
+        return Vec::new();
+    }
+    let own: HashSet<Point> = path.iter().copied().collect();
+    let mut segments: Vec<(usize, usize)> = Vec::new();
+    let mut first = 0;
+    for i in 1..path.len() {
+        let straight = i + 1 < path.len()
+            && Direction::between(&path[i - 1], &path[i])
+                == Direction::between(&path[i], &path[i + 1]);
+        if !straight {
+            segments.push((first, i));
+            first = i;
+        }
+    }
+    // Stable, so equally long segments keep their order along the path.
+    segments.sort_by_key(|&(a, b)| std::cmp::Reverse(b - a));
+    let inline = position == LabelPosition::Inline;
+    let fits = |cells: &[Point]| {
+        let (first, last) = (cells[0], cells[cells.len() - 1]);
+        cells
+            .iter()
+            .all(|c| !occupied.contains(c) && (inline || !own.contains(c)))
+            && !occupied.contains(&(first - Point(1, 0)))
+            && !occupied.contains(&(last + Point(1, 0)))
+    };
+    for (a, b) in segments {
+        let horizontal = path[a].1 == path[b].1;
+        // The cells between the segment's corners, in increasing coordinate order.
+        let mut interior: Vec<Point> = path[a + 1..b].to_vec();
+        interior.sort_by_key(|p| (p.0, p.1));
+        if interior.is_empty() {
+            continue;
+        }
+        let mid = (interior.len() as isize - 1) / 2;
+        let mut offsets: Vec<isize> = (0..interior.len() as isize).collect();
+        offsets.sort_by_key(|o| (o - mid).abs());
+        for offset in offsets {
+            let anchor = interior[offset as usize];
+            let cells: Vec<Point> = match (horizontal, position) {
+                (true, _) => {
+                    let left = anchor.0 - n / 2;
+                    let y = anchor.1 + !inline as isize;
+                    let lo = interior[0].0 + inline as isize;
+                    let hi = interior[interior.len() - 1].0 - inline as isize;
+                    if left < lo || left + n - 1 > hi {
+                        continue;
                     }
+                    (0..n).map(|i| Point(left + i, y)).collect()
+                }
+                (false, LabelPosition::Inline) => (0..n)
+                    .map(|i| Point(anchor.0 - n / 2 + i, anchor.1))
+                    .collect(),
+                (false, LabelPosition::Beside) => {
+                    (0..n).map(|i| Point(anchor.0 + 1 + i, anchor.1)).collect()
+                }
+            };
+            // An inline label on a vertical segment may only break the line at its anchor.
+            let crossing_ok = horizontal || cells.iter().filter(|c| own.contains(c)).count() <= 1;
+            if crossing_ok && fits(&cells) {
+                return cells.into_iter().zip(chars.iter().copied()).collect();
+            }
+        }
+    }
+    // Otherwise, take the nearest free row of cells around the middle of the path, preferring
+    // spots above it.
+    let middle = path[path.len() / 2];
+    let reach = n.max(4);
+    let mut nearby: Vec<(isize, isize)> =
+        (-reach..=reach).cartesian_product(-reach..=reach).collect();
+    nearby.sort_by_key(|&(dx, dy)| (dx.abs().max(dy.abs()), dy.abs(), dy < 0, dx.abs(), dx < 0));
+    nearby
+        .into_iter()
+        .map(|(dx, dy)| {
+            (0..n)
+                .map(|i| Point(middle.0 - n / 2 + dx + i, middle.1 + dy))
+                .collect::<Vec<_>>()
+        })
+        .find(|cells| cells.iter().all(|c| !own.contains(c)) && fits(cells))
+        .map(|cells| cells.into_iter().zip(chars).collect())
+        .unwrap_or_default()
+}
+
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ArrowType {
+    #[default]
+    Arrow,
+    OpenArrow,
+    Custom {
+        up: char,
+        right: char,
+        down: char,
+        left: char,
+    },
+}
+impl ArrowType {
+    /// Finds the arrow type and direction of a built-in arrowhead character.
+    fn decode(character: char) -> Option<(ArrowType, Direction)> {
+        [ArrowType::Arrow, ArrowType::OpenArrow]
+            .into_iter()
+            .flat_map(|arrow| Direction::all().map(|direction| (arrow, direction)))
+            .find(|(arrow, direction)| arrow.render(direction).starts_with(character))
+    }
+    fn render(&self, direction: &Direction) -> String {
+        match self {
+            ArrowType::Arrow => match direction {
+                Direction::Up => "▲",
+                Direction::Right => "▶",
+                Direction::Down => "▼",
+                Direction::Left => "◀",
+            }
+            .to_string(),
+            ArrowType::OpenArrow => match direction {
+                Direction::Up => "△",
+                Direction::Right => "▷",
+                Direction::Down => "▽",
+                Direction::Left => "◁",
+            }
+            .to_string(),
+            ArrowType::Custom {
+                up,
+                right,
+                down,
+                left,
+            } => match direction {
+                Direction::Up => up,
+                Direction::Right => right,
+                Direction::Down => down,
+                Direction::Left => left,
+            }
+            .to_string(),
+        }
+    }
+}
+
+/// Parses a string to generate an arrow-like character
+///
+/// This is intended to be used with the Pixel class to render a
+/// styled arrow. Eventually the TextPath class will support arrow
+/// endings in a more convenient manner, but for now, they must be
+/// placed manually.
+///
+/// The arrow format is a direction ('up', 'right', 'down', or 'left')
+/// followed by an arrow type ('arrow', 'openarrow', or 'custom[<chars>]'),
+/// where the first of these lists will be used if either part is ommitted.
+///
+/// Custom characters can be used by specifying them inside the 'custom[<chars>]'
+/// brackets, so 'custom[urdl]' would give unique letters for each direction and
+/// 'custom[X]' would use 'X' for every direction. Right now, this is not very
+/// useful, but it is the format which will be used in the TextPath class in a
+/// future update.
+///
+/// Parameters
+/// ----------
+/// fmt : str
+///     The arrow format string.
+///
+/// Returns
+/// -------
+/// str
+///     The parsed arrow character.
+///
+/// Examples
+/// --------
+/// >>> from textdraw import arrow
+/// >>> print(arrow("up arrow"))
+/// '▲'
+///
+#[pyfunction]
+fn arrow(fmt: &str) -> PyResult<String> {
+    let (direction, arrow) = parse_arrow(fmt)?;
+    Ok(arrow.render(&direction))
+}
+
+/// Parses an arrow format string (see `arrow`) into its direction and arrow type.
+fn parse_arrow(fmt: &str) -> PyResult<(Direction, ArrowType)> {
+    let all_arrow_types = ["openarrow", "arrow", "custom"];
+    let arrow_types_re = all_arrow_types.join("|");
+    let re = Regex::new(&format!(
+            r"^(?P<direction>(?:(up|right|down|left))\s*)?(?P<arrows>(?:({arrow_types_re}))|(custom\[(?P<customs>.{{4}})\])|(custom\[(?P<custom>.)\])\s*)?$"
+)).unwrap();
+    let mut direction = Direction::default();
+    let mut arrow = ArrowType::default();
+    if let Some(captures) = re.captures(fmt.to_lowercase().trim()) {
+        if let Some(matched_direction) = captures.name("direction") {
+            match matched_direction.as_str() {
+                "up" => direction = Direction::Up,
+                "right" => direction = Direction::Right,
+                "down" => direction = Direction::Down,
+                "left" => direction = Direction::Left,
+                _ => unreachable!(),
+            }
+        }
+        if let Some(matched_arrow) = captures.name("arrows") {
+            match matched_arrow.as_str() {
+                "arrow" => arrow = ArrowType::Arrow,
+                "openarrow" => arrow = ArrowType::OpenArrow,
+                _ => {
+                    if let Some(matched_custom) = captures.name("custom") {
+                        let custom_char = matched_custom.as_str().chars().next().unwrap();
+                        arrow = ArrowType::Custom {
+                            up: custom_char,
+                            right: custom_char,
+                            down: custom_char,
+                            left: custom_char,
+                        }
+                    } else if let Some(matched_customs) = captures.name("customs") {
+                        let custom_chars = matched_customs.as_str().chars().collect::<Vec<char>>();
+                        arrow = ArrowType::Custom {
+                            up: custom_chars[0],
+                            right: custom_chars[1],
+                            down: custom_chars[2],
+                            left: custom_chars[3],
+                        }
+                    } else {
+                        unreachable!()
+                    }
+                }
+            }
+        }
+        return Ok((direction, arrow));
+    }
+    Err(PyValueError::new_err("Failed to parse style string"))
+}
+
+/// A rearrangement of the cells of a drawing which redraws line and arrow characters to match.
+#[derive(Clone, Copy)]
+enum Transform {
+    /// Flips x-coordinates (`Axis::X`) or y-coordinates (`Axis::Y`).
+    Mirror(Axis),
+    /// Rotates by a number of quarter turns counterclockwise.
+    Rotate(isize),
+    /// Swaps rows and columns, reflecting across the diagonal from the top-left corner.
+    Transpose,
+    /// Stretches by a number of cells horizontally and vertically, extending lines to match.
+    Scale(usize, usize),
+}
+impl Transform {
+    fn point(self, p: Point) -> Point {
+        match self {
+            Transform::Mirror(Axis::X) => Point(-p.0, p.1),
+            Transform::Mirror(Axis::Y) => Point(p.0, -p.1),
+            Transform::Rotate(turns) => match turns.rem_euclid(4) {
+                0 => p,
+                1 => Point(-p.1, p.0),
+                2 => Point(-p.0, -p.1),
+                _ => Point(p.1, -p.0),
+            },
+            Transform::Transpose => Point(-p.1, -p.0),
+            Transform::Scale(x, y) => Point(p.0 * x as isize, p.1 * y as isize),
+        }
+    }
+    fn direction(self, direction: Direction) -> Direction {
+        let Point(x, y) = self.point(direction.delta());
+        Direction::between(&Point(0, 0), &Point(x.signum(), y.signum()))
+            .expect("transforms map directions onto directions")
+    }
+    fn links(self, links: [bool; 4]) -> [bool; 4] {
+        let mut transformed = [false; 4];
+        for direction in Direction::all() {
+            transformed[self.direction(direction).index()] = links[direction.index()];
+        }
+        transformed
+    }
+    /// The character drawn in place of `character`. Characters which are not lines or built-in
+    /// arrowheads are left as they are.
+    fn character(self, character: char) -> char {
+        if let Some((style, links)) = LineStyle::decode(character) {
+            let [up, right, down, left] = self.links(links);
+            style.get_char((up, right, down, left))
+        } else if let Some((arrow, direction)) = ArrowType::decode(character) {
+            arrow
+                .render(&self.direction(direction))
+                .chars()
+                .next()
+                .unwrap_or(character)
+        } else {
+            character
+        }
+    }
+    /// Transforms a set of pixels, placing the result so that its corner (see
+    /// `Coordinates::corner`) is at `position`, or where the original corner was.
+    ///
+    /// Blocks of text (see `text_blocks`) are moved without being turned, centered on the cells
+    /// they would have covered, so that they stay readable. They do not cover lines, and text
+    /// enclosed by lines (like the text of a Box) is clipped to the enclosed cells. Text drawn over
+    /// a line (see `covered_lines`) is the exception: the line is drawn again under it, and the
+    /// text is placed over the line if the line is still horizontal, or left along it otherwise.
+    fn apply(self, pixels: &[Pixel], position: Option<Point>) -> PixelGroup {
+        let covered = covered_lines(pixels);
+        let mut transformed: HashMap<Point, Pixel> = HashMap::new();
+        for pixel in pixels {
+            // Spaces over a line are replaced by the line, while other text stays where it is
+            // until it is moved below.
+            let pixel = match covered.get(&pixel.position) {
+                Some(line) if pixel.character == ' ' => line,
+                _ => pixel,
+            };
+            let position = self.point(pixel.position);
+            transformed.insert(
+                position,
+                Pixel {
+                    character: self.character(pixel.character),
+                    position,
+                    ..pixel.clone()
+                },
+            );
+        }
+        if let Transform::Scale(x, y) = self {
+            // Fill in the cells between the stretched lines, including the lines under text.
+            for pixel in pixels {
+                let pixel = covered.get(&pixel.position).unwrap_or(pixel);
+                let Some((style, [up, right, ..])) = LineStyle::decode(pixel.character) else {
+                    continue;
+                };
+                let origin = self.point(pixel.position);
+                let gaps = (1..x as isize)
+                    .filter(|_| right)
+                    .map(|i| (Point(origin.0 + i, origin.1), (false, true, false, true)))
+                    .chain(
+                        (1..y as isize)
+                            .filter(|_| up)
+                            .map(|i| (Point(origin.0, origin.1 + i), (true, false, true, false))),
+                    );
+                for (position, links) in gaps {
+                    transformed.entry(position).or_insert_with(|| Pixel {
+                        character: style.get_char(links),
+                        position,
+                        ..pixel.clone()
+                    });
+                }
+            }
+        }
+        let turned = matches!(
+            self.direction(Direction::Right),
+            Direction::Up | Direction::Down
+        );
+        let original_bbox = pixels_to_bounding_box(pixels);
+        let original_lines: HashSet<Point> = pixels
+            .iter()
+            .filter(|p| LineStyle::decode(p.character).is_some())
+            .map(|p| p.position)
+            .chain(covered.keys().copied())
+            .collect();
+        let covered_targets: HashSet<Point> = covered.keys().map(|&p| self.point(p)).collect();
+        let transformed_bbox = pixels_to_bounding_box(transformed.values());
+        // Text over a line is kept apart from the text next to it (like the title of a Box and the
+        // first line of its text), and single characters over a line (like a one-letter title) are
+        // moved like blocks too, so that they keep the spaces around them.
+        let (over_lines, others): (Vec<Pixel>, Vec<Pixel>) = pixels
+            .iter()
+            .cloned()
+            .partition(|p| covered.contains_key(&p.position));
+        let mut blocks = text_blocks(&others);
+        blocks.extend(text_blocks(&over_lines));
+        let in_blocks: HashSet<Point> = blocks.iter().flatten().map(|p| p.position).collect();
+        blocks.extend(
+            pixels
+                .iter()
+                .filter(|p| {
+                    p.character != ' '
+                        && covered.contains_key(&p.position)
+                        && !in_blocks.contains(&p.position)
+                })
+                .map(|p| vec![p.clone()]),
+        );
+        for block in blocks {
+            let on_line = block.iter().all(|p| covered.contains_key(&p.position));
+            if on_line && turned {
+                // Text along a line which is now vertical reads from top to bottom.
+                let targets = block
+                    .iter()
+                    .map(|p| self.point(p.position))
+                    .sorted_by_key(|p| (-p.1, p.0));
+                let characters = block
+                    .iter()
+                    .sorted_by_key(|p| (-p.position.1, p.position.0));
+                for (position, pixel) in targets.zip(characters) {
+                    transformed.insert(
+                        position,
+                        Pixel {
+                            position,
+                            ..pixel.clone()
+                        },
+                    );
+                }
+                continue;
+            }
+            for pixel in &block {
+                let pixel = covered.get(&pixel.position).unwrap_or(pixel);
+                let position = self.point(pixel.position);
+                transformed.insert(
+                    position,
+                    Pixel {
+                        character: if on_line {
+                            self.character(pixel.character)
+                        } else {
+                            ' '
+                        },
+                        position,
+                        ..pixel.clone()
+                    },
+                );
+            }
+            // Centers are measured in half cells to keep them integral.
+            let bb = pixels_to_bounding_box(&block);
+            let corners =
+                [Point(bb.left, bb.bottom), Point(bb.right, bb.top)].map(|p| self.point(p));
+            let shift = Point(
+                (corners[0].0 + corners[1].0 - bb.left - bb.right).div_euclid(2),
+                (corners[0].1 + corners[1].1 - bb.bottom - bb.top).div_euclid(2),
+            );
+            let clipped = enclosed(|p| original_lines.contains(&p), original_bbox, bb.center());
+            let mut placed = block.clone();
+            if on_line {
+                // Keep the spaces on either side of text over a line.
+                placed.extend(
+                    [
+                        Point(bb.left - 1, bb.bottom),
+                        Point(bb.right + 1, bb.bottom),
+                    ]
+                    .into_iter()
+                    .filter_map(|p| pixels.iter().rfind(|pixel| pixel.position == p))
+                    .filter(|p| p.character == ' ' && covered.contains_key(&p.position))
+                    .cloned(),
+                );
+            }
+            for pixel in placed {
+                let position = pixel.position + shift;
+                let is_line = |p: Point| {
+                    covered_targets.contains(&p)
+                        || transformed
+                            .get(&p)
+                            .is_some_and(|p| LineStyle::decode(p.character).is_some())
+                };
+                let fits = if clipped {
+                    enclosed(is_line, transformed_bbox, position)
+                } else {
+                    !is_line(position)
+                };
+                if on_line || fits {
+                    transformed.insert(position, pixel.duplicate_shifted(shift));
+                }
+            }
+        }
+        let shift = if pixels.is_empty() {
+            Point::default()
+        } else {
+            let corner = Coordinates::corner(pixels_to_bounding_box(pixels));
+            position.unwrap_or(corner)
+                - Coordinates::corner(pixels_to_bounding_box(transformed.values()))
+        };
+        PixelGroup {
+            pixels: transformed
+                .into_values()
+                .map(|pixel| pixel.duplicate_shifted(shift))
+                .collect(),
+            position: Point::default(),
+            style: TextStyle::default(),
+            weight: Some(0),
+        }
+    }
+}
+
+/// The text cells drawn over a line, like the title of a Box over its top border, mapped to the
+/// line which would be drawn there without the text. A cell counts if it is part of a row (or
+/// column) of cells which are not lines, and which is between the ends of a line on both sides.
+fn covered_lines(pixels: &[Pixel]) -> HashMap<Point, Pixel> {
+    let cells: HashMap<Point, &Pixel> = pixels.iter().map(|p| (p.position, p)).collect();
+    let mut covered = HashMap::new();
+    for pixel in cells.values() {
+        let Some((style, links)) = LineStyle::decode(pixel.character) else {
+            continue;
+        };
+        for (direction, straight) in [
+            (Direction::Right, (false, true, false, true)),
+            (Direction::Up, (true, false, true, false)),
+        ] {
+            if !links[direction.index()] {
+                continue;
+            }
+            let mut under = Vec::new();
+            let mut position = pixel.position + direction.delta();
+            while let Some(cell) = cells.get(&position) {
+                match LineStyle::decode(cell.character) {
+                    Some((_, links)) => {
+                        if links[direction.opposite().index()] {
+                            covered.extend(under.drain(..).map(|position| {
+                                let line = Pixel {
+                                    character: style.get_char(straight),
+                                    position,
+                                    ..(*pixel).clone()
+                                };
+                                (position, line)
+                            }));
+                        }
+                        break;
+                    }
+                    None if ArrowType::decode(cell.character).is_none() => under.push(position),
+                    None => break,
+                }
+                position += direction.delta();
+            }
+        }
+    }
+    covered
+}
+
+/// Whether a cell is not a line, but has a line somewhere in each direction within `bbox`, like
+/// the inside of a Box.
+fn enclosed(is_line: impl Fn(Point) -> bool, bbox: BoundingBox, cell: Point) -> bool {
+    !is_line(cell)
+        && Direction::all().into_iter().all(|direction| {
+            let mut p = cell + direction.delta();
+            while bbox.contains_point(&p) {
+                if is_line(p) {
+                    return true;
+                }
+                p += direction.delta();
+            }
+            false
+        })
+}
+
+/// The blocks of text in a set of pixels, which transforms move without turning them.
+///
+/// A block is made up of runs of two or more cells in a row which are neither lines nor arrowheads
+/// (without any spaces at either end), along with the runs overlapping them in the rows above and
+/// below, so that the lines of a multi-line text stay in order. Later pixels cover earlier ones, as
+/// when rendered.
+fn text_blocks(pixels: &[Pixel]) -> Vec<Vec<Pixel>> {
+    let cells: HashMap<Point, &Pixel> = pixels.iter().map(|p| (p.position, p)).collect();
+    let mut runs: Vec<Vec<Pixel>> = Vec::new();
+    let text = cells
+        .into_values()
+        .filter(|p| {
+            LineStyle::decode(p.character).is_none() && ArrowType::decode(p.character).is_none()
+        })
+        .sorted_by_key(|p| (p.position.1, p.position.0));
+    for pixel in text {
+        match runs.last_mut() {
+            Some(run) if run[run.len() - 1].position + Point(1, 0) == pixel.position => {
+                run.push(pixel.clone())
+            }
+            _ => runs.push(vec![pixel.clone()]),
+        }
+    }
+    let runs = runs
+        .into_iter()
+        .filter_map(|run| {
+            let start = run.iter().position(|p| p.character != ' ')?;
+            let end = run.iter().rposition(|p| p.character != ' ')?;
+            (end > start).then(|| run[start..=end].to_vec())
+        })
+        .collect_vec();
+    // Runs are sorted by row, so each run only needs to be compared with the runs before it.
+    let mut block_of: Vec<usize> = (0..runs.len()).collect();
+    for (i, run) in runs.iter().enumerate() {
+        let (row, left, right) = (
+            run[0].position.1,
+            run[0].position.0,
+            run[run.len() - 1].position.0,
+        );
+        for (j, other) in runs[..i].iter().enumerate() {
+            if other[0].position.1 == row - 1
+                && other[0].position.0 <= right
+                && other[other.len() - 1].position.0 >= left
+            {
+                let (from, to) = (block_of[i].max(block_of[j]), block_of[i].min(block_of[j]));
+                block_of
+                    .iter_mut()
+                    .filter(|b| **b == from)
+                    .for_each(|b| *b = to);
+            }
+        }
+    }
+    runs.into_iter()
+        .zip(block_of)
+        .into_group_map_by(|(_, block)| *block)
+        .into_iter()
+        .sorted_by_key(|(block, _)| *block)
+        .map(|(_, runs)| runs.into_iter().flat_map(|(run, _)| run).collect())
+        .collect()
+}
+
+/// Mirrors a list of objects, redrawing lines and arrowheads to match.
+///
+/// Text (like the text and title of a Box, or the label of a path) is moved as a whole, so it still
+/// reads from left to right.
+///
+/// Parameters
+/// ----------
+/// objs : list
+///     The objects to mirror (TextPath, Box, Table, HStack, VStack, Grid, Pixel, or PixelGroup).
+/// direction : {'horizontal', 'vertical'}, default='horizontal'
+///     Whether to swap left and right ('horizontal') or top and bottom ('vertical').
+/// position : Point or tuple of ints, optional
+///     The position of the result (bottom-left corner, or top-left in screen coordinates).
+///     Defaults to the position of the original objects.
+///
+/// Returns
+/// -------
+/// PixelGroup
+///     The mirrored objects.
+///
+/// Examples
+/// --------
+/// >>> from textdraw import Pixel, TextPath, arrow, mirror, render
+/// >>> path = TextPath((0, 0), (4, 2))
+/// >>> head = Pixel(arrow('right'), (5, 2))
+/// >>> print(render([path, head]))
+///     │▶
+///     │
+/// ────┘
+/// >>> print(render([mirror([path, head])]))
+/// ◀│
+///  │
+///  └────
+///
+#[pyfunction(signature = (objs, direction = "horizontal", position = None))]
+fn mirror(
+    objs: Bound<'_, PyAny>,
+    direction: &str,
+    position: Option<Bound<'_, PyAny>>,
+) -> PyResult<PixelGroup> {
+    let axis = match direction.to_lowercase().as_str() {
+        "horizontal" => Axis::X,
+        "vertical" => Axis::Y,
+        _ => {
+            return Err(PyValueError::new_err(format!(
+                "Invalid mirror direction [{}]",
+                direction
+            )))
+        }
+    };
+    transform(&objs, Transform::Mirror(axis), position)
+}
+
+/// Rotates a list of objects by quarter turns, redrawing lines and arrowheads to match.
+///
+/// Text (like the text of a Box) is not turned, so it still reads from left to right. It is
+/// centered on the cells it would have covered without covering any lines, and text inside a Box
+/// is cut off at its border. Text over a line (like the title of a Box, or the label of a path)
+/// stays on the line, reading from top to bottom if the line is now vertical.
+///
+/// Parameters
+/// ----------
+/// objs : list
+///     The objects to rotate (TextPath, Box, Table, HStack, VStack, Grid, Pixel, or PixelGroup).
+/// turns : int, default=1
+///     The number of quarter turns counterclockwise (negative values turn clockwise).
+/// position : Point or tuple of ints, optional
+///     The position of the result (bottom-left corner, or top-left in screen coordinates).
+///     Defaults to the position of the original objects.
+///
+/// Returns
+/// -------
+/// PixelGroup
+///     The rotated objects.
+///
+#[pyfunction(signature = (objs, turns = 1, position = None))]
+fn rotate(
+    objs: Bound<'_, PyAny>,
+    turns: isize,
+    position: Option<Bound<'_, PyAny>>,
+) -> PyResult<PixelGroup> {
+    transform(&objs, Transform::Rotate(turns), position)
+}
+
+/// Swaps the rows and columns of a list of objects, redrawing lines and arrowheads to match.
+///
+/// Text is not turned, as with `rotate`.
+///
+/// Parameters
+/// ----------
+/// objs : list
+///     The objects to transpose (TextPath, Box, Table, HStack, VStack, Grid, Pixel, or
+///     PixelGroup).
+/// position : Point or tuple of ints, optional
+///     The position of the result (bottom-left corner, or top-left in screen coordinates).
+///     Defaults to the position of the original objects.
+///
+/// Returns
+/// -------
+/// PixelGroup
+///     The transposed objects.
+///
+#[pyfunction(signature = (objs, position = None))]
+fn transpose(objs: Bound<'_, PyAny>, position: Option<Bound<'_, PyAny>>) -> PyResult<PixelGroup> {
+    transform(&objs, Transform::Transpose, position)
+}
+
+/// Stretches a list of objects by whole cells, extending lines to fill the gaps.
+///
+/// Text is not spread out, but centered on the cells it would stretch across. Lines under text
+/// (like the border under the title of a Box) are stretched as if the text was not there.
+///
+/// Parameters
+/// ----------
+/// objs : list
+///     The objects to scale (TextPath, Box, Table, HStack, VStack, Grid, Pixel, or PixelGroup).
+/// factor : int or tuple of ints
+///     The horizontal and vertical scale factors, or a single factor for both.
+/// position : Point or tuple of ints, optional
+///     The position of the result (bottom-left corner, or top-left in screen coordinates).
+///     Defaults to the position of the original objects.
+///
+/// Returns
+/// -------
+/// PixelGroup
+///     The scaled objects.
+///
+/// Raises
+/// ------
+/// ValueError
+///     If a scale factor is zero.
+///
+#[pyfunction(signature = (objs, factor, position = None))]
+fn scale(
+    objs: Bound<'_, PyAny>,
+    factor: Bound<'_, PyAny>,
+    position: Option<Bound<'_, PyAny>>,
+) -> PyResult<PixelGroup> {
+    let (x, y) = if let Ok(factor) = factor.extract::<usize>() {
+        (factor, factor)
+    } else {
+        factor.extract::<(usize, usize)>()?
+    };
+    if x == 0 || y == 0 {
+        return Err(PyValueError::new_err("Scale factors must be positive"));
+    }
+    transform(&objs, Transform::Scale(x, y), position)
+}
+
+fn transform(
+    objs: &Bound<'_, PyAny>,
+    transform: Transform,
+    position: Option<Bound<'_, PyAny>>,
+) -> PyResult<PixelGroup> {
+    let position = position.map(|p| Point::extract_bound(&p)).transpose()?;
+    Ok(transform.apply(&objs_to_pixels(objs)?, position))
+}
+
+#[derive(Eq, PartialEq)]
+struct State {
+    cost: usize,
+    pos: Point,
+    dir: Option<Direction>,
+}
+impl Ord for State {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.cmp(&self.cost)
+    }
+}
+/// The search state a cell was reached from, indexed by the direction used to enter it.
+type CameFrom = [Option<(Point, Option<Direction>)>; 4];
+impl PartialOrd for State {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Runs an A* search from `start` to `end` over the given weights (`None` cells are impassible).
+///
+/// `initial_dir` is the direction the path was already travelling in at `start`, so that turning
+/// immediately is penalized. Returns the cells of the path, its cost, and the direction of travel
+/// on arrival, or the cells which were explored if no path exists.
+fn find_path(
+    start: Point,
+    end: Point,
+    initial_dir: Option<Direction>,
+    weights: &Grid<Option<usize>>,
+    bend_penalty: usize,
+    heuristic_scale: usize,
+) -> Result<(Vec<Point>, usize, Option<Direction>), Vec<Point>> {
+    let bb = weights.bbox;
+    let mut heap = BinaryHeap::new();
+    let mut came_from: Grid<CameFrom> = Grid::new(bb, [None; 4]);
+    let mut cost_so_far = Grid::new(bb, usize::MAX);
+    let mut visited = Grid::new(bb, false);
+    let mut explored = Vec::new();
+
+    heap.push(State {
+        cost: 0,
+        pos: start,
+        dir: initial_dir,
+    });
+    cost_so_far.set(&start, 0);
+    while let Some(State { cost, pos, dir }) = heap.pop() {
+        if visited.get(&pos).is_some_and(|v| !v) {
+            visited.set(&pos, true);
+            explored.push(pos);
+        }
+        if pos == end {
+            let mut path = vec![pos];
+            let mut current = (pos, dir);
+            while let Some(&(previous_pos, previous_dir)) = current
+                .1
+                .and_then(|d| came_from.get(&current.0)?[d.index()].as_ref())
+            {
+                path.push(previous_pos);
+                current = (previous_pos, previous_dir);
+            }
+            path.reverse();
+            return Ok((path, cost, dir));
+        }
+
+        for new_dir in Direction::all() {
+            let delta = new_dir.delta();
+            let next = pos + delta;
+            let weight = match weights.get(&next) {
+                None | Some(None) => continue,
+                Some(Some(w)) => *w,
+            };
+            let bend_cost = if Some(new_dir) != dir && dir.is_some() {
+                bend_penalty
+            } else {
+                0
+            };
+            let new_cost = cost + weight + bend_cost;
+            let heuristic = |pos: Point| -> usize {
+                ((end.0 - pos.0).abs() + (end.1 - pos.1).abs()) as usize * heuristic_scale
+            };
+            let entry = cost_so_far
+                .get_mut(&next)
+                .expect("neighbor is inside the search grid");
+            if new_cost < *entry {
+                *entry = new_cost;
+                heap.push(State {
+                    cost: new_cost + heuristic(next),
+                    pos: next,
+                    dir: Some(new_dir),
+                });
+                if let Some(from) = came_from.get_mut(&next) {
+                    from[new_dir.index()] = Some((pos, dir));
+                }
+            }
+        }
+    }
+    Err(explored)
+}
+
+/// Runs the search in two levels: first over the channels between the given boxes, then cell by cell
+/// inside the channels chosen by the first search.
+///
+/// The grid is split along the edges of every box (and at the ends of the route), so the number of
+/// channels depends on the number of boxes rather than on the size of the grid. A channel counts as
+/// impassible if its first cell is. If no channels lead to the end or the refined search fails, the
+/// whole grid is searched instead.
+fn find_channel_path(
+    start: Point,
+    end: Point,
+    initial_dir: Option<Direction>,
+    weights: &Grid<Option<usize>>,
+    boxes: &[BoundingBox],
+    bend_penalty: usize,
+    heuristic_scale: usize,
+) -> Result<(Vec<Point>, usize, Option<Direction>), Vec<Point>> {
+    let bb = weights.bbox;
+    let blocked = |x: isize, y: isize| matches!(weights.get(&Point(x, y)), Some(None));
+    let mut xs = vec![
+        bb.left,
+        bb.right + 1,
+        start.0,
+        start.0 + 1,
+        end.0,
+        end.0 + 1,
+    ];
+    let mut ys = vec![
+        bb.bottom,
+        bb.top + 1,
+        start.1,
+        start.1 + 1,
+        end.1,
+        end.1 + 1,
+    ];
+    for b in boxes {
+        xs.extend([b.left, b.right + 1].map(|x| x.clamp(bb.left, bb.right + 1)));
+        ys.extend([b.bottom, b.top + 1].map(|y| y.clamp(bb.bottom, bb.top + 1)));
+    }
+    xs.sort_unstable();
+    xs.dedup();
+    ys.sort_unstable();
+    ys.dedup();
+    let channel_of = |p: Point| {
+        (
+            xs.partition_point(|&x| x <= p.0) - 1,
+            ys.partition_point(|&y| y <= p.1) - 1,
+        )
+    };
+    // Channel centers are measured in half cells to keep them integral.
+    let center = |(i, j): (usize, usize)| (xs[i] + xs[i + 1] - 1, ys[j] + ys[j + 1] - 1);
+    let (source, target) = (channel_of(start), channel_of(end));
+    let (columns, rows) = (xs.len() - 1, ys.len() - 1);
+    let mut cost_so_far = vec![usize::MAX; columns * rows];
+    let mut came_from = vec![None; columns * rows];
+    let mut heap = BinaryHeap::new();
+    cost_so_far[source.1 * columns + source.0] = 0;
+    heap.push(std::cmp::Reverse((0, source)));
+    while let Some(std::cmp::Reverse((cost, channel))) = heap.pop() {
+        if channel == target || cost > cost_so_far[channel.1 * columns + channel.0] {
+            continue;
+        }
+        let (i, j) = channel;
+        let neighbors = [
+            (j + 1 < rows).then(|| (i, j + 1)),
+            (i + 1 < columns).then(|| (i + 1, j)),
+            j.checked_sub(1).map(|j| (i, j)),
+            i.checked_sub(1).map(|i| (i, j)),
+        ];
+        for next in neighbors.into_iter().flatten() {
+            if next != target && blocked(xs[next.0], ys[next.1]) {
+                continue;
+            }
+            let (a, b) = (center(channel), center(next));
+            let new_cost = cost + ((a.0 - b.0).abs() + (a.1 - b.1).abs()) as usize;
+            let index = next.1 * columns + next.0;
+            if new_cost < cost_so_far[index] {
+                cost_so_far[index] = new_cost;
+                came_from[index] = Some(channel);
+                heap.push(std::cmp::Reverse((new_cost, next)));
+            }
+        }
+    }
+    if cost_so_far[target.1 * columns + target.0] == usize::MAX {
+        return find_path(
+            start,
+            end,
+            initial_dir,
+            weights,
+            bend_penalty,
+            heuristic_scale,
+        );
+    }
+    let mut corridor = Grid::new(bb, None);
+    let mut current = Some(target);
+    while let Some((i, j)) = current {
+        for y in ys[j]..ys[j + 1] {
+            for x in xs[i]..xs[i + 1] {
+                let cell = Point(x, y);
+                corridor.set(&cell, weights.get(&cell).copied().flatten());
+            }
+        }
+        current = came_from[j * columns + i];
+    }
+    find_path(
+        start,
+        end,
+        initial_dir,
+        &corridor,
+        bend_penalty,
+        heuristic_scale,
+    )
+    .or_else(|_| {
+        find_path(
+            start,
+            end,
+            initial_dir,
+            weights,
+            bend_penalty,
+            heuristic_scale,
+        )
+    })
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+enum Router {
+    #[default]
+    Grid,
+    Channel,
+}
+impl Router {
+    fn find_path(
+        self,
+        start: Point,
+        end: Point,
+        initial_dir: Option<Direction>,
+        weights: &Grid<Option<usize>>,
+        boxes: &[BoundingBox],
+        bend_penalty: usize,
+        heuristic_scale: usize,
+    ) -> Result<(Vec<Point>, usize, Option<Direction>), Vec<Point>> {
+        match self {
+            Router::Grid => find_path(
+                start,
+                end,
+                initial_dir,
+                weights,
+                bend_penalty,
+                heuristic_scale,
+            ),
+            Router::Channel => find_channel_path(
+                start,
+                end,
+                initial_dir,
+                weights,
+                boxes,
+                bend_penalty,
+                heuristic_scale,
+            ),
+        }
+    }
+}
+impl Display for Router {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Router::Grid => "grid",
+                Router::Channel => "channel",
+            }
+        )
+    }
+}
+impl FromStr for Router {
+    type Err = PyErr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "grid" => Ok(Router::Grid),
+            "channel" => Ok(Router::Channel),
+            other => Err(PyValueError::new_err(format!("Invalid router [{}]", other))),
+        }
+    }
+}
+
+/// The cells leading straight out of the obstacles which cover `port`.
+///
+/// These cells stay open so that a path can leave or reach a port inside a box. If no direction is
+/// given, the shortest way out is used.
+fn port_cells(
+    port: Point,
+    direction: Option<Direction>,
+    obstacles: &[BoundingBox],
+    bb: BoundingBox,
+) -> Vec<Point> {
+    let corridor = |d: Direction| {
+        let mut cells = Vec::new();
+        let mut current = port;
+        while bb.contains_point(&current) && obstacles.iter().any(|o| o.contains_point(&current)) {
+            cells.push(current);
+            current += d.delta();
+        }
+        cells
+    };
+    match direction {
+        Some(d) => corridor(d),
+        None => Direction::all()
+            .into_iter()
+            .map(corridor)
+            .min_by_key(|cells| cells.len())
+            .unwrap_or_default(),
+    }
+}
+
+/// The cells covered by any of the objects a path was routed around, which labels must not cover.
+fn occupied_cells(
+    environment: &[Pixel],
+    barriers: &[Pixel],
+    paths: &[Vec<Pixel>],
+) -> HashSet<Point> {
+    environment
+        .iter()
+        .chain(barriers)
+        .chain(paths.iter().flatten())
+        .map(|p| p.position)
+        .collect()
+}
+
+/// Maps each cell covered by the given paths to the indices of the paths which cover it.
+fn path_identities(paths: &[Vec<Pixel>]) -> HashMap<Point, Vec<usize>> {
+    let mut identities: HashMap<Point, Vec<usize>> = HashMap::new();
+    for (id, pixels) in paths.iter().enumerate() {
+        for pixel in pixels {
+            let ids = identities.entry(pixel.position).or_default();
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+    }
+    identities
+}
+
+create_exception!(
+    textdraw,
+    RouteError,
+    PyValueError,
+    "Raised when no route can be found for a path.
+
+Attributes
+----------
+edge : int or None
+    The index of the failed edge when routing several paths with `multipath`.
+start : Point
+    The starting point of the path.
+end : Point
+    The ending point of the path.
+waypoint : Point or None
+    The waypoint the route was heading towards when it failed, if any.
+start_in_barrier : bool
+    Whether the starting point lies on an impassible cell.
+end_in_barrier : bool
+    Whether the ending point lies on an impassible cell.
+start_outside_bbox : bool
+    Whether the starting point lies outside of the given `bbox`.
+end_outside_bbox : bool
+    Whether the ending point lies outside of the given `bbox`.
+explored : int
+    The number of cells the search explored before giving up.
+overlay : PixelGroup
+    The explored cells, which can be rendered on top of the diagram for debugging."
+);
+
+/// The details of a failed route, raised to Python as a `RouteError`.
+struct RouteFailure {
+    edge: Option<usize>,
+    start: Point,
+    end: Point,
+    waypoint: Option<(Point, Option<Direction>)>,
+    start_in_barrier: bool,
+    end_in_barrier: bool,
+    start_outside_bbox: bool,
+    end_outside_bbox: bool,
+    explored: Vec<Point>,
+}
+impl RouteFailure {
+    fn message(&self) -> String {
+        let mut message = "No path found".to_string();
+        if let Some(edge) = self.edge {
+            write!(message, " for edge {}", edge).unwrap();
+        }
+        write!(message, " from {} to {}", self.start, self.end).unwrap();
+        if let Some((waypoint, direction)) = self.waypoint {
+            write!(message, " through waypoint {}", waypoint).unwrap();
+            if let Some(d) = direction {
+                write!(message, " going {}", d).unwrap();
+            }
+        }
+        let reasons = [
+            (self.start_in_barrier, "the start is inside a barrier"),
+            (self.end_in_barrier, "the end is inside a barrier"),
+            (self.start_outside_bbox, "the start is outside the bbox"),
+            (self.end_outside_bbox, "the end is outside the bbox"),
+        ]
+        .into_iter()
+        .filter_map(|(failed, reason)| failed.then_some(reason))
+        .join(", ");
+        if !reasons.is_empty() {
+            write!(message, ": {}", reasons).unwrap();
+        }
+        write!(message, " ({} cells explored)", self.explored.len()).unwrap();
+        message
+    }
+    fn overlay(&self) -> PixelGroup {
+        PixelGroup {
+            pixels: self
+                .explored
+                .iter()
+                .map(|&position| Pixel {
+                    character: '░',
+                    position,
+                    weight: None,
+                    style: TextStyle::default(),
+                })
+                .collect(),
+            position: Point::default(),
+            style: "dimmed red".parse().expect("overlay style is valid"),
+            weight: None,
+        }
+    }
+}
+impl From<RouteFailure> for PyErr {
+    fn from(failure: RouteFailure) -> Self {
+        Python::with_gil(|py| {
+            let err = RouteError::new_err(failure.message());
+            let value = err.value(py);
+            let attributes = || -> PyResult<()> {
+                value.setattr("edge", failure.edge)?;
+                value.setattr("start", failure.start)?;
+                value.setattr("end", failure.end)?;
+                value.setattr("waypoint", failure.waypoint.map(|(w, _)| w))?;
+                value.setattr("start_in_barrier", failure.start_in_barrier)?;
+                value.setattr("end_in_barrier", failure.end_in_barrier)?;
+                value.setattr("start_outside_bbox", failure.start_outside_bbox)?;
+                value.setattr("end_outside_bbox", failure.end_outside_bbox)?;
+                value.setattr("explored", failure.explored.len())?;
+                value.setattr("overlay", failure.overlay())?;
+                Ok(())
+            };
+            match attributes() {
+                Ok(()) => err,
+                Err(e) => e,
+            }
+        })
+    }
+}
+
+/// A set of pixels which represent a path between two points.
+///
+/// Parameters
+/// ----------
+/// start : Point or tuple of ints
+///     The starting point of the path.
+/// end : Point or tuple of ints
+///     The ending point of the path.
+/// position : Point or tuple of ints, optional
+///     The (x, y) coordinates of the new TextPath.
+/// style : str, optional
+///     The style to apply to the path.
+/// line_style : {'light', 'heavy', 'double'}, optional
+///     The set of characters to use for the path.
+/// weight : int, optional
+///     The weights to apply to each pixel in the path.
+/// start_direction : {'up', 'right', 'down', 'left'}, optional
+///     The direction to use for the start pixel.
+/// end_direction : {'up', 'right', 'down', 'left'}, optional
+///     The direction to use for the end pixel.
+/// bend_penalty : int, default=1
+///     The penalty weight to apply to bends in the path.
+/// environment : list
+///     A list of objects (TextPath, Box, Table, Pixel, or PixelGroup) which the pathfinding
+///     algorithm can see with their given weights.
+/// barriers : list
+///     A list of objects (TextPath, Box, Table, Pixel, or PixelGroup) which the pathfinding
+///     algorithm considers impassible. A Region can be given to block off every cell in it.
+/// paths : list
+///     A list of objects (TextPath, Box, Table, Pixel, or PixelGroup) which the pathfinding
+///     algorithm will try to follow (these objects have no additional weight cost but also count
+///     when determining neighbors for generating path characters). Only cells where one of these
+///     objects actually continues are joined, so a path running alongside one of them is drawn as
+///     a plain line rather than a row of junctions.
+/// bbox : BoundingBox, optional
+///     If provided, limits the search to the bounding box.
+/// crossing : {'join', 'hop', 'gap'}, default='join'
+///     How to draw cells where this path crosses straight over one of the `paths` it was given.
+///     'join' draws a junction, 'hop' keeps this path's line and breaks the crossed line, and
+///     'gap' additionally blanks the crossed line on either side of the crossing. Real junctions,
+///     where the paths share a segment, are always joined. The path must be rendered after the
+///     paths it crosses.
+/// waypoints : list of Point, tuple of ints, or (point, direction) tuples, optional
+///     Points the path must pass through, in order. A waypoint given as a (point, direction)
+///     tuple must be passed through travelling in the given direction
+///     ({'up', 'right', 'down', 'left'}).
+/// avoid_boxes : bool, default=False
+///     If True, the interiors and borders of every Box in `environment` or `barriers` are
+///     impassible. Only the start and end points and the cells leading straight out of the box
+///     from them (away from `start_direction` or `end_direction` if given) stay open as ports.
+/// clearance : int, default=0
+///     The number of cells around each avoided Box which are also impassible.
+/// router : {'grid', 'channel'}, default='grid'
+///     With 'grid', the route is searched cell by cell over the whole bounding box. With
+///     'channel', a coarse route is first found through the open channels between the boxes and
+///     tables in `environment` and `barriers` and then refined cell by cell inside those channels,
+///     which is much faster on large diagrams but may not find the cheapest route.
+/// label : str, optional
+///     Text to place on the longest straight segment of the path which has room for it without
+///     covering any of the `environment`, `barriers` or `paths`. If there is no such segment, the
+///     label goes in the nearest free spot around the middle of the path, or is left out if there
+///     is none within its own length.
+/// label_style : str, optional
+///     The style to apply to the label.
+/// label_position : {'inline', 'beside'}, default='inline'
+///     Whether the label breaks the line or sits next to it (above horizontal segments and to the
+///     right of vertical ones).
+///
+/// Raises
+/// ------
+/// RouteError
+///     If no route can be found. The error records why the endpoints may be unreachable, how much
+///     of the grid was searched, and an overlay of the explored cells.
+///
+/// Attributes
+/// ----------
+/// style : Style
+///     The style to apply to the path.
+/// line_style : {'light', 'heavy', 'double'}
+///     The set of characters to use for the path.
+/// crossing : {'join', 'hop', 'gap'}
+///     How to draw cells where this path crosses another path.
+/// label : str or None
+///     The label placed along the path.
+/// label_style : Style
+///     The style to apply to the label.
+/// label_position : {'inline', 'beside'}
+///     Whether the label breaks the line or sits next to it.
+/// weight : int or None
+///     The weight to apply to each pixel in the path.
+/// start_direction : {'up', 'right', 'down', 'left'} or None
+///     The direction to use for the start pixel.
+/// end_direction : {'up', 'right', 'down', 'left'} or None
+///     The direction to use for the end pixel.
+///
+/// Examples
+/// --------
+/// >>> from textdraw import TextPath, render
+/// >>> p = TextPath((0, 0), (3, 5))
+/// >>> print(render(p))
+/// ┌──╴
+/// │
+/// │
+/// │
+/// │
+/// ╵
+///
+///
+
+#[pyclass(module = "textdraw")]
+#[derive(Clone, Serialize, Deserialize)]
+struct TextPath {
+    path: Vec<Point>,
+    #[pyo3(get, set)]
+    position: Point,
+    #[pyo3(get, set)]
+    style: TextStyle,
+    line_style: LineStyle,
+    #[pyo3(get, set)]
+    weight: Option<usize>,
+    start_direction: Option<Direction>,
+    end_direction: Option<Direction>,
+    start: Point,
+    end: Point,
+    /// Cells of the paths this path may follow, with the indices of the paths covering each cell.
+    #[serde(with = "cell_map")]
+    paths: HashMap<Point, Vec<usize>>,
+    crossing: Crossing,
+    /// An arrowhead drawn in place of the last cell, pointing in the direction of travel.
+    arrow: Option<ArrowType>,
+    #[pyo3(get)]
+    label: Option<String>,
+    #[pyo3(get, set)]
+    label_style: TextStyle,
+    label_position: LabelPosition,
+    /// The cells of the label, placed when the path is created.
+    label_cells: Vec<(Point, char)>,
+    #[pyo3(get)]
+    cost: usize,
+}
+
+#[pymethods]
+impl TextPath {
+    #[new]
+    #[pyo3(signature = (start, end, position = None, style = None, *, line_style = "light".to_string(), weight = None, start_direction = None, end_direction = None, bend_penalty = 1, environment = None, barriers = None, paths = None, bbox = None, crossing = "join", waypoints = None, avoid_boxes = false, clearance = 0, router = "grid", label = None, label_style = None, label_position = "inline"))]
+    fn new(
+        py: Python,
+        start: Bound<PyAny>,
+        end: Bound<PyAny>,
+        position: Option<Bound<PyAny>>,
+        style: Option<String>,
+        line_style: String,
+        weight: Option<usize>,
+        start_direction: Option<String>,
+        end_direction: Option<String>,
+        bend_penalty: usize,
+        environment: Option<Bound<'_, PyAny>>,
+        barriers: Option<Bound<'_, PyAny>>,
+        paths: Option<Bound<'_, PyAny>>,
+        bbox: Option<Bound<'_, PyAny>>,
+        crossing: &str,
+        waypoints: Option<Vec<Bound<'_, PyAny>>>,
+        avoid_boxes: bool,
+        clearance: usize,
+        router: &str,
+        label: Option<String>,
+        label_style: Option<String>,
+        label_position: &str,
+    ) -> PyResult<Self> {
+        let crossing: Crossing = crossing.parse()?;
+        let router: Router = router.parse()?;
+        let label_style: TextStyle = label_style.unwrap_or_default().parse()?;
+        let label_position: LabelPosition = label_position.parse()?;
+        let waypoints = waypoints
+            .unwrap_or_default()
+            .iter()
+            .map(|w| {
+                if let Ok(point) = Point::extract_bound(w) {
+                    Ok((point, None))
+                } else if let Ok((point, direction)) = w.extract::<(Bound<PyAny>, String)>() {
+                    Ok((Point::extract_bound(&point)?, Some(direction.parse()?)))
+                } else {
+                    Err(PyTypeError::new_err(
+                        "Waypoints must be points or (point, direction) tuples",
+                    ))
+                }
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+        let start = Point::extract_bound(&start)?;
+        let end = Point::extract_bound(&end)?;
+        // Objects with a `__textdraw__` method are only drawn once, for both their boxes and pixels.
+        let environment =
+            objs_to_elements(&environment.unwrap_or(PyTuple::empty(py).as_any().clone()))?;
+        let barriers = objs_to_elements(&barriers.unwrap_or(PyTuple::empty(py).as_any().clone()))?;
+        let boxes = elements_to_box_bounds(&environment)?
+            .into_iter()
+            .chain(elements_to_box_bounds(&barriers)?)
+            .collect_vec();
+        let obstacles = if avoid_boxes {
+            boxes.iter().map(|bb| bb.expanded(clearance)).collect()
+        } else {
+            Vec::new()
+        };
+        let environment = elements_to_pixels(&environment)?;
+        let barriers = elements_to_pixels(&barriers)?;
+        let paths =
+            objs_to_pixel_sets(&paths.unwrap_or(PyTuple::empty(py).as_any().clone()), false)?;
+        let bbox = bbox.map(|bb| BoundingBox::extract_bound(&bb)).transpose()?;
+        TextPath::calculate_path(
+            start,
+            end,
+            position.map(|p| Point::extract_bound(&p)).transpose()?,
+            style,
+            line_style,
+            weight,
+            start_direction,
+            end_direction,
+            bend_penalty,
+            &environment,
+            &barriers,
+            &paths,
+            bbox,
+            &boxes,
+            &obstacles,
+            &waypoints,
+            1,
+            router,
+            None,
+        )
+        .map(|textpath| {
+            textpath.with_crossing(crossing).with_label(
+                label,
+                label_style,
+                label_position,
+                &occupied_cells(&environment, &barriers, &paths),
+            )
+        })
+    }
+    /// Creates a TextPath from an explicit polyline instead of searching for a route.
+    ///
+    /// Parameters
+    /// ----------
+    /// points : list of Point or tuple of ints
+    ///     The vertices of the polyline. Consecutive vertices must share either their x- or
+    ///     y-coordinate.
+    /// position : Point or tuple of ints, optional
+    ///     The (x, y) coordinates of the new TextPath.
+    /// style : str, optional
+    ///     The style to apply to the path.
+    /// line_style : {'light', 'heavy', 'double'}, optional
+    ///     The set of characters to use for the path.
+    /// weight : int, optional
+    ///     The weights to apply to each pixel in the path.
+    /// start_direction : {'up', 'right', 'down', 'left'}, optional
+    ///     The direction to use for the start pixel.
+    /// end_direction : {'up', 'right', 'down', 'left'}, optional
+    ///     The direction to use for the end pixel.
+    /// paths : list
+    ///     A list of objects (TextPath, Box, Table, Pixel, or PixelGroup) which count when
+    ///     determining neighbors for generating path characters.
+    /// crossing : {'join', 'hop', 'gap'}, default='join'
+    ///     How to draw cells where this path crosses one of the `paths`.
+    ///
+    /// Returns
+    /// -------
+    /// TextPath
+    ///     A new TextPath following the polyline.
+    ///
+    /// Raises
+    /// ------
+    /// ValueError
+    ///     If no points are given or a segment is not horizontal or vertical.
+    ///
+    /// Examples
+    /// --------
+    /// >>> from textdraw import TextPath, render
+    /// >>> p = TextPath.from_polyline([(0, 0), (0, 2), (3, 2)])
+    /// >>> print(render([p]))
+    /// ┌──╴
+    /// │
+    /// ╵
+    ///
+    #[staticmethod]
+    #[pyo3(signature = (points, position = None, style = None, *, line_style = "light".to_string(), weight = None, start_direction = None, end_direction = None, paths = None, crossing = "join"))]
+    fn from_polyline(
+        py: Python,
+        points: Vec<Bound<PyAny>>,
+        position: Option<Bound<PyAny>>,
+        style: Option<String>,
+        line_style: String,
+        weight: Option<usize>,
+        start_direction: Option<String>,
+        end_direction: Option<String>,
+        paths: Option<Bound<'_, PyAny>>,
+        crossing: &str,
+    ) -> PyResult<Self> {
+        let points = points
+            .iter()
+            .map(Point::extract_bound)
+            .collect::<PyResult<Vec<_>>>()?;
+        let (Some(&start), Some(&end)) = (points.first(), points.last()) else {
+            return Err(PyValueError::new_err("A polyline needs at least one point"));
+        };
+        let mut path = vec![start];
+        for (a, b) in points.iter().tuple_windows() {
+            if a.0 != b.0 && a.1 != b.1 {
+                return Err(PyValueError::new_err(format!(
+                    "Polyline segment from {} to {} is not horizontal or vertical",
+                    a, b
+                )));
+            }
+            let step = Point((b.0 - a.0).signum(), (b.1 - a.1).signum());
+            let mut current = *a;
+            while current != *b {
+                current += step;
+                path.push(current);
+            }
+        }
+        let paths =
+            objs_to_pixel_sets(&paths.unwrap_or(PyTuple::empty(py).as_any().clone()), false)?;
+        Ok(Self {
+            cost: path.len() - 1,
+            path,
+            position: position
+                .map(|p| Point::extract_bound(&p))
+                .transpose()?
+                .unwrap_or_default(),
+            style: style.unwrap_or_default().parse()?,
+            line_style: line_style.parse()?,
+            weight,
+            start_direction: start_direction.map(|s| s.parse()).transpose()?,
+            end_direction: end_direction.map(|s| s.parse()).transpose()?,
+            start,
+            end,
+            paths: path_identities(&paths),
+            crossing: crossing.parse()?,
+            arrow: None,
+            label: None,
+            label_style: TextStyle::default(),
+            label_position: LabelPosition::default(),
+            label_cells: Vec::new(),
+        })
+    }
+    /// Creates a TextPath which loops from a side of a Box back into the same side.
+    ///
+    /// The loop leaves the middle of the given side and re-enters it `span` cells further along
+    /// (towards the top for the left and right sides, towards the right for the top and bottom
+    /// sides). Both ends stay clear of the corners of the box, so the side needs at least
+    /// `span + 1` cells between its corners.
+    ///
+    /// Parameters
+    /// ----------
+    /// box : Box
+    ///     The box to attach the loop to.
+    /// side : {'up', 'right', 'down', 'left'}, default='right'
+    ///     The side of the box to attach the loop to.
+    /// style : str, optional
+    ///     The style to apply to the path.
+    /// line_style : {'light', 'heavy', 'double'}, optional
+    ///     The set of characters to use for the path.
+    /// weight : int, optional
+    ///     The weights to apply to each pixel in the path.
+    /// size : int, default=1
+    ///     How many cells the loop extends beyond the cells next to the box.
+    /// span : int, default=1
+    ///     The distance along the side between the two ends of the loop.
+    /// arrow : str, optional
+    ///     An arrow type ('arrow', 'openarrow', or 'custom[<chars>]', see `arrow`) to draw where the
+    ///     loop re-enters the box.
+    ///
+    /// Returns
+    /// -------
+    /// TextPath
+    ///     A new TextPath forming the loop.
+    ///
+    /// Raises
+    /// ------
+    /// ValueError
+    ///     If `size` or `span` is zero, or the side has fewer than `span + 1` cells between its
+    ///     corners.
+    ///
+    /// Examples
+    /// --------
+    /// >>> from textdraw import Box, TextPath, render
+    /// >>> b = Box('A\nB', padding=(0, 1, 0, 1))
+    /// >>> print(render([b, TextPath.self_loop(b, 'right', arrow='arrow')]))
+    /// ┌───┐
+    /// │ A │◀┐
+    /// │ B │─┘
+    /// └───┘
+    ///
+    #[staticmethod]
+    #[pyo3(signature = (r#box, side = "right", style = None, *, line_style = "light".to_string(), weight = None, size = 1, span = 1, arrow = None))]
+    fn self_loop(
+        r#box: &Box,
+        side: &str,
+        style: Option<String>,
+        line_style: String,
+        weight: Option<usize>,
+        size: usize,
+        span: usize,
+        arrow: Option<String>,
+    ) -> PyResult<Self> {
+        if size == 0 || span == 0 {
+            return Err(PyValueError::new_err(
+                "A self-loop needs a size and span of at least 1",
+            ));
+        }
+        let side: Direction = side.parse()?;
+        let bbox = r#box.get_bbox();
+        let center = bbox.center();
+        // The loop runs along the side from `first` to `first + span`, keeping both ends between
+        // the corners of the side.
+        let (along, first, lowest, highest) = match side {
+            Direction::Up | Direction::Down => {
+                (Direction::Right, center.0, bbox.left + 1, bbox.right - 1)
+            }
+            Direction::Left | Direction::Right => {
+                (Direction::Up, center.1, bbox.bottom + 1, bbox.top - 1)
+            }
+        };
+        let first = first.min(highest - span as isize);
+        if first < lowest {
+            return Err(PyValueError::new_err(format!(
+                "The {} side of the box is too short for a self-loop with span {}",
+                side, span
+            )));
+        }
+        let edge = match side {
+            Direction::Up => Point(first, bbox.top),
+            Direction::Right => Point(bbox.right, first),
+            Direction::Down => Point(first, bbox.bottom),
+            Direction::Left => Point(bbox.left, first),
+        };
+        let out = side.delta();
+        let step = along.delta();
+        let mut path = Vec::with_capacity(2 * size + span + 3);
+        let mut current = edge + out;
+        path.push(current);
+        for _ in 0..size {
+            current += out;
+            path.push(current);
+        }
+        for _ in 0..span {
+            current += step;
+            path.push(current);
+        }
+        for _ in 0..size {
+            current -= out;
+            path.push(current);
+        }
+        let arrow = arrow.map(|a| parse_arrow(&a).map(|(_, t)| t)).transpose()?;
+        Ok(Self {
+            cost: path.len() - 1,
+            start: path[0],
+            end: current,
+            path,
+            position: Point::default(),
+            style: style.unwrap_or_default().parse()?,
+            line_style: line_style.parse()?,
+            weight,
+            start_direction: Some(side.opposite()),
+            end_direction: Some(side.opposite()),
+            paths: HashMap::new(),
+            crossing: Crossing::default(),
+            arrow,
+            label: None,
+            label_style: TextStyle::default(),
+            label_position: LabelPosition::default(),
+            label_cells: Vec::new(),
+        })
+    }
+    /// TextPaths are shown as the polyline through their corners, since routing them again from
+    /// their ends needs the objects they were routed around. Labels and arrowheads are left out.
+    fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+        let corners = self
+            .path
+            .iter()
+            .enumerate()
+            .filter(|&(i, point)| {
+                i == 0
+                    || i == self.path.len() - 1
+                    || *point - self.path[i - 1] != self.path[i + 1] - *point
+            })
+            .map(|(_, point)| *point)
+            .collect::<Vec<_>>();
+        Ok(constructor_repr(
+            "TextPath.from_polyline",
+            &[py_repr(py, corners)?, py_repr(py, self.position)?],
+            &[
+                (
+                    "style",
+                    keyword(py, self.style.spec(), self.style == TextStyle::default())?,
+                ),
+                (
+                    "line_style",
+                    keyword(
+                        py,
+                        self.line_style.to_string(),
+                        self.line_style == LineStyle::Light,
+                    )?,
+                ),
+                ("weight", keyword(py, self.weight, self.weight.is_none())?),
+                (
+                    "start_direction",
+                    keyword(
+                        py,
+                        self.start_direction.map(|d| d.to_string()),
+                        self.start_direction.is_none(),
+                    )?,
+                ),
+                (
+                    "end_direction",
+                    keyword(
+                        py,
+                        self.end_direction.map(|d| d.to_string()),
+                        self.end_direction.is_none(),
+                    )?,
+                ),
+            ],
+        ))
+    }
+    fn __reduce__<'py>(&self, py: Python<'py>) -> PyResult<(Bound<'py, PyAny>, (String,))> {
+        reduce(py, Record::TextPath(self.clone()))
+    }
+    fn __copy__(&self) -> Self {
+        self.clone()
+    }
+    fn __deepcopy__(&self, _memo: Bound<PyAny>) -> Self {
+        self.clone()
+    }
+    #[getter]
+    fn get_start_direction(&self) -> String {
+        self.start_direction
+            .map_or("None".to_string(), |d| d.to_string())
+    }
+    #[setter]
+    fn set_start_direction(&mut self, start_direction: Option<String>) -> PyResult<()> {
+        self.start_direction = start_direction.map(|s| s.parse()).transpose()?;
+        Ok(())
+    }
+    #[getter]
+    fn get_end_direction(&self) -> String {
+        self.end_direction
+            .map_or("None".to_string(), |d| d.to_string())
+    }
+    #[setter]
+    fn set_end_direction(&mut self, end_direction: Option<String>) -> PyResult<()> {
+        self.end_direction = end_direction.map(|s| s.parse()).transpose()?;
+        Ok(())
+    }
+    #[getter]
+    fn get_line_style(&self) -> String {
+        self.line_style.to_string()
+    }
+    #[setter]
+    fn set_line_style(&mut self, line_style: String) -> PyResult<()> {
+        self.line_style = line_style.parse()?;
+        Ok(())
+    }
+    #[getter]
+    fn get_label_position(&self) -> String {
+        self.label_position.to_string()
+    }
+    #[getter]
+    fn get_crossing(&self) -> String {
+        self.crossing.to_string()
+    }
+    #[setter]
+    fn set_crossing(&mut self, crossing: String) -> PyResult<()> {
+        self.crossing = crossing.parse()?;
+        Ok(())
+    }
+    #[getter]
+    fn get_bbox(&self) -> BoundingBox {
+        let mut bbox = BoundingBox::EMPTY;
+        for point in &self.path {
+            bbox += point;
+        }
+        bbox
+    }
+    /// Duplicate a TextPath at a given position.
+    ///
+    /// Parameters
+    /// ----------
+    /// position : Point or tuple of ints, optional
+    ///     The (x, y) coordinates of the new TextPath.
+    ///
+    /// Returns
+    /// -------
+    /// TextPath
+    ///     A new TextPath at the given position.
+    ///
+    #[pyo3(name = "duplicate", signature = (position = None))]
+    fn py_duplicate(&self, position: Option<Bound<PyAny>>) -> PyResult<Self> {
+        Ok(self.duplicate(
+            position
+                .map(|p| Point::extract_bound(&p))
+                .transpose()?
+                .unwrap_or_default(),
+        ))
+    }
+    /// Duplicate a TextPath shifted by the given delta.
+    ///
+    /// Parameters
+    /// ----------
+    /// delta : Point or tuple of ints
+    ///     The (x, y) shift difference.
+    ///
+    /// Returns
+    /// -------
+    /// TextPath
+    ///     A new TextPath shifted by the given delta.
+    ///
+    #[pyo3(name = "duplicate_shifted")]
+    fn py_duplicate_shifted(&self, delta: Bound<PyAny>) -> PyResult<Self> {
+        Ok(self.duplicate_shifted(Point::extract_bound(&delta)?))
+    }
+}
+/// How many free cells the default search area of a path leaves around its start and end.
+const ROUTE_MARGIN: usize = 2;
+
+impl TextPath {
+    fn with_crossing(mut self, crossing: Crossing) -> Self {
+        self.crossing = crossing;
+        self
+    }
+    fn with_label(
+        mut self,
+        label: Option<String>,
+        style: TextStyle,
+        position: LabelPosition,
+        occupied: &HashSet<Point>,
+    ) -> Self {
+        self.label_cells = label
+            .as_deref()
+            .map(|l| place_label(&self.path, l, position, occupied))
+            .unwrap_or_default();
+        self.label = label;
+        self.label_style = style;
+        self.label_position = position;
+        self
+    }
+    fn calculate_path(
+        start: Point,
+        end: Point,
+        position: Option<Point>,
+        style: Option<String>,
+        line_style: String,
+        weight: Option<usize>,
+        start_direction: Option<String>,
+        end_direction: Option<String>,
+        bend_penalty: usize,
+        environment: &[Pixel],
+        barriers: &[Pixel],
+        paths: &[Vec<Pixel>],
+        bbox: Option<BoundingBox>,
+        boxes: &[BoundingBox],
+        obstacles: &[BoundingBox],
+        waypoints: &[(Point, Option<Direction>)],
+        heuristic_scale: usize,
+        router: Router,
+        edge: Option<usize>,
+    ) -> PyResult<Self> {
+        let start_direction: Option<Direction> = start_direction.map(|s| s.parse()).transpose()?;
+        let end_direction: Option<Direction> = end_direction.map(|s| s.parse()).transpose()?;
+        let mut bb = bbox.unwrap_or_else(|| {
+            // Leave a ring of free cells around obstacles so that paths can go around them, and
+            // around the ends and waypoints too, so that a path between two points is not squeezed
+            // into the rectangle between them (where equally short routes all hug its edges) and
+            // a waypoint can be entered and left from any side.
+            [start, end]
+                .into_iter()
+                .chain(waypoints.iter().map(|&(waypoint, _)| waypoint))
+                .fold(
+                    obstacles.iter().fold(
+                        pixels_to_bounding_box(
+                            environment
+                                .iter()
+                                .chain(barriers)
+                                .chain(paths.iter().flatten()),
+                        ),
+                        |bb, obstacle| bb + obstacle.expanded(1),
+                    ),
+                    |bb, point| {
+                        bb + BoundingBox::new(point.1, point.0, point.1, point.0)
+                            .expanded(ROUTE_MARGIN)
+                    },
+                )
+        });
+        bb += start;
+        bb += end;
+        for (waypoint, _) in waypoints {
+            bb += waypoint;
+        }
+        let mut weights = Grid::new(bb, Some(1));
+        for pixel in environment {
+            weights.set(&pixel.position, pixel.weight);
+        }
+        for pixel in barriers {
+            weights.set(&pixel.position, None);
+        }
+        for pixel in paths.iter().flatten() {
+            weights.set(&pixel.position, Some(0));
+        }
+        if !obstacles.is_empty() {
+            let ports: HashSet<Point> = port_cells(
+                start,
+                start_direction.map(Direction::opposite),
+                obstacles,
+                bb,
+            )
+            .into_iter()
+            .chain(port_cells(
+                end,
+                end_direction.map(Direction::opposite),
+                obstacles,
+                bb,
+            ))
+            .collect();
+            for obstacle in obstacles {
+                for y in obstacle.bottom.max(bb.bottom)..=obstacle.top.min(bb.top) {
+                    for x in obstacle.left.max(bb.left)..=obstacle.right.min(bb.right) {
+                        let cell = Point(x, y);
+                        if !ports.contains(&cell) {
+                            weights.set(&cell, None);
+                        }
+                    }
+                }
+            }
+            for port in &ports {
+                if let Some(weight @ None) = weights.get_mut(port) {
+                    *weight = Some(1);
+                }
+            }
+        }
+        let start_in_barrier = weights.get(&start).is_some_and(|w| w.is_none());
+        let end_in_barrier = weights.get(&end).is_some_and(|w| w.is_none());
+        let failure = |waypoint, explored| {
+            PyErr::from(RouteFailure {
+                edge,
+                start,
+                end,
+                waypoint,
+                start_in_barrier,
+                end_in_barrier,
+                start_outside_bbox: bbox.is_some_and(|bb| !bb.contains_point(&start)),
+                end_outside_bbox: bbox.is_some_and(|bb| !bb.contains_point(&end)),
+                explored,
+            })
+        };
+        // The channel router splits the search area along the boxes as well as the clearance around
+        // any avoided ones.
+        let channel_boxes = boxes.iter().chain(obstacles).copied().collect_vec();
+        // The route is searched in legs between consecutive stops. A waypoint with a required
+        // direction is entered from the cell behind it and left through the cell ahead of it.
+        // Cells used by earlier legs are blocked so that the route does not double back on itself.
+        let mut path = vec![start];
+        let mut cost = 0;
+        let mut dir = None;
+        for &(waypoint, direction) in waypoints {
+            let forced = direction.map(|d| {
+                let steps = [waypoint, waypoint + d.delta()];
+                let step_weights = steps.map(|step| weights.get(&step).copied().flatten());
+                for step in steps {
+                    weights.set(&step, None);
+                }
+                (d, steps, step_weights)
+            });
+            let target = direction.map_or(waypoint, |d| waypoint - d.delta());
+            let (leg, leg_cost, leg_dir) = router
+                .find_path(
+                    *path.last().expect("path is never empty"),
+                    target,
+                    dir,
+                    &weights,
+                    &channel_boxes,
+                    bend_penalty,
+                    heuristic_scale,
+                )
+                .map_err(|explored| failure(Some((waypoint, direction)), explored))?;
+            for cell in &leg[..leg.len() - 1] {
+                weights.set(cell, None);
+            }
+            path.extend(leg.into_iter().skip(1));
+            cost += leg_cost;
+            dir = leg_dir;
+            if let Some((d, steps, step_weights)) = forced {
+                for (step, step_weight) in steps.into_iter().zip(step_weights) {
+                    let Some(w) = step_weight else {
+                        return Err(failure(Some((waypoint, direction)), Vec::new()));
+                    };
+                    cost += w;
+                    weights.set(path.last().expect("path is never empty"), None);
+                    path.push(step);
+                }
+                if dir.is_some_and(|prev| prev != d) {
+                    cost += bend_penalty;
+                }
+                dir = Some(d);
+            }
+        }
+        let (leg, leg_cost, _) = router
+            .find_path(
+                *path.last().expect("path is never empty"),
+                end,
+                dir,
+                &weights,
+                &channel_boxes,
+                bend_penalty,
+                heuristic_scale,
+            )
+            .map_err(|explored| failure(None, explored))?;
+        path.extend(leg.into_iter().skip(1));
+        cost += leg_cost;
+        Ok(Self {
+            path,
+            position: position.unwrap_or_default(),
+            style: style.unwrap_or_default().parse()?,
+            line_style: line_style.parse()?,
+            weight,
+            start_direction,
+            end_direction,
+            start,
+            end,
+            paths: path_identities(paths),
+            crossing: Crossing::default(),
+            arrow: None,
+            label: None,
+            label_style: TextStyle::default(),
+            label_position: LabelPosition::default(),
+            label_cells: Vec::new(),
+            cost,
+        })
+    }
+    /// The directions in which another path covering `pos` continues into a neighboring cell.
+    ///
+    /// Two cells are only connected if the same path covers both of them, so paths which merely run
+    /// alongside each other are not joined.
+    fn followed_links(&self, pos: &Point) -> [bool; 4] {
+        let Some(ids) = self.paths.get(pos) else {
+            return [false; 4];
+        };
+        Direction::all().map(|d| {
+            self.paths
+                .get(&(pos + d.delta()))
+                .is_some_and(|other| other.iter().any(|id| ids.contains(id)))
+        })
+    }
+    /// The directions in which the path itself continues from each of its cells.
+    fn own_links(&self) -> HashMap<Point, [bool; 4]> {
+        let mut links: HashMap<Point, [bool; 4]> = HashMap::new();
+        for (a, b) in self.path.iter().tuple_windows() {
+            if let Some(d) = Direction::between(a, b) {
+                links.entry(*a).or_default()[d.index()] = true;
+                links.entry(*b).or_default()[d.opposite().index()] = true;
+            }
+        }
+        if let Some(start_dir) = self.start_direction {
+            links.entry(self.start).or_default()[start_dir.index()] = true;
+        }
+        if let Some(end_dir) = self.end_direction {
+            links.entry(self.end).or_default()[end_dir.index()] = true;
+        }
+        links
+    }
+    fn as_group(&self) -> PyResult<PixelGroup> {
+        let mut group = self.as_line_group()?;
+        group.pixels.extend(self.label_pixels());
+        Ok(group)
+    }
+    fn label_pixels(&self) -> impl Iterator<Item = Pixel> + '_ {
+        self.label_cells.iter().map(|&(position, character)| Pixel {
+            character,
+            position,
+            weight: self.weight,
+            style: self.label_style.clone(),
+        })
+    }
+    /// The pixels of the line itself, without the label.
+    fn as_line_group(&self) -> PyResult<PixelGroup> {
+        let path_map: HashSet<Point> = self.path.iter().copied().collect();
+        let links = self.own_links();
+        let mut pixels = Vec::with_capacity(self.path.len());
+        for pos in &self.path {
+            let own_links = links.get(pos).copied().unwrap_or_default();
+            let followed_links = self.followed_links(pos);
+            let [up, right, down, left] = if self.crossing != Crossing::Join
+                && Crossing::is_crossing(own_links, followed_links)
+            {
+                if self.crossing == Crossing::Gap {
+                    for d in Direction::all() {
+                        let gap = pos + d.delta();
+                        if followed_links[d.index()] && !path_map.contains(&gap) {
+                            pixels.push(Pixel {
+                                character: ' ',
+                                position: gap,
+                                weight: self.weight,
+                                style: self.style.clone(),
+                            });
+                        }
+                    }
+                }
+                own_links
+            } else {
+                [0, 1, 2, 3].map(|i| own_links[i] || followed_links[i])
+            };
+            let character = match self.arrow {
+                Some(arrow) if *pos == self.end => {
+                    let direction = self
+                        .end_direction
+                        .or_else(|| {
+                            let [.., a, b] = self.path.as_slice() else {
+                                return None;
+                            };
+                            Direction::between(a, b)
+                        })
+                        .unwrap_or_default();
+                    arrow
+                        .render(&direction)
+                        .chars()
+                        .next()
+                        .expect("arrows are a single character")
+                }
+                _ => self.line_style.get_char((up, right, down, left)),
+            };
+            pixels.push(Pixel {
+                character,
+                position: *pos,
+                weight: self.weight,
+                style: self.style.clone(),
+            });
+        }
+        Ok(PixelGroup {
+            pixels,
+            position: self.position,
+            style: TextStyle::default(),
+            weight: Some(0),
+        })
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+enum EdgeMode {
+    #[default]
+    Merge,
+    Lanes,
+    Bundle,
+}
+impl FromStr for EdgeMode {
+    type Err = PyErr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "merge" => Ok(EdgeMode::Merge),
+            "lanes" => Ok(EdgeMode::Lanes),
+            "bundle" => Ok(EdgeMode::Bundle),
+            other => Err(PyValueError::new_err(format!(
+                "Invalid edge mode [{}]",
+                other
+            ))),
+        }
+    }
+}
+impl Display for EdgeMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                EdgeMode::Merge => "merge",
+                EdgeMode::Lanes => "lanes",
+                EdgeMode::Bundle => "bundle",
+            }
+        )
+    }
+}
+
+/// The cost of each cell of an earlier path for later paths in the 'lanes' edge mode when no
+/// `weight` is given. It is high enough that paths keep to their own lanes but low enough that one
+/// path can still cross another instead of taking a long detour.
+const LANE_WEIGHT: usize = 8;
+/// The number of cells short of the nearest target where the trunk of a bundle splits up, which
+/// leaves room for the branches to bend towards their targets.
+const SPLIT_MARGIN: isize = 2;
+
+/// Where the trunk of a bundle running from `shared` towards `others` splits up.
+///
+/// The trunk runs along the main direction of the bundle and stops `SPLIT_MARGIN` cells short of
+/// the nearest of the other ends, level with their centre. There is no trunk if the other ends are
+/// too close to (or on both sides of) the shared end.
+fn bundle_split(shared: Point, others: &[Point]) -> Option<Point> {
+    if others.is_empty() {
+        return None;
+    }
+    let n = others.len() as isize;
+    let centre = Point(
+        others.iter().map(|p| p.0).sum::<isize>() / n,
+        others.iter().map(|p| p.1).sum::<isize>() / n,
+    );
+    let (dx, dy) = (centre.0 - shared.0, centre.1 - shared.1);
+    if dx.abs() >= dy.abs() {
+        let reach = others
+            .iter()
+            .map(|p| (p.0 - shared.0) * dx.signum())
+            .min()?
+            - SPLIT_MARGIN;
+        (reach > 0).then_some(Point(shared.0 + reach * dx.signum(), centre.1))
+    } else {
+        let reach = others
+            .iter()
+            .map(|p| (p.1 - shared.1) * dy.signum())
+            .min()?
+            - SPLIT_MARGIN;
+        (reach > 0).then_some(Point(centre.0, shared.1 + reach * dy.signum()))
+    }
+}
+
+/// Generate a list of TextPaths between multiple start and end points.
+///
+/// Parameters
+/// ----------
+/// start : list of Point or tuple of ints
+///     The starting point of the path.
+/// end : list of Point or tuple of ints
+///     The ending point of the path.
+/// style : str, optional
+///     The style to apply to the path.
+/// line_style : {'light', 'heavy', 'double'}, optional
+///     The set of characters to use for the path.
+/// weight : int, optional
+///     The weights to apply to each pixel in the path.
+/// start_direction : list of {'up', 'right', 'down', 'left'}, optional
+///     The direction to use for the start pixel.
+/// end_direction : list of {'up', 'right', 'down', 'left'}, optional
+///     The direction to use for the end pixel.
+/// bend_penalty : int, default=1
+///     The penalty weight to apply to bends in the path.
+/// environment : list
+///     A list of objects (TextPath, Box, Table, Pixel, or PixelGroup) which the pathfinding
+///     algorithm can see with their given weights.
+/// barriers : list
+///     A list of objects (TextPath, Box, Table, Pixel, or PixelGroup) which the pathfinding
+///     algorithm considers impassible. A Region can be given to block off every cell in it.
+/// paths : list
+///     A list of objects (TextPath, Box, Table, Pixel, or PixelGroup) which the pathfinding
+///     algorithm will try to follow (these objects have no additional weight cost but also count
+///     when determining neighbors for generating path characters). Only cells where one of these
+///     objects actually continues are joined, so a path running alongside one of them is drawn as
+///     a plain line rather than a row of junctions.
+/// bbox : BoundingBox, optional
+///     If provided, limits the search to the bounding box.
+/// optimize : bool, default=False
+///     If True, iterate through all permutations of path orderings to minimize total cost.
+/// edge_mode : {'merge', 'lanes', 'bundle'}, default='merge'
+///     How the generated paths interact with each other. With 'merge', each path may follow the
+///     previously generated ones at no cost. With 'lanes', every cell of a previously generated
+///     path costs the given `weight` (or 8 if None), so that parallel edges run in adjacent lanes
+///     while edges which have to cross still can. With 'bundle', paths which share a start (or
+///     end) point follow a common trunk which splits up just short of their other ends, and every
+///     path is drawn with the correct junction characters where the bundle splits.
+/// crossing : {'join', 'hop', 'gap'}, default='join'
+///     How to draw cells where a path crosses straight over another path (see `TextPath`).
+/// avoid_boxes : bool, default=False
+///     If True, the interiors and borders of every Box in `environment` or `barriers` are
+///     impassible except for the ports at each start and end point (see `TextPath`).
+/// clearance : int, default=0
+///     The number of cells around each avoided Box which are also impassible.
+/// router : {'grid', 'channel'}, default='grid'
+///     The search used for each path (see `TextPath`).
+/// labels : list of str or None, optional
+///     The label to place along each path (see `TextPath`). Labels avoid each other as well as
+///     the other objects.
+/// label_style : str, optional
+///     The style to apply to the labels.
+/// label_position : {'inline', 'beside'}, default='inline'
+///     Whether the labels break the lines or sit next to them.
+///
+/// Raises
+/// ------
+/// RouteError
+///     If no route can be found for one of the paths. The `edge` attribute of the error holds the
+///     index of the failed start and end pair.
+///
+#[pyfunction]
+#[pyo3(signature = (starts, ends, position = None, style = None, *, line_style = "light".to_string(), weight = None, start_directions = None, end_directions = None, bend_penalty = 1, environment = None, barriers = None, paths = None, bbox = None, optimize = false, edge_mode = "merge", crossing = "join", avoid_boxes = false, clearance = 0, router = "grid", labels = None, label_style = None, label_position = "inline"))]
+fn multipath(
+    py: Python,
+    starts: Bound<PyAny>,
+    ends: Bound<PyAny>,
+    position: Option<Bound<PyAny>>,
+    style: Option<String>,
+    line_style: String,
+    weight: Option<usize>,
+    start_directions: Option<Vec<Option<String>>>,
+    end_directions: Option<Vec<Option<String>>>,
+    bend_penalty: usize,
+    environment: Option<Bound<'_, PyAny>>,
+    barriers: Option<Bound<'_, PyAny>>,
+    paths: Option<Bound<'_, PyAny>>,
+    bbox: Option<Bound<'_, PyAny>>,
+    optimize: bool,
+    edge_mode: &str,
+    crossing: &str,
+    avoid_boxes: bool,
+    clearance: usize,
+    router: &str,
+    labels: Option<Vec<Option<String>>>,
+    label_style: Option<String>,
+    label_position: &str,
+) -> PyResult<Vec<TextPath>> {
+    let edge_mode: EdgeMode = edge_mode.parse()?;
+    let router: Router = router.parse()?;
+    let label_style: TextStyle = label_style.unwrap_or_default().parse()?;
+    let label_position: LabelPosition = label_position.parse()?;
+    let crossing: Crossing = crossing.parse()?;
+    let starts: Vec<Point> = starts
+        .downcast::<PyList>()?
+        .iter()
+        .map(|p| Point::extract_bound(&p))
+        .collect::<PyResult<Vec<_>>>()?;
+    let ends: Vec<Point> = ends
+        .downcast::<PyList>()?
+        .iter()
+        .map(|p| Point::extract_bound(&p))
+        .collect::<PyResult<Vec<_>>>()?;
+    if starts.len() != ends.len() {
+        return Err(PyValueError::new_err(
+            "The number of start and end points must be equal",
+        ));
+    }
+    let position = position.map(|p| Point::extract_bound(&p)).transpose()?;
+    let start_directions = start_directions.unwrap_or(vec![None; starts.len()]);
+    if starts.len() != start_directions.len() {
+        return Err(PyValueError::new_err(
+            "The number of start points and starting directions must be equal",
+        ));
+    }
+    let end_directions = end_directions.unwrap_or(vec![None; ends.len()]);
+    if ends.len() != end_directions.len() {
+        return Err(PyValueError::new_err(
+            "The number of end points and ending directions must be equal",
+        ));
+    }
+    let labels = labels.unwrap_or(vec![None; starts.len()]);
+    if starts.len() != labels.len() {
+        return Err(PyValueError::new_err(
+            "The number of start points and labels must be equal",
+        ));
+    }
+    // Objects with a `__textdraw__` method are only drawn once, for both their boxes and pixels.
+    let environment =
+        objs_to_elements(&environment.unwrap_or(PyTuple::empty(py).as_any().clone()))?;
+    let barriers = objs_to_elements(&barriers.unwrap_or(PyTuple::empty(py).as_any().clone()))?;
+    let boxes = elements_to_box_bounds(&environment)?
+        .into_iter()
+        .chain(elements_to_box_bounds(&barriers)?)
+        .collect_vec();
+    let obstacles = if avoid_boxes {
+        boxes.iter().map(|bb| bb.expanded(clearance)).collect()
+    } else {
+        Vec::new()
+    };
+    let environment = elements_to_pixels(&environment)?;
+    let barriers = elements_to_pixels(&barriers)?;
+    let paths = objs_to_pixel_sets(&paths.unwrap_or(PyTuple::empty(py).as_any().clone()), false)?;
+    let bbox = bbox.map(|bb| BoundingBox::extract_bound(&bb)).transpose()?;
+    // Bundled paths which leave from (or arrive at) the same point all follow a trunk which is
+    // routed first and splits up just short of their other ends.
+    let mut trunks: Vec<Vec<Vec<Pixel>>> = vec![Vec::new(); starts.len()];
+    if edge_mode == EdgeMode::Bundle {
+        for (shared_ends, other_ends, outwards) in [(&starts, &ends, true), (&ends, &starts, false)]
+        {
+            let groups = (0..starts.len()).into_group_map_by(|&i| shared_ends[i]);
+            for (shared, members) in groups {
+                if members.len() < 2 {
+                    continue;
+                }
+                let others = members.iter().map(|&i| other_ends[i]).collect_vec();
+                let Some(split) = bundle_split(shared, &others) else {
+                    continue;
+                };
+                let directions = if outwards {
+                    &start_directions
+                } else {
+                    &end_directions
+                };
+                let direction = members
+                    .iter()
+                    .map(|&i| directions[i].clone())
+                    .all_equal_value()
+                    .ok()
+                    .flatten();
+                let (trunk_start, trunk_end, start_direction, end_direction) = if outwards {
+                    (shared, split, direction, None)
+                } else {
+                    (split, shared, None, direction)
+                };
+                // A bundle without a trunk still merges where it can, so a failed trunk is skipped.
+                let Ok(trunk) = TextPath::calculate_path(
+                    trunk_start,
+                    trunk_end,
+                    None,
+                    None,
+                    line_style.clone(),
+                    None,
+                    start_direction,
+                    end_direction,
+                    bend_penalty,
+                    &environment,
+                    &barriers,
+                    &paths,
+                    bbox,
+                    &boxes,
+                    &obstacles,
+                    &[],
+                    1,
+                    router,
+                    None,
+                ) else {
+                    continue;
+                };
+                let pixels = trunk.as_line_group()?.pixels;
+                for i in members {
+                    trunks[i].push(pixels.clone());
+                }
+            }
+        }
+    }
+    let mut best_paths: Vec<TextPath> = Vec::with_capacity(starts.len());
+    let mut best_cost: usize = usize::MAX;
+    let order: Vec<usize> = (0..starts.len()).collect();
+    let permutations = if optimize {
+        order
+            .iter()
+            .permutations(order.len())
+            .map(|p| p.into_iter().copied().collect())
+            .collect_vec()
+    } else {
+        vec![order]
+    };
+    for permutation in permutations {
+        let mut local_cost = 0;
+        let mut local_paths: Vec<TextPath> = Vec::with_capacity(starts.len());
+        let mut local_groups: Vec<PixelGroup> = Vec::with_capacity(starts.len());
+        let mut local_labels: Vec<Pixel> = Vec::new();
+        for i in permutation {
+            let local_sets = local_groups.iter().map(|g| g.pixels.clone());
+            let (local_environment, all_paths): (Vec<Pixel>, Vec<Vec<Pixel>>) = match edge_mode {
+                EdgeMode::Merge | EdgeMode::Bundle => (
+                    environment.clone(),
+                    local_sets.chain(paths.iter().cloned()).collect(),
+                ),
+                EdgeMode::Lanes => (
+                    environment
+                        .iter()
+                        .cloned()
+                        .chain(
+                            local_sets
+                                .flatten()
+                                .filter(|p| p.position != starts[i] && p.position != ends[i])
+                                .map(|p| Pixel {
+                                    weight: Some(p.weight.unwrap_or(LANE_WEIGHT)),
+                                    ..p
+                                }),
+                        )
+                        .collect(),
+                    paths.clone(),
+                ),
+            };
+            let textpath = TextPath::calculate_path(
+                starts[i],
+                ends[i],
+                position,
+                style.clone(),
+                line_style.clone(),
+                weight,
+                start_directions[i].clone(),
+                end_directions[i].clone(),
+                bend_penalty,
+                &local_environment,
+                &barriers,
+                &all_paths.iter().chain(&trunks[i]).cloned().collect_vec(),
+                bbox,
+                &boxes,
+                &obstacles,
+                &[],
+                // Zero-cost trunks only attract bundled paths if the heuristic never overestimates.
+                if edge_mode == EdgeMode::Bundle { 0 } else { 1 },
+                router,
+                Some(i),
+            )?
+            .with_crossing(crossing);
+            let mut occupied = occupied_cells(&local_environment, &barriers, &all_paths);
+            occupied.extend(local_labels.iter().map(|p| p.position));
+            let textpath = textpath.with_label(
+                labels[i].clone(),
+                label_style.clone(),
+                label_position,
+                &occupied,
+            );
+            local_cost += textpath.cost;
+            local_groups.push(textpath.as_line_group()?);
+            local_labels.extend(textpath.label_pixels());
+            local_paths.push(textpath);
+        }
+        if local_cost < best_cost {
+            best_cost = local_cost;
+            best_paths = local_paths;
+        }
+    }
+    if edge_mode != EdgeMode::Merge {
+        // Every path needs to see all of the others so that the junction glyphs where bundled
+        // paths split off (or where lanes cross) are drawn no matter which path is rendered last.
+        let members = best_paths
+            .iter()
+            .map(|p| p.as_line_group().map(|g| g.pixels))
+            .collect::<PyResult<Vec<_>>>()?;
+        for (i, path) in best_paths.iter_mut().enumerate() {
+            let others = members
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .map(|(_, pixels)| pixels.clone());
+            path.paths = path_identities(&paths.iter().cloned().chain(others).collect_vec());
+        }
+    }
+    Ok(best_paths)
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Alignment {
+    #[default]
+    Top,
+    Center,
+    Bottom,
+}
+impl FromStr for Alignment {
+    type Err = PyErr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "top" => Ok(Alignment::Top),
+            "center" => Ok(Alignment::Center),
+            "bottom" => Ok(Alignment::Bottom),
+            _ => Err(PyValueError::new_err("Invalid alignment")),
+        }
+    }
+}
+impl Display for Alignment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Alignment::Top => "top",
+                Alignment::Center => "center",
+                Alignment::Bottom => "bottom",
+            }
+        )
+    }
+}
+#[derive(Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Justification {
+    #[default]
+    Right,
+    Center,
+    Left,
+}
+impl FromStr for Justification {
+    type Err = PyErr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "right" => Ok(Justification::Right),
+            "center" => Ok(Justification::Center),
+            "left" => Ok(Justification::Left),
+            _ => Err(PyValueError::new_err("Invalid alignment")),
+        }
+    }
+}
+impl Display for Justification {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Justification::Right => "right",
+                Justification::Center => "center",
+                Justification::Left => "left",
+            }
+        )
+    }
+}
+
+/// A box which can contain some text in a border.
+///
+/// Parameters
+/// ----------
+/// text : str, default=''
+///     The text contained in the box.
+/// position : Point or tuple of ints, optional
+///     The position of the box (bottom-left corner, or top-left in screen coordinates).
+/// width : int, optional
+///     The width of the box (automatically determined by text if None).
+/// height : int, optional
+///     The height of the box (automatically determined by text if None).
+/// style : str, optional
+///     The style to apply to the text.
+/// border_style : str, optional
+///     The style to apply to the border.
+/// line_style : {'light', 'heavy', 'double'}, optional
+///     The set of characters to use for the border.
+/// weight : int, optional
+///     The weight to apply to each pixel in the box.
+/// padding : tuple of ints, default=(0, 1, 0, 1)
+///     The padding applied to each side of the text (top, right, bottom, left).
+/// padding_style : str, optional
+///     The style to apply to the padding.
+/// align : {'top', 'center', 'bottom'}
+///     The alignment of the text in the box.
+/// justify : {'left', 'center', 'right'}
+///     The justification of the text in the box.
+/// truncate_string : str, optional
+///     String to use if text is truncated.
+/// transparent : bool, default=False
+///     If True, the space inside the bounding box of the text will be transparent.
+/// transparent_padding : bool, default=False
+///     If True, the padding space will be transparent.
+/// title : str, optional
+///     Text drawn into the top border of the box (truncated to fit using `truncate_string`).
+/// title_style : str, optional
+///     The style to apply to the title (defaults to `border_style`).
+/// title_justify : {'left', 'center', 'right'}
+///     The justification of the title in the top border.
+/// footer : str, optional
+///     Text drawn into the bottom border of the box (truncated to fit using `truncate_string`).
+/// footer_style : str, optional
+///     The style to apply to the footer (defaults to `border_style`).
+/// footer_justify : {'left', 'center', 'right'}
+///     The justification of the footer in the bottom border.
+/// borders : tuple of bools, default=(True, True, True, True)
+///     Which sides of the border (top, right, bottom, left) are drawn. Hidden sides are left
+///     blank, so the size of the box does not change.
+/// shadow : str, optional
+///     A character (such as '▒' or '█') used to draw a drop shadow offset by (1, -1).
+/// shadow_style : str, optional
+///     The style to apply to the shadow.
+///
+/// Attributes
+/// ----------
+/// text : str
+///     The text contained in the box.
+/// position : Point or None
+///     The position of the box (bottom-left corner, or top-left in screen coordinates).
+/// width : int or None
+///     The width of the box (automatically determined by text if None).
+/// height : int or None
+///     The height of the box (automatically determined by text if None).
+/// style : str or None
+///     The style to apply to the text.
+/// border_style : str or None
+///     The style to apply to the border.
+/// line_style : {'light', 'heavy', 'double'} or None
+///     The set of characters to use for the border.
+/// weight : int or None
+///     The weight to apply to each pixel in the box.
+/// padding : tuple of ints
+///     The padding applied to each side of the text (top, right, bottom, left).
+/// padding_style : str or None
+///     The style to apply to the padding.
+/// align : {'top', 'center', 'bottom'}
+///     The alignment of the text in the box.
+/// justify : {'left', 'center', 'right'}
+///     The justification of the text in the box.
+/// truncate_string : str or None
+///     String to use if text is truncated.
+/// transparent : bool
+///     If True, the space inside the bounding box of the text will be transparent.
+/// transparent_padding : bool
+///     If True, the padding space will be transparent.
+/// title : str or None
+///     Text drawn into the top border of the box.
+/// title_style : Style
+///     The style to apply to the title.
+/// title_justify : {'left', 'center', 'right'}
+///     The justification of the title in the top border.
+/// footer : str or None
+///     Text drawn into the bottom border of the box.
+/// footer_style : Style
+///     The style to apply to the footer.
+/// footer_justify : {'left', 'center', 'right'}
+///     The justification of the footer in the bottom border.
+/// borders : tuple of bools
+///     Which sides of the border (top, right, bottom, left) are drawn.
+/// shadow : str or None
+///     The character used to draw a drop shadow.
+/// shadow_style : Style
+///     The style to apply to the shadow.
+/// bbox : BoundingBox
+///     The bounding box of the box itself, including its shadow.
+///
+#[pyclass(module = "textdraw")]
+#[derive(Clone, Serialize, Deserialize)]
+struct Box {
+    #[pyo3(get, set)]
+    text: String,
+    #[pyo3(get, set)]
+    position: Point,
+    #[pyo3(get, set)]
+    width: Option<usize>,
+    #[pyo3(get, set)]
+    height: Option<usize>,
+    #[pyo3(get, set)]
+    style: TextStyle,
+    #[pyo3(get, set)]
+    border_style: TextStyle,
+    line_style: Option<LineStyle>,
+    #[pyo3(get, set)]
+    weight: Option<usize>,
+    #[pyo3(get, set)]
+    padding: Option<(usize, usize, usize, usize)>,
+    #[pyo3(get, set)]
+    padding_style: TextStyle,
+    align: Alignment,
+    justify: Justification,
+    #[pyo3(get, set)]
+    truncate_string: Option<String>,
+    #[pyo3(get, set)]
+    transparent: bool,
+    #[pyo3(get, set)]
+    transparent_padding: bool,
+    #[pyo3(get, set)]
+    title: Option<String>,
+    #[pyo3(get, set)]
+    title_style: TextStyle,
+    title_justify: Justification,
+    #[pyo3(get, set)]
+    footer: Option<String>,
+    #[pyo3(get, set)]
+    footer_style: TextStyle,
+    footer_justify: Justification,
+    #[pyo3(get, set)]
+    borders: (bool, bool, bool, bool),
+    shadow: Option<char>,
+    #[pyo3(get, set)]
+    shadow_style: TextStyle,
+}
+#[pymethods]
+impl Box {
+    #[new]
+    #[pyo3(signature = (text = "", position = None, width = None, height = None, style = None, *, border_style = None, line_style = Some("light".to_string()), weight = 1, padding = None, padding_style = None, align = "top", justify= "left", truncate_string = None, transparent = false, transparent_padding = false, title = None, title_style = None, title_justify = "left", footer = None, footer_style = None, footer_justify = "left", borders = (true, true, true, true), shadow = None, shadow_style = None))]
+    fn new(
+        text: &str,
+        position: Option<Bound<PyAny>>,
+        width: Option<usize>,
+        height: Option<usize>,
+        style: Option<String>,
+        border_style: Option<String>,
+        line_style: Option<String>,
+        weight: Option<usize>,
+        padding: Option<(usize, usize, usize, usize)>,
+        padding_style: Option<String>,
+        align: &str,
+        justify: &str,
+        truncate_string: Option<String>,
+        transparent: bool,
+        transparent_padding: bool,
+        title: Option<String>,
+        title_style: Option<String>,
+        title_justify: &str,
+        footer: Option<String>,
+        footer_style: Option<String>,
+        footer_justify: &str,
+        borders: (bool, bool, bool, bool),
+        shadow: Option<String>,
+        shadow_style: Option<String>,
+    ) -> PyResult<Self> {
+        let pad = padding.unwrap_or_default();
+        if let Some(w) = width {
+            let min_width = pad.1 + pad.3 + 2;
+            if w < min_width {
+                return Err(PyValueError::new_err(format!(
+                    "Width {} is too small. Minimum width with current padding is {}",
+                    w, min_width
+                )));
+            }
+        }
+
+        if let Some(h) = height {
+            let min_height = pad.0 + pad.2 + 2;
+            if h < min_height {
+                return Err(PyValueError::new_err(format!(
+                    "Height {} is too small. Minimum height with current padding is {}",
+                    h, min_height
+                )));
+            }
+        }
+        Ok(Self {
+            text: text.to_string(),
+            position: position
+                .map(|p| Point::extract_bound(&p))
+                .transpose()?
+                .unwrap_or_default(),
+            width,
+            height,
+            style: style.unwrap_or_default().parse()?,
+            title_style: title_style
+                .or_else(|| border_style.clone())
+                .unwrap_or_default()
+                .parse()?,
+            footer_style: footer_style
+                .or_else(|| border_style.clone())
+                .unwrap_or_default()
+                .parse()?,
+            border_style: border_style.unwrap_or_default().parse()?,
+            line_style: line_style.map(|s| s.parse()).transpose()?,
+            weight,
+            padding,
+            padding_style: padding_style.unwrap_or_default().parse()?,
+            align: align.parse()?,
+            justify: justify.parse()?,
+            truncate_string,
+            transparent,
+            transparent_padding,
+            title,
+            title_justify: title_justify.parse()?,
+            footer,
+            footer_justify: footer_justify.parse()?,
+            borders,
+            shadow: shadow.map(|s| Box::parse_shadow(&s)).transpose()?,
+            shadow_style: shadow_style.unwrap_or_default().parse()?,
+        })
+    }
+    fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+        let default_style = TextStyle::default();
+        let style =
+            |style: &TextStyle, default: &TextStyle| keyword(py, style.spec(), style == default);
+        Ok(constructor_repr(
+            "Box",
+            &[
+                py_repr(py, self.text.as_str())?,
+                py_repr(py, self.position)?,
+            ],
+            &[
+                ("width", keyword(py, self.width, self.width.is_none())?),
+                ("height", keyword(py, self.height, self.height.is_none())?),
+                ("style", style(&self.style, &default_style)?),
+                ("border_style", style(&self.border_style, &default_style)?),
+                (
+                    "line_style",
+                    keyword(
+                        py,
+                        self.line_style.map(|s| s.to_string()),
+                        self.line_style == Some(LineStyle::Light),
+                    )?,
+                ),
+                ("weight", keyword(py, self.weight, self.weight == Some(1))?),
+                (
+                    "padding",
+                    keyword(py, self.padding, self.padding.is_none())?,
+                ),
+                ("padding_style", style(&self.padding_style, &default_style)?),
+                (
+                    "align",
+                    keyword(py, self.align.to_string(), self.align == Alignment::Top)?,
+                ),
+                (
+                    "justify",
+                    keyword(
+                        py,
+                        self.justify.to_string(),
+                        self.justify == Justification::Left,
+                    )?,
+                ),
+                (
+                    "truncate_string",
+                    keyword(
+                        py,
+                        self.truncate_string.as_deref(),
+                        self.truncate_string.is_none(),
+                    )?,
+                ),
+                (
+                    "transparent",
+                    keyword(py, self.transparent, !self.transparent)?,
+                ),
+                (
+                    "transparent_padding",
+                    keyword(py, self.transparent_padding, !self.transparent_padding)?,
+                ),
+                (
+                    "title",
+                    keyword(py, self.title.as_deref(), self.title.is_none())?,
+                ),
+                // The title and footer take the border style unless they have their own.
+                ("title_style", style(&self.title_style, &self.border_style)?),
+                (
+                    "title_justify",
+                    keyword(
+                        py,
+                        self.title_justify.to_string(),
+                        self.title_justify == Justification::Left,
+                    )?,
+                ),
+                (
+                    "footer",
+                    keyword(py, self.footer.as_deref(), self.footer.is_none())?,
+                ),
+                (
+                    "footer_style",
+                    style(&self.footer_style, &self.border_style)?,
+                ),
+                (
+                    "footer_justify",
+                    keyword(
+                        py,
+                        self.footer_justify.to_string(),
+                        self.footer_justify == Justification::Left,
+                    )?,
+                ),
+                (
+                    "borders",
+                    keyword(py, self.borders, self.borders == (true, true, true, true))?,
+                ),
+                (
+                    "shadow",
+                    keyword(py, self.get_shadow(), self.shadow.is_none())?,
+                ),
+                ("shadow_style", style(&self.shadow_style, &default_style)?),
+            ],
+        ))
+    }
+    fn __reduce__<'py>(&self, py: Python<'py>) -> PyResult<(Bound<'py, PyAny>, (String,))> {
+        reduce(py, Record::Box(self.clone()))
+    }
+    fn __copy__(&self) -> Self {
+        self.clone()
+    }
+    fn __deepcopy__(&self, _memo: Bound<PyAny>) -> Self {
+        self.clone()
+    }
+    #[getter]
+    fn get_shadow(&self) -> Option<String> {
+        self.shadow.map(|c| c.to_string())
+    }
+    #[setter]
+    fn set_shadow(&mut self, shadow: Option<String>) -> PyResult<()> {
+        self.shadow = shadow.map(|s| Box::parse_shadow(&s)).transpose()?;
+        Ok(())
+    }
+    #[getter]
+    fn get_line_style(&self) -> Option<String> {
+        self.line_style.map(|s| s.to_string())
+    }
+    #[setter]
+    fn set_line_style(&mut self, line_style: Option<String>) -> PyResult<()> {
+        self.line_style = line_style.map(|s| s.parse()).transpose()?;
+        Ok(())
+    }
+    #[getter]
+    fn get_align(&self) -> String {
+        self.align.to_string()
+    }
+    #[setter]
+    fn set_align(&mut self, align: String) -> PyResult<()> {
+        self.align = align.parse()?;
+        Ok(())
+    }
+    #[getter]
+    fn get_justify(&self) -> String {
+        self.justify.to_string()
+    }
+    #[setter]
+    fn set_justify(&mut self, justify: String) -> PyResult<()> {
+        self.justify = justify.parse()?;
+        Ok(())
+    }
+    #[getter]
+    fn get_title_justify(&self) -> String {
+        self.title_justify.to_string()
+    }
+    #[setter]
+    fn set_title_justify(&mut self, title_justify: String) -> PyResult<()> {
+        self.title_justify = title_justify.parse()?;
+        Ok(())
+    }
+    #[getter]
+    fn get_footer_justify(&self) -> String {
+        self.footer_justify.to_string()
+    }
+    #[setter]
+    fn set_footer_justify(&mut self, footer_justify: String) -> PyResult<()> {
+        self.footer_justify = footer_justify.parse()?;
+        Ok(())
+    }
+    #[getter]
+    fn get_bbox(&self) -> BoundingBox {
+        let (_, bbox) = self.anchored().format_box();
+        bbox
+    }
+    #[getter]
+    fn get_text_bbox(&self) -> BoundingBox {
+        let (_, bbox) = self.anchored().format_text();
+        bbox
+    }
+    /// Duplicate a Box at a given position.
+    ///
+    /// Parameters
+    /// ----------
+    /// position : Point or tuple of ints, optional
+    ///     The (x, y) coordinates of the new Box.
+    ///
+    /// Returns
+    /// -------
+    /// Box
+    ///     A new Box at the given position.
+    ///
+    #[pyo3(name = "duplicate", signature = (position = None))]
+    fn py_duplicate(&self, position: Option<Bound<PyAny>>) -> PyResult<Self> {
+        Ok(self.duplicate(
+            position
+                .map(|p| Point::extract_bound(&p))
+                .transpose()?
+                .unwrap_or_default(),
+        ))
+    }
+    /// Duplicate a Box shifted by the given delta.
+    ///
+    /// Parameters
+    /// ----------
+    /// delta : Point or tuple of ints
+    ///     The (x, y) shift difference.
+    ///
+    /// Returns
+    /// -------
+    /// Box
+    ///     A new Box shifted by the given delta.
+    ///
+    #[pyo3(name = "duplicate_shifted")]
+    fn py_duplicate_shifted(&self, delta: Bound<PyAny>) -> PyResult<Self> {
+        Ok(self.duplicate_shifted(Point::extract_bound(&delta)?))
+    }
+}
+impl Box {
+    /// The box moved so that its position is its bottom-left corner, which is what the drawing
+    /// code expects whichever coordinate system is in use.
+    fn anchored(&self) -> Cow<'_, Self> {
+        let (_, total_height) = self.size(&self.format_text().1);
+        let position = Coordinates::bottom_left(self.position, total_height);
+        if position == self.position {
+            Cow::Borrowed(self)
+        } else {
+            Cow::Owned(self.duplicate(position))
+        }
+    }
+    /// The width and height of the box including its border, given the bounds of its text.
+    fn size(&self, bb_text: &BoundingBox) -> (usize, usize) {
+        let padding = self.padding.unwrap_or_default();
+        if let (Some(w), Some(h)) = (self.width, self.height) {
+            (w, h)
+        } else {
+            let width = self
+                .width
+                .unwrap_or_else(|| bb_text.width() + padding.1 + padding.3 + 2);
+            let height = self
+                .height
+                .unwrap_or_else(|| bb_text.height() + padding.0 + padding.2 + 2);
+            (width, height)
+        }
+    }
+    fn format_box(&self) -> (HashMap<Point, Pixel>, BoundingBox) {
+        let (text, bb_text) = self.format_text();
+        let (total_width, total_height) = self.size(&bb_text);
+        let bb_border = BoundingBox::new(
+            self.position.1 + total_height as isize - 1,
+            self.position.0 + total_width as isize - 1,
+            self.position.1,
+            self.position.0,
+        );
+        let mut pixels: HashMap<Point, Pixel> = bb_border.as_map(
+            &self.border_style,
+            &self.padding_style,
+            self.line_style,
+            self.weight,
+            self.transparent_padding,
+            self.borders,
+        );
+        pixels.extend(text);
+        for (text, style, justify, y) in [
+            (
+                &self.title,
+                &self.title_style,
+                self.title_justify,
+                bb_border.top,
+            ),
+            (
+                &self.footer,
+                &self.footer_style,
+                self.footer_justify,
+                bb_border.bottom,
+            ),
+        ] {
+            if let Some(text) = text {
+                pixels.extend(
+                    self.border_text(text, style, justify, &bb_border, y)
+                        .into_iter()
+                        .map(|p| (p.position, p)),
+                );
+            }
+        }
+        let mut bbox = bb_border;
+        if let Some(character) = self.shadow {
+            let shadow = (bb_border.left + 1..=bb_border.right + 1)
+                .map(|x| Point(x, bb_border.bottom - 1))
+                .chain((bb_border.bottom..bb_border.top).map(|y| Point(bb_border.right + 1, y)));
+            for position in shadow {
+                bbox += position;
+                pixels.insert(
+                    position,
+                    Pixel {
+                        character,
+                        position,
+                        style: self.shadow_style.clone(),
+                        weight: self.weight,
+                    },
+                );
+            }
+        }
+        (pixels, bbox)
+    }
+    fn parse_shadow(shadow: &str) -> PyResult<char> {
+        let mut chars = shadow.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(c),
+            _ => Err(PyValueError::new_err(format!(
+                "Shadow must be a single character [{}]",
+                shadow
+            ))),
+        }
+    }
+    /// Lays out a title or footer in the border row at height `y`.
+    ///
+    /// A rule cell is kept next to each corner and the text is surrounded by a space on either
+    /// side, so the text is truncated to fit in the remaining cells.
+    fn border_text(
+        &self,
+        text: &str,
+        style: &TextStyle,
+        justify: Justification,
+        bb_border: &BoundingBox,
+        y: isize,
+    ) -> Vec<Pixel> {
+        let room = (bb_border.right - bb_border.left - 5).max(0) as usize;
+        let mut chars: Vec<char> = text.chars().collect();
+        if chars.len() > room {
+            let trunc: Vec<char> = self
+                .truncate_string
+                .as_deref()
+                .unwrap_or_default()
+                .chars()
+                .collect();
+            let fits = trunc.len() < room;
+            chars.truncate(if fits { room - trunc.len() } else { room });
+            // A space at the cut would double up with the space around the text.
+            while chars.last().is_some_and(|c| c.is_whitespace()) {
+                chars.pop();
+            }
+            if fits {
+                chars.extend(trunc);
+            }
+        }
+        if chars.is_empty() {
+            return Vec::new();
+        }
+        let span = (bb_border.right - bb_border.left - 3) as usize;
+        let length = chars.len() + 2;
+        let start = bb_border.left
+            + 2
+            + match justify {
+                Justification::Left => 0,
+                Justification::Center => (span - length) / 2,
+                Justification::Right => span - length,
+            } as isize;
+        std::iter::once((' ', &self.border_style))
+            .chain(chars.into_iter().map(|c| (c, style)))
+            .chain(std::iter::once((' ', &self.border_style)))
+            .enumerate()
+            .map(|(i, (character, style))| Pixel {
+                character,
+                position: Point(start + i as isize, y),
+                style: style.clone(),
+                weight: self.weight,
+            })
+            .collect()
+    }
+    fn as_group(&self) -> PixelGroup {
+        PixelGroup {
+            pixels: self.anchored().format_box().0.into_values().collect(),
+            position: Point::default(),
+            style: TextStyle::default(),
+            weight: self.weight,
+        }
+    }
+    fn format_text(&self) -> (HashMap<Point, Pixel>, BoundingBox) {
+        let trunc = self.truncate_string.clone().unwrap_or("".to_string());
+        let mut raw_lines = Vec::new();
+        let padding = self.padding.unwrap_or_default();
+
+        let content_width = if let Some(total_width) = self.width {
+            total_width.saturating_sub(2 + padding.3 + padding.1)
+        } else {
+            self.text.lines().map(|line| line.len()).max().unwrap_or(0)
+        };
+
+        for line in self.text.lines() {
+            if content_width > 0 {
+                let mut processed_line = line.to_string();
+
+                if processed_line.len() > content_width {
+                    if !trunc.is_empty() && trunc.len() <= content_width {
+                        processed_line.truncate(content_width - trunc.len());
+                        processed_line.push_str(&trunc);
+                    } else if !trunc.is_empty() && trunc.len() > content_width {
+                        processed_line.clear();
+                    } else {
+                        processed_line.truncate(content_width);
+                    }
+                }
+
+                raw_lines.push(processed_line);
+            } else {
+                raw_lines.push(String::new());
+            }
+        }
+
+        // This doesn't seem necessary
+        let effective_width = if self.width.is_some() {
+            content_width
+        } else {
+            raw_lines.iter().map(|l| l.len()).max().unwrap_or(0)
+        };
+
+        let effective_height = if let Some(total_height) = self.height {
+            total_height.saturating_sub(2 + padding.0 + padding.2)
+        } else {
+            raw_lines.len()
+        };
+
+        if raw_lines.len() > effective_height {
+            raw_lines.truncate(effective_height);
+        }
+
+        let pad_line = |line: &str| -> Vec<Option<String>> {
+            let padding_chars = effective_width.saturating_sub(line.len());
+            let (left_pad, right_pad) = match self.justify {
+                Justification::Left => (0, padding_chars),
+                Justification::Right => (padding_chars, 0),
+                Justification::Center => (padding_chars / 2, padding_chars - padding_chars / 2),
+            };
+            let mut row = vec![None; left_pad];
+            row.extend(line.chars().map(|c| Some(c.to_string())));
+            row.extend(std::iter::repeat_n(None, right_pad));
+            row
+        };
+
+        let padded_lines: Vec<Vec<Option<String>>> =
+            raw_lines.iter().map(|l| pad_line(l)).collect();
+
+        let blank_row: Vec<Option<String>> = vec![None; effective_width];
+        let vertical_padding = effective_height.saturating_sub(padded_lines.len());
+        let (top_pad, bottom_pad) = match self.align {
+            Alignment::Top => (0, vertical_padding),
+            Alignment::Bottom => (vertical_padding, 0),
+            Alignment::Center => (
+                vertical_padding / 2,
+                vertical_padding - vertical_padding / 2,
+            ),
+        };
+
+        let mut result = Vec::new();
+        result.extend(std::iter::repeat_n(blank_row.clone(), bottom_pad));
+        result.extend(padded_lines.into_iter().rev().collect::<Vec<_>>());
+        result.extend(std::iter::repeat_n(blank_row, top_pad));
+
+        let text_start_x = self.position.0 + 1 + padding.3 as isize;
+        let text_start_y = self.position.1 + 1 + padding.2 as isize;
+
+        (
+            result
+                .iter()
+                .enumerate()
+                .flat_map(|(j, chars)| {
+                    chars
+                        .iter()
+                        .enumerate()
+                        .filter_map(|(i, c)| match c {
+                            Some(chr) => Some(Pixel {
+                                character: chr.chars().collect::<Vec<char>>()[0],
+                                position: Point::new(
+                                    text_start_x + i as isize,
+                                    text_start_y + j as isize,
+                                ),
+                                style: self.style.clone(),
+                                weight: self.weight,
+                            }),
+                            None => {
+                                if self.transparent {
+                                    None
+                                } else {
+                                    Some(Pixel {
+                                        character: ' ',
+                                        position: Point::new(
+                                            text_start_x + i as isize,
+                                            text_start_y + j as isize,
+                                        ),
+                                        style: self.style.clone(),
+                                        weight: self.weight,
+                                    })
+                                }
+                            }
+                        })
+                        .collect::<Vec<Pixel>>()
+                })
+                .map(|p| (p.position, p))
+                .collect(),
+            BoundingBox {
+                top: text_start_y + effective_height as isize - 1,
+                right: text_start_x + effective_width as isize - 1,
+                bottom: text_start_y,
+                left: text_start_x,
+            },
+        )
+    }
+}
+
+/// A table of text cells separated by grid lines.
+///
+/// Parameters
+/// ----------
+/// rows : list of list
+///     The cells of each row. Each cell is converted to a string and may span several lines.
+/// position : Point or tuple of ints, optional
+///     The position of the table (bottom-left corner, or top-left in screen coordinates).
+/// headers : list of str, optional
+///     The column headers, separated from the rows by a rule.
+/// style : str, optional
+///     The style to apply to the cells.
+/// header_style : str, optional
+///     The style to apply to the headers (defaults to `style`).
+/// border_style : str, optional
+///     The style to apply to the grid lines.
+/// line_style : {'light', 'heavy', 'double'}, default='light'
+///     The set of characters to use for the grid lines.
+/// weight : int, optional
+///     The weight to apply to each pixel in the table.
+/// justify : {'left', 'center', 'right'} or list of them, default='left'
+///     The justification of every column, or of each column in turn.
+/// widths : list of int or None, optional
+///     The width of the contents of each column. Columns without a width fit their contents.
+/// padding : int, default=1
+///     The number of spaces on either side of the contents of each cell.
+/// row_lines : bool, default=False
+///     If True, rules are also drawn between rows.
+/// truncate_string : str, optional
+///     String to use if the contents of a cell are truncated to fit a fixed width.
+///
+/// Attributes
+/// ----------
+/// rows : list of list of str
+///     The cells of each row.
+/// position : Point
+///     The position of the table (bottom-left corner, or top-left in screen coordinates).
+/// headers : list of str or None
+///     The column headers.
+/// style : Style
+///     The style to apply to the cells.
+/// header_style : Style
+///     The style to apply to the headers.
+/// border_style : Style
+///     The style to apply to the grid lines.
+/// line_style : {'light', 'heavy', 'double'}
+///     The set of characters to use for the grid lines.
+/// weight : int or None
+///     The weight to apply to each pixel in the table.
+/// justify : list of {'left', 'center', 'right'}
+///     The justification of each column.
+/// widths : list of int or None, or None
+///     The fixed widths of the column contents.
+/// padding : int
+///     The number of spaces on either side of the contents of each cell.
+/// row_lines : bool
+///     Whether rules are drawn between rows.
+/// truncate_string : str or None
+///     String to use if the contents of a cell are truncated.
+/// bbox : BoundingBox
+///     The bounding box of the table.
+///
+/// Examples
+/// --------
+/// >>> from textdraw import Table, render
+/// >>> t = Table([['api', 'up'], ['db', 'down']], headers=['service', 'status'])
+/// >>> print(render([t]))
+/// ┌─────────┬────────┐
+/// │ service │ status │
+/// ├─────────┼────────┤
+/// │ api     │ up     │
+/// │ db      │ down   │
+/// └─────────┴────────┘
+///
+#[pyclass(module = "textdraw")]
+#[derive(Clone, Serialize, Deserialize)]
+struct Table {
+    #[pyo3(get, set)]
+    rows: Vec<Vec<String>>,
+    #[pyo3(get, set)]
+    position: Point,
+    #[pyo3(get, set)]
+    headers: Option<Vec<String>>,
+    #[pyo3(get, set)]
+    style: TextStyle,
+    #[pyo3(get, set)]
+    header_style: TextStyle,
+    #[pyo3(get, set)]
+    border_style: TextStyle,
+    line_style: LineStyle,
+    #[pyo3(get, set)]
+    weight: Option<usize>,
+    /// The justification of each column, where the last entry applies to any remaining columns.
+    justify: Vec<Justification>,
+    #[pyo3(get, set)]
+    widths: Option<Vec<Option<usize>>>,
+    #[pyo3(get, set)]
+    padding: usize,
+    #[pyo3(get, set)]
+    row_lines: bool,
+    #[pyo3(get, set)]
+    truncate_string: Option<String>,
+}
+
+/// The positions of the columns, rows and rules of a `Table`.
+struct TableLayout {
+    bbox: BoundingBox,
+    /// The left x-coordinate and width of each column, including padding.
+    columns: Vec<(isize, usize)>,
+    /// The top y-coordinate and height of the header row, if there are headers.
+    header: Option<(isize, usize)>,
+    /// The top y-coordinate and height of each row.
+    rows: Vec<(isize, usize)>,
+    /// The y-coordinates of the horizontal rules.
+    rules: Vec<isize>,
+}
+
+#[pymethods]
+impl Table {
+    #[new]
+    #[pyo3(signature = (rows, position = None, *, headers = None, style = None, header_style = None, border_style = None, line_style = "light", weight = 1, justify = None, widths = None, padding = 1, row_lines = false, truncate_string = None))]
+    fn new(
+        rows: Vec<Vec<Bound<PyAny>>>,
+        position: Option<Bound<PyAny>>,
+        headers: Option<Vec<String>>,
+        style: Option<String>,
+        header_style: Option<String>,
+        border_style: Option<String>,
+        line_style: &str,
+        weight: Option<usize>,
+        justify: Option<Bound<PyAny>>,
+        widths: Option<Vec<Option<usize>>>,
+        padding: usize,
+        row_lines: bool,
+        truncate_string: Option<String>,
+    ) -> PyResult<Self> {
+        Ok(Self {
+            rows: rows
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|cell| Ok(cell.str()?.to_string()))
+                        .collect::<PyResult<Vec<_>>>()
+                })
+                .collect::<PyResult<Vec<_>>>()?,
+            position: position
+                .map(|p| Point::extract_bound(&p))
+                .transpose()?
+                .unwrap_or_default(),
+            headers,
+            header_style: header_style
+                .or_else(|| style.clone())
+                .unwrap_or_default()
+                .parse()?,
+            style: style.unwrap_or_default().parse()?,
+            border_style: border_style.unwrap_or_default().parse()?,
+            line_style: line_style.parse()?,
+            weight,
+            justify: Table::parse_justify(justify)?,
+            widths,
+            padding,
+            row_lines,
+            truncate_string,
+        })
+    }
+    fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+        let default_style = TextStyle::default();
+        let style =
+            |style: &TextStyle, default: &TextStyle| keyword(py, style.spec(), style == default);
+        let justify = match self.justify.as_slice() {
+            [justify] => py_repr(py, justify.to_string())?,
+            justify => py_repr(
+                py,
+                justify.iter().map(|j| j.to_string()).collect::<Vec<_>>(),
+            )?,
+        };
+        Ok(constructor_repr(
+            "Table",
+            &[py_repr(py, self.rows.clone())?, py_repr(py, self.position)?],
+            &[
+                (
+                    "headers",
+                    keyword(py, self.headers.clone(), self.headers.is_none())?,
+                ),
+                ("style", style(&self.style, &default_style)?),
+                // The headers take the cell style unless they have their own.
+                ("header_style", style(&self.header_style, &self.style)?),
+                ("border_style", style(&self.border_style, &default_style)?),
+                (
+                    "line_style",
+                    keyword(
+                        py,
+                        self.line_style.to_string(),
+                        self.line_style == LineStyle::Light,
+                    )?,
+                ),
+                ("weight", keyword(py, self.weight, self.weight == Some(1))?),
+                (
+                    "justify",
+                    (self.justify != [Justification::Left]).then_some(justify),
+                ),
+                (
+                    "widths",
+                    keyword(py, self.widths.clone(), self.widths.is_none())?,
+                ),
+                ("padding", keyword(py, self.padding, self.padding == 1)?),
+                ("row_lines", keyword(py, self.row_lines, !self.row_lines)?),
+                (
+                    "truncate_string",
+                    keyword(
+                        py,
+                        self.truncate_string.as_deref(),
+                        self.truncate_string.is_none(),
+                    )?,
+                ),
+            ],
+        ))
+    }
+    fn __reduce__<'py>(&self, py: Python<'py>) -> PyResult<(Bound<'py, PyAny>, (String,))> {
+        reduce(py, Record::Table(self.clone()))
+    }
+    fn __copy__(&self) -> Self {
+        self.clone()
+    }
+    fn __deepcopy__(&self, _memo: Bound<PyAny>) -> Self {
+        self.clone()
+    }
+    #[getter]
+    fn get_line_style(&self) -> String {
+        self.line_style.to_string()
+    }
+    #[setter]
+    fn set_line_style(&mut self, line_style: String) -> PyResult<()> {
+        self.line_style = line_style.parse()?;
+        Ok(())
+    }
+    #[getter]
+    fn get_justify(&self) -> Vec<String> {
+        (0..self.column_count())
+            .map(|c| self.column_justify(c).to_string())
+            .collect()
+    }
+    #[setter]
+    fn set_justify(&mut self, justify: Option<Bound<PyAny>>) -> PyResult<()> {
+        self.justify = Table::parse_justify(justify)?;
+        Ok(())
+    }
+    #[getter]
+    fn get_bbox(&self) -> BoundingBox {
+        self.layout().bbox
+    }
+    /// The bounding box of a cell, including its padding.
+    ///
+    /// Parameters
+    /// ----------
+    /// row : int
+    ///     The index of the row (not counting the headers).
+    /// column : int
+    ///     The index of the column.
+    ///
+    /// Returns
+    /// -------
+    /// BoundingBox
+    ///     The bounding box of the cell.
+    ///
+    /// Raises
+    /// ------
+    /// IndexError
+    ///     If the row or column does not exist.
+    ///
+    fn cell_bbox(&self, row: usize, column: usize) -> PyResult<BoundingBox> {
+        let layout = self.layout();
+        let row = layout
+            .rows
+            .get(row)
+            .ok_or_else(|| PyIndexError::new_err(format!("Row {} is out of range", row)))?;
+        Table::span_bbox(&layout, *row, column)
+    }
+    /// The bounding box of a header cell, including its padding.
+    ///
+    /// Parameters
+    /// ----------
+    /// column : int
+    ///     The index of the column.
+    ///
+    /// Returns
+    /// -------
+    /// BoundingBox
+    ///     The bounding box of the header cell.
+    ///
+    /// Raises
+    /// ------
+    /// IndexError
+    ///     If the table has no headers or the column does not exist.
+    ///
+    fn header_bbox(&self, column: usize) -> PyResult<BoundingBox> {
+        let layout = self.layout();
+        let header = layout
+            .header
+            .ok_or_else(|| PyIndexError::new_err("The table has no headers"))?;
+        Table::span_bbox(&layout, header, column)
+    }
+    /// Duplicate a Table at a given position.
+    ///
+    /// Parameters
+    /// ----------
+    /// position : Point or tuple of ints, optional
+    ///     The (x, y) coordinates of the new Table.
+    ///
+    /// Returns
+    /// -------
+    /// Table
+    ///     A new Table at the given position.
+    ///
+    #[pyo3(name = "duplicate", signature = (position = None))]
+    fn py_duplicate(&self, position: Option<Bound<PyAny>>) -> PyResult<Self> {
+        Ok(self.duplicate(
+            position
+                .map(|p| Point::extract_bound(&p))
+                .transpose()?
+                .unwrap_or_default(),
+        ))
+    }
+    /// Duplicate a Table shifted by the given delta.
+    ///
+    /// Parameters
+    /// ----------
+    /// delta : Point or tuple of ints
+    ///     The (x, y) shift difference.
+    ///
+    /// Returns
+    /// -------
+    /// Table
+    ///     A new Table shifted by the given delta.
+    ///
+    #[pyo3(name = "duplicate_shifted")]
+    fn py_duplicate_shifted(&self, delta: Bound<PyAny>) -> PyResult<Self> {
+        Ok(self.duplicate_shifted(Point::extract_bound(&delta)?))
+    }
+}
+impl Table {
+    fn parse_justify(justify: Option<Bound<PyAny>>) -> PyResult<Vec<Justification>> {
+        let Some(justify) = justify else {
+            return Ok(vec![Justification::Left]);
+        };
+        if let Ok(justify) = justify.extract::<String>() {
+            Ok(vec![justify.parse()?])
+        } else {
+            let justify = justify
+                .extract::<Vec<String>>()?
+                .iter()
+                .map(|j| j.parse())
+                .collect::<PyResult<Vec<Justification>>>()?;
+            if justify.is_empty() {
+                Ok(vec![Justification::Left])
+            } else {
+                Ok(justify)
+            }
+        }
+    }
+    fn column_justify(&self, column: usize) -> Justification {
+        self.justify
+            .get(column)
+            .or(self.justify.last())
+            .copied()
+            .unwrap_or(Justification::Left)
+    }
+    fn column_count(&self) -> usize {
+        self.rows
+            .iter()
+            .map(|row| row.len())
+            .chain(self.headers.as_ref().map(|h| h.len()))
+            .max()
+            .unwrap_or(0)
+    }
+    fn span_bbox(
+        layout: &TableLayout,
+        (top, height): (isize, usize),
+        column: usize,
+    ) -> PyResult<BoundingBox> {
+        let &(left, width) = layout
+            .columns
+            .get(column)
+            .ok_or_else(|| PyIndexError::new_err(format!("Column {} is out of range", column)))?;
+        Ok(BoundingBox::new(
+            top,
+            left + width as isize - 1,
+            top - height as isize + 1,
+            left,
+        ))
+    }
+    fn layout(&self) -> TableLayout {
+        let columns = self.column_count();
+        fn cell(row: &[String], c: usize) -> &str {
+            row.get(c).map_or("", |s| s.as_str())
+        }
+        let widths: Vec<usize> = (0..columns)
+            .map(|c| {
+                self.widths
+                    .as_ref()
+                    .and_then(|w| w.get(c).copied().flatten())
+                    .unwrap_or_else(|| {
+                        self.rows
+                            .iter()
+                            .chain(self.headers.as_ref())
+                            .flat_map(|row| cell(row, c).lines())
+                            .map(|line| line.chars().count())
+                            .max()
+                            .unwrap_or(0)
+                    })
+                    + 2 * self.padding
+            })
+            .collect();
+        let height = |row: &Vec<String>| {
+            (0..columns)
+                .map(|c| cell(row, c).lines().count())
+                .max()
+                .unwrap_or(0)
+                .max(1)
+        };
+        let mut x = self.position.0 + 1;
+        let column_spans: Vec<(isize, usize)> = widths
+            .iter()
+            .map(|&w| {
+                let span = (x, w);
+                x += w as isize + 1;
+                span
+            })
+            .collect();
+        let right = (x - 1).max(self.position.0 + 1);
+        let body: usize = self.rows.iter().map(height).sum::<usize>()
+            + if self.row_lines {
+                self.rows.len().saturating_sub(1)
+            } else {
+                0
+            };
+        let total_height = 2
+            + self.headers.as_ref().map_or(0, |h| height(h) + 1)
+            + body
+            + if self.headers.is_some() || !self.rows.is_empty() {
+                0
+            } else {
+                1
+            };
+        let origin = Coordinates::bottom_left(self.position, total_height);
+        let top = origin.1 + total_height as isize - 1;
+        let mut rules = vec![top];
+        let mut y = top - 1;
+        let header = self.headers.as_ref().map(|h| {
+            let span = (y, height(h));
+            y -= span.1 as isize;
+            rules.push(y);
+            y -= 1;
+            span
+        });
+        let mut rows = Vec::with_capacity(self.rows.len());
+        for (i, row) in self.rows.iter().enumerate() {
+            rows.push((y, height(row)));
+            y -= height(row) as isize;
+            if self.row_lines && i + 1 < self.rows.len() {
+                rules.push(y);
+                y -= 1;
+            }
+        }
+        rules.push(origin.1);
+        rules.dedup();
+        TableLayout {
+            bbox: BoundingBox::new(top, right, origin.1, self.position.0),
+            columns: column_spans,
+            header,
+            rows,
+            rules,
+        }
+    }
+    /// Lays out the lines of a cell, truncated and justified to the width of its column.
+    fn cell_text(
+        &self,
+        text: &str,
+        (left, width): (isize, usize),
+        (top, height): (isize, usize),
+        justify: Justification,
+        style: &TextStyle,
+    ) -> Vec<Pixel> {
+        let content = width.saturating_sub(2 * self.padding);
+        let trunc: Vec<char> = self
+            .truncate_string
+            .as_deref()
+            .unwrap_or_default()
+            .chars()
+            .collect();
+        let mut pixels = Vec::new();
+        for (j, line) in text.lines().take(height).enumerate() {
+            let mut chars: Vec<char> = line.chars().collect();
+            if chars.len() > content {
+                if !trunc.is_empty() && trunc.len() <= content {
+                    chars.truncate(content - trunc.len());
+                    chars.extend(&trunc);
+                } else {
+                    chars.truncate(content);
+                }
+            }
+            let free = content - chars.len();
+            let offset = match justify {
+                Justification::Left => 0,
+                Justification::Center => free / 2,
+                Justification::Right => free,
+            };
+            let x = left + (self.padding + offset) as isize;
+            pixels.extend(chars.into_iter().enumerate().map(|(i, character)| Pixel {
+                character,
+                position: Point(x + i as isize, top - j as isize),
+                style: style.clone(),
+                weight: self.weight,
+            }));
+        }
+        pixels
+    }
+    fn format_table(&self) -> HashMap<Point, Pixel> {
+        let layout = self.layout();
+        let bb = layout.bbox;
+        let mut links: HashMap<Point, [bool; 4]> = HashMap::new();
+        for &y in &layout.rules {
+            for x in bb.left..bb.right {
+                links.entry(Point(x, y)).or_default()[Direction::Right.index()] = true;
+                links.entry(Point(x + 1, y)).or_default()[Direction::Left.index()] = true;
+            }
+        }
+        let verticals = [bb.left, bb.right].into_iter().chain(
+            layout
+                .columns
+                .iter()
+                .map(|&(left, width)| left + width as isize),
+        );
+        for x in verticals {
+            for y in bb.bottom..bb.top {
+                links.entry(Point(x, y)).or_default()[Direction::Up.index()] = true;
+                links.entry(Point(x, y + 1)).or_default()[Direction::Down.index()] = true;
+            }
+        }
+        let mut pixels: HashMap<Point, Pixel> = HashMap::new();
+        for y in bb.bottom..=bb.top {
+            for x in bb.left..=bb.right {
+                let position = Point(x, y);
+                let pixel = match links.get(&position) {
+                    Some(&[up, right, down, left]) => Pixel {
+                        character: self.line_style.get_char((up, right, down, left)),
+                        position,
+                        style: self.border_style.clone(),
+                        weight: self.weight,
+                    },
+                    None => Pixel {
+                        character: ' ',
+                        position,
+                        style: if layout
+                            .header
+                            .is_some_and(|(top, height)| y <= top && y > top - height as isize)
+                        {
+                            self.header_style.clone()
+                        } else {
+                            self.style.clone()
+                        },
+                        weight: self.weight,
+                    },
+                };
+                pixels.insert(position, pixel);
+            }
+        }
+        let cells = self
+            .headers
+            .iter()
+            .zip(layout.header)
+            .map(|(row, span)| (row, span, &self.header_style))
+            .chain(
+                self.rows
+                    .iter()
+                    .zip(layout.rows.iter().copied())
+                    .map(|(row, span)| (row, span, &self.style)),
+            );
+        for (row, row_span, style) in cells {
+            for (c, text) in row.iter().enumerate() {
+                let justify = self.column_justify(c);
+                for pixel in self.cell_text(text, layout.columns[c], row_span, justify, style) {
+                    pixels.insert(pixel.position, pixel);
                 }
             }
         }
-        return Ok(arrow.render(&direction));
+        pixels
+    }
+    fn as_group(&self) -> PixelGroup {
+        PixelGroup {
+            pixels: self.format_table().into_values().collect(),
+            position: Point::default(),
+            style: TextStyle::default(),
+            weight: self.weight,
+        }
     }
-    Err(PyValueError::new_err("Failed to parse style string"))
 }
 
-#[derive(Eq, PartialEq)]
-struct State {
-    cost: usize,
-    pos: Point,
-    dir: Option<Direction>,
+/// The children of a container moved into place, along with the bounding box of the container.
+struct ContainerLayout {
+    bbox: BoundingBox,
+    children: Vec<Vec<Element>>,
 }
-impl Ord for State {
-    fn cmp(&self, other: &Self) -> Ordering {
-        other.cost.cmp(&self.cost)
+impl ContainerLayout {
+    /// Arranges rows of children (top to bottom) into columns (left to right), where each column
+    /// is as wide as its widest child and each row is as tall as its tallest child. Children are
+    /// placed within their cell according to `align` and `justify`, and `position` is the
+    /// corner of the container (including its padding) given by the coordinate system.
+    fn arrange(
+        rows: &[Vec<Element>],
+        position: Point,
+        gap: (usize, usize),
+        padding: (usize, usize, usize, usize),
+        align: Alignment,
+        justify: Justification,
+    ) -> PyResult<Self> {
+        let bboxes = rows
+            .iter()
+            .map(|row| row.iter().map(|child| child.bbox()).collect())
+            .collect::<PyResult<Vec<Vec<BoundingBox>>>>()?;
+        let n_columns = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+        let mut widths = vec![0; n_columns];
+        let mut heights = vec![0; rows.len()];
+        for (r, row) in bboxes.iter().enumerate() {
+            for (c, bb) in row.iter().enumerate() {
+                widths[c] = widths[c].max(bb.width());
+                heights[r] = heights[r].max(bb.height());
+            }
+        }
+        let inner_width = widths.iter().sum::<usize>() + gap.0 * n_columns.saturating_sub(1);
+        let inner_height = heights.iter().sum::<usize>() + gap.1 * rows.len().saturating_sub(1);
+        let width = (inner_width + padding.1 + padding.3).max(1);
+        let height = (inner_height + padding.0 + padding.2).max(1);
+        let position = Coordinates::bottom_left(position, height);
+        let bbox = BoundingBox::new(
+            position.1 + height as isize - 1,
+            position.0 + width as isize - 1,
+            position.1,
+            position.0,
+        );
+        let mut children = Vec::with_capacity(rows.len());
+        let mut top = bbox.top - padding.0 as isize;
+        for ((row, row_bboxes), row_height) in rows.iter().zip(&bboxes).zip(&heights) {
+            let mut left = bbox.left + padding.3 as isize;
+            let mut placed = Vec::with_capacity(row.len());
+            for ((child, bb), column_width) in row.iter().zip(row_bboxes).zip(&widths) {
+                let dx = match justify {
+                    Justification::Left => 0,
+                    Justification::Center => (column_width - bb.width()) / 2,
+                    Justification::Right => column_width - bb.width(),
+                };
+                let dy = match align {
+                    Alignment::Top => 0,
+                    Alignment::Center => (row_height - bb.height()) / 2,
+                    Alignment::Bottom => row_height - bb.height(),
+                };
+                let delta = Point(left + dx as isize - bb.left, top - dy as isize - bb.top);
+                placed.push(child.duplicate_shifted(delta));
+                left += (column_width + gap.0) as isize;
+            }
+            children.push(placed);
+            top -= (row_height + gap.1) as isize;
+        }
+        Ok(Self { bbox, children })
     }
-}
-impl PartialOrd for State {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+    fn pixels(&self, labels: bool) -> PyResult<Vec<Pixel>> {
+        let mut pixels = Vec::new();
+        for child in self.children.iter().flatten() {
+            pixels.extend(child.pixels(labels)?);
+        }
+        Ok(pixels)
+    }
+    fn box_bounds(&self) -> PyResult<Vec<BoundingBox>> {
+        let mut bounds = Vec::new();
+        for child in self.children.iter().flatten() {
+            bounds.extend(child.box_bounds()?);
+        }
+        Ok(bounds)
     }
 }
 
-/// A set of pixels which represent a path between two points.
+/// A container which arranges its children from left to right.
+///
+/// The positions of the children are ignored; each child is moved next to the previous one and
+/// aligned vertically within the height of the tallest child.
 ///
 /// Parameters
 /// ----------
-/// start : Point or tuple of ints
-///     The starting point of the path.
-/// end : Point or tuple of ints
-///     The ending point of the path.
+/// children : list
+///     The objects to arrange (TextPath, Box, Table, HStack, VStack, Grid, Pixel, or PixelGroup).
 /// position : Point or tuple of ints, optional
-///     The (x, y) coordinates of the new TextPath.
-/// style : str, optional
-///     The style to apply to the path.
-/// line_style : {'light', 'heavy', 'double'}, optional
-///     The set of characters to use for the path.
-/// weight : int, optional
-///     The weights to apply to each pixel in the path.
-/// start_direction : {'up', 'right', 'down', 'left'}, optional
-///     The direction to use for the start pixel.
-/// end_direction : {'up', 'right', 'down', 'left'}, optional
-///     The direction to use for the end pixel.
-/// bend_penalty : int, default=1
-///     The penalty weight to apply to bends in the path.
-/// environment : list
-///     A list of objects (TextPath, Box, Pixel, or PixelGroup) which the pathfinding algorithm can
-///     see with their given weights.
-/// barriers : list
-///     A list of objects (TextPath, Box, Pixel, or PixelGroup) which the pathfinding algorithm
-///     considers impassible.
-/// paths : list
-///     A list of objects (TextPath, Box, Pixel, or PixelGroup) which the pathfinding algorithm
-///     will try to follow (these objects have no additional weight cost but also count when
-///     determining neighbors for generating path characters).
-/// bbox : BoundingBox, optional
-///     If provided, limits the search to the bounding box.
+///     The position of the container (bottom-left corner, or top-left in screen coordinates,
+///     including padding).
+/// gap : int, default=1
+///     The number of columns between adjacent children.
+/// padding : tuple of ints, default=(0, 0, 0, 0)
+///     The empty space around the children (top, right, bottom, left).
+/// align : {'top', 'center', 'bottom'}, default='top'
+///     The vertical alignment of each child.
 ///
 /// Attributes
 /// ----------
-/// style : Style
-///     The style to apply to the path.
-/// line_style : {'light', 'heavy', 'double'}
-///     The set of characters to use for the path.
-/// weight : int or None
-///     The weight to apply to each pixel in the path.
-/// start_direction : {'up', 'right', 'down', 'left'} or None
-///     The direction to use for the start pixel.
-/// end_direction : {'up', 'right', 'down', 'left'} or None
-///     The direction to use for the end pixel.
+/// children : list
+///     The objects to arrange, at their original positions.
+/// position : Point
+///     The position of the container (bottom-left corner, or top-left in screen coordinates,
+///     including padding).
+/// gap : int
+///     The number of columns between adjacent children.
+/// padding : tuple of ints
+///     The empty space around the children (top, right, bottom, left).
+/// align : {'top', 'center', 'bottom'}
+///     The vertical alignment of each child.
+/// arranged : list
+///     Copies of the children moved to their place in the container.
+/// bbox : BoundingBox
+///     The bounding box of the container, including padding.
 ///
 /// Examples
 /// --------
-/// >>> from textdraw import TextPath, render
-/// >>> p = TextPath((0, 0), (3, 5))
-/// >>> print(render(p))
-/// ┌──╴
-/// │
-/// │
-/// │
-/// │
-/// ╵
+/// >>> from textdraw import Box, HStack, render
+/// >>> print(render([HStack([Box('a'), Box('b\nc')], align='bottom')]))
+///     ┌─┐
+/// ┌─┐ │b│
+/// │a│ │c│
+/// └─┘ └─┘
 ///
-///
-
-#[pyclass]
-#[derive(Clone)]
-struct TextPath {
-    path: Vec<Point>,
+#[pyclass(module = "textdraw")]
+#[derive(Clone, Serialize, Deserialize)]
+struct HStack {
+    #[pyo3(get, set)]
+    children: Vec<Element>,
     #[pyo3(get, set)]
     position: Point,
     #[pyo3(get, set)]
-    style: TextStyle,
-    line_style: LineStyle,
+    gap: usize,
     #[pyo3(get, set)]
-    weight: Option<usize>,
-    start_direction: Option<Direction>,
-    end_direction: Option<Direction>,
-    start: Point,
-    end: Point,
-    paths: HashMap<Point, Pixel>,
-    #[pyo3(get)]
-    cost: usize,
+    padding: (usize, usize, usize, usize),
+    align: Alignment,
 }
-
 #[pymethods]
-impl TextPath {
+impl HStack {
     #[new]
-    #[pyo3(signature = (start, end, position = None, style = None, *, line_style = "light".to_string(), weight = None, start_direction = None, end_direction = None, bend_penalty = 1, environment = None, barriers = None, paths = None, bbox = None))]
+    #[pyo3(signature = (children, position = None, *, gap = 1, padding = (0, 0, 0, 0), align = "top"))]
     fn new(
-        py: Python,
-        start: Bound<PyAny>,
-        end: Bound<PyAny>,
+        children: Vec<Element>,
         position: Option<Bound<PyAny>>,
-        style: Option<String>,
-        line_style: String,
-        weight: Option<usize>,
-        start_direction: Option<String>,
-        end_direction: Option<String>,
-        bend_penalty: usize,
-        environment: Option<Bound<'_, PyAny>>,
-        barriers: Option<Bound<'_, PyAny>>,
-        paths: Option<Bound<'_, PyAny>>,
-        bbox: Option<Bound<'_, PyAny>>,
+        gap: usize,
+        padding: (usize, usize, usize, usize),
+        align: &str,
     ) -> PyResult<Self> {
-        let start = Point::extract_bound(&start)?;
-        let end = Point::extract_bound(&end)?;
-        let environment = objs_to_map(&environment.unwrap_or(PyTuple::empty(py).as_any().clone()))?;
-        let barriers = objs_to_map(&barriers.unwrap_or(PyTuple::empty(py).as_any().clone()))?;
-        let paths = objs_to_map(&paths.unwrap_or(PyTuple::empty(py).as_any().clone()))?;
-        let bbox = bbox
-            .map(|bb| {
-                if let Ok(boundingbox) = bb.extract::<BoundingBox>() {
-                    Ok(boundingbox)
-                } else if let Ok(tuple_bbox) = bb.extract::<(isize, isize, isize, isize)>() {
-                    Ok(BoundingBox::new(
-                        tuple_bbox.0,
-                        tuple_bbox.1,
-                        tuple_bbox.2,
-                        tuple_bbox.3,
-                    ))
-                } else {
-                    return Err(PyValueError::new_err(
-                        "bbox must be a BoundingBox or a tuple[int, int, int, int]",
-                    ));
-                }
-            })
-            .transpose()?;
-        TextPath::calculate_path(
-            start,
-            end,
-            position.map(|p| Point::extract_bound(&p)).transpose()?,
-            style,
-            line_style,
-            weight,
-            start_direction,
-            end_direction,
-            bend_penalty,
-            &environment,
-            &barriers,
-            &paths,
-            bbox,
-        )
+        Ok(Self {
+            children,
+            position: position
+                .map(|p| Point::extract_bound(&p))
+                .transpose()?
+                .unwrap_or_default(),
+            gap,
+            padding,
+            align: align.parse()?,
+        })
+    }
+    fn __reduce__<'py>(&self, py: Python<'py>) -> PyResult<(Bound<'py, PyAny>, (String,))> {
+        reduce(py, Record::HStack(self.clone()))
+    }
+    fn __copy__(&self) -> Self {
+        self.clone()
+    }
+    fn __deepcopy__(&self, _memo: Bound<PyAny>) -> Self {
+        self.clone()
     }
     #[getter]
-    fn get_start_direction(&self) -> String {
-        self.start_direction
-            .map_or("None".to_string(), |d| d.to_string())
+    fn get_align(&self) -> String {
+        self.align.to_string()
     }
     #[setter]
-    fn set_start_direction(&mut self, start_direction: Option<String>) -> PyResult<()> {
-        self.start_direction = start_direction.map(|s| s.parse()).transpose()?;
+    fn set_align(&mut self, align: String) -> PyResult<()> {
+        self.align = align.parse()?;
         Ok(())
     }
     #[getter]
-    fn get_end_direction(&self) -> String {
-        self.end_direction
-            .map_or("None".to_string(), |d| d.to_string())
+    fn get_bbox(&self) -> PyResult<BoundingBox> {
+        Ok(self.layout()?.bbox)
     }
-    #[setter]
-    fn set_end_direction(&mut self, end_direction: Option<String>) -> PyResult<()> {
-        self.end_direction = end_direction.map(|s| s.parse()).transpose()?;
-        Ok(())
+    #[getter]
+    fn get_arranged(&self) -> PyResult<Vec<Element>> {
+        Ok(self.layout()?.children.into_iter().flatten().collect())
+    }
+    /// Duplicate an HStack at a given position.
+    ///
+    /// Parameters
+    /// ----------
+    /// position : Point or tuple of ints, optional
+    ///     The (x, y) coordinates of the new HStack.
+    ///
+    /// Returns
+    /// -------
+    /// HStack
+    ///     A new HStack at the given position.
+    ///
+    #[pyo3(name = "duplicate", signature = (position = None))]
+    fn py_duplicate(&self, position: Option<Bound<PyAny>>) -> PyResult<Self> {
+        Ok(self.duplicate(
+            position
+                .map(|p| Point::extract_bound(&p))
+                .transpose()?
+                .unwrap_or_default(),
+        ))
+    }
+    /// Duplicate an HStack shifted by the given delta.
+    ///
+    /// Parameters
+    /// ----------
+    /// delta : Point or tuple of ints
+    ///     The (x, y) shift difference.
+    ///
+    /// Returns
+    /// -------
+    /// HStack
+    ///     A new HStack shifted by the given delta.
+    ///
+    #[pyo3(name = "duplicate_shifted")]
+    fn py_duplicate_shifted(&self, delta: Bound<PyAny>) -> PyResult<Self> {
+        Ok(self.duplicate_shifted(Point::extract_bound(&delta)?))
+    }
+}
+impl HStack {
+    fn layout(&self) -> PyResult<ContainerLayout> {
+        ContainerLayout::arrange(
+            std::slice::from_ref(&self.children),
+            self.position,
+            (self.gap, 0),
+            self.padding,
+            self.align,
+            Justification::Left,
+        )
+    }
+}
+
+/// A container which arranges its children from top to bottom.
+///
+/// The positions of the children are ignored; each child is moved below the previous one and
+/// justified horizontally within the width of the widest child.
+///
+/// Parameters
+/// ----------
+/// children : list
+///     The objects to arrange (TextPath, Box, Table, HStack, VStack, Grid, Pixel, or PixelGroup).
+/// position : Point or tuple of ints, optional
+///     The position of the container (bottom-left corner, or top-left in screen coordinates,
+///     including padding).
+/// gap : int, default=1
+///     The number of rows between adjacent children.
+/// padding : tuple of ints, default=(0, 0, 0, 0)
+///     The empty space around the children (top, right, bottom, left).
+/// justify : {'left', 'center', 'right'}, default='left'
+///     The horizontal justification of each child.
+///
+/// Attributes
+/// ----------
+/// children : list
+///     The objects to arrange, at their original positions.
+/// position : Point
+///     The position of the container (bottom-left corner, or top-left in screen coordinates,
+///     including padding).
+/// gap : int
+///     The number of rows between adjacent children.
+/// padding : tuple of ints
+///     The empty space around the children (top, right, bottom, left).
+/// justify : {'left', 'center', 'right'}
+///     The horizontal justification of each child.
+/// arranged : list
+///     Copies of the children moved to their place in the container.
+/// bbox : BoundingBox
+///     The bounding box of the container, including padding.
+///
+/// Examples
+/// --------
+/// >>> from textdraw import Box, VStack, render
+/// >>> print(render([VStack([Box('a'), Box('bcd')], gap=0, justify='center')]))
+///  ┌─┐
+///  │a│
+///  └─┘
+/// ┌───┐
+/// │bcd│
+/// └───┘
+///
+#[pyclass(module = "textdraw")]
+#[derive(Clone, Serialize, Deserialize)]
+struct VStack {
+    #[pyo3(get, set)]
+    children: Vec<Element>,
+    #[pyo3(get, set)]
+    position: Point,
+    #[pyo3(get, set)]
+    gap: usize,
+    #[pyo3(get, set)]
+    padding: (usize, usize, usize, usize),
+    justify: Justification,
+}
+#[pymethods]
+impl VStack {
+    #[new]
+    #[pyo3(signature = (children, position = None, *, gap = 1, padding = (0, 0, 0, 0), justify = "left"))]
+    fn new(
+        children: Vec<Element>,
+        position: Option<Bound<PyAny>>,
+        gap: usize,
+        padding: (usize, usize, usize, usize),
+        justify: &str,
+    ) -> PyResult<Self> {
+        Ok(Self {
+            children,
+            position: position
+                .map(|p| Point::extract_bound(&p))
+                .transpose()?
+                .unwrap_or_default(),
+            gap,
+            padding,
+            justify: justify.parse()?,
+        })
+    }
+    fn __reduce__<'py>(&self, py: Python<'py>) -> PyResult<(Bound<'py, PyAny>, (String,))> {
+        reduce(py, Record::VStack(self.clone()))
+    }
+    fn __copy__(&self) -> Self {
+        self.clone()
+    }
+    fn __deepcopy__(&self, _memo: Bound<PyAny>) -> Self {
+        self.clone()
     }
     #[getter]
-    fn get_line_style(&self) -> String {
-        self.line_style.to_string()
+    fn get_justify(&self) -> String {
+        self.justify.to_string()
     }
     #[setter]
-    fn set_line_style(&mut self, line_style: String) -> PyResult<()> {
-        self.line_style = line_style.parse()?;
+    fn set_justify(&mut self, justify: String) -> PyResult<()> {
+        self.justify = justify.parse()?;
         Ok(())
     }
     #[getter]
-    fn get_bbox(&self) -> BoundingBox {
-        let mut bbox = BoundingBox::default();
-        for point in &self.path {
-            bbox += point;
-        }
-        bbox
+    fn get_bbox(&self) -> PyResult<BoundingBox> {
+        Ok(self.layout()?.bbox)
     }
-    /// Duplicate a TextPath at a given position.
+    #[getter]
+    fn get_arranged(&self) -> PyResult<Vec<Element>> {
+        Ok(self.layout()?.children.into_iter().flatten().collect())
+    }
+    /// Duplicate a VStack at a given position.
     ///
     /// Parameters
     /// ----------
     /// position : Point or tuple of ints, optional
-    ///     The (x, y) coordinates of the new TextPath.
+    ///     The (x, y) coordinates of the new VStack.
     ///
     /// Returns
     /// -------
-    /// TextPath
-    ///     A new TextPath at the given position.
+    /// VStack
+    ///     A new VStack at the given position.
     ///
     #[pyo3(name = "duplicate", signature = (position = None))]
     fn py_duplicate(&self, position: Option<Bound<PyAny>>) -> PyResult<Self> {
@@ -1838,7 +7694,7 @@ impl TextPath {
                 .unwrap_or_default(),
         ))
     }
-    /// Duplicate a TextPath shifted by the given delta.
+    /// Duplicate a VStack shifted by the given delta.
     ///
     /// Parameters
     /// ----------
@@ -1847,555 +7703,130 @@ impl TextPath {
     ///
     /// Returns
     /// -------
-    /// TextPath
-    ///     A new TextPath shifted by the given delta.
+    /// VStack
+    ///     A new VStack shifted by the given delta.
     ///
     #[pyo3(name = "duplicate_shifted")]
     fn py_duplicate_shifted(&self, delta: Bound<PyAny>) -> PyResult<Self> {
         Ok(self.duplicate_shifted(Point::extract_bound(&delta)?))
     }
 }
-impl TextPath {
-    fn duplicate(&self, position: Point) -> Self {
-        let mut new_obj = self.clone();
-        new_obj.position = position;
-        new_obj
-    }
-    fn duplicate_shifted(&self, delta: Point) -> Self {
-        let mut new_obj = self.clone();
-        new_obj.position += delta;
-        new_obj
-    }
-    fn calculate_path(
-        start: Point,
-        end: Point,
-        position: Option<Point>,
-        style: Option<String>,
-        line_style: String,
-        weight: Option<usize>,
-        start_direction: Option<String>,
-        end_direction: Option<String>,
-        bend_penalty: usize,
-        environment: &HashMap<Point, Pixel>,
-        barriers: &HashMap<Point, Pixel>,
-        paths: &HashMap<Point, Pixel>,
-        bbox: Option<BoundingBox>,
-    ) -> PyResult<Self> {
-        let mut environment = environment.clone();
-        for (pos, pixel) in barriers {
-            environment.insert(*pos, pixel.with_weight(None));
-        }
-        for (pos, pixel) in paths {
-            environment.insert(*pos, pixel.with_weight(Some(0)));
-        }
-        let mut bb = bbox.unwrap_or(map_to_bounding_box(&environment));
-        bb += start;
-        bb += end;
-        let mut heap = BinaryHeap::new();
-        let mut came_from = HashMap::new();
-        let mut cost_so_far = HashMap::new();
-
-        heap.push(State {
-            cost: 0,
-            pos: start,
-            dir: None,
-        });
-        cost_so_far.insert(start, 0);
-        while let Some(State { cost, pos, dir }) = heap.pop() {
-            if pos == end {
-                let mut path = vec![pos];
-                let mut current = (pos, dir);
-                while let Some(&(previous_pos, previous_dir)) = came_from.get(&current) {
-                    path.push(previous_pos);
-                    current = (previous_pos, previous_dir);
-                }
-                path.reverse();
-                return Ok(Self {
-                    path,
-                    position: position.unwrap_or_default(),
-                    style: style.unwrap_or_default().parse()?,
-                    line_style: line_style.parse()?,
-                    weight,
-                    start_direction: start_direction.map(|s| s.parse().unwrap()),
-                    end_direction: end_direction.map(|s| s.parse().unwrap()),
-                    start,
-                    end,
-                    paths: paths.clone(),
-                    cost,
-                });
-            }
-
-            for new_dir in Direction::all() {
-                let delta = new_dir.delta();
-                let next = pos + delta;
-                if !bb.contains_point(&next) {
-                    continue;
-                }
-                let weight = match environment.get(&next) {
-                    Some(Pixel { weight: None, .. }) => continue,
-                    Some(Pixel {
-                        weight: Some(w), ..
-                    }) => *w,
-                    None => 1,
-                };
-                let bend_cost = if Some(new_dir) != dir && dir.is_some() {
-                    bend_penalty
-                } else {
-                    0
-                };
-                let new_cost = cost + weight + bend_cost;
-                let entry = cost_so_far.entry(next).or_insert(usize::MAX);
-                let heuristic = |pos: Point| -> usize {
-                    ((end.0 - pos.0).abs() + (end.1 - pos.1).abs()) as usize
-                };
-                if new_cost < *entry {
-                    *entry = new_cost;
-                    heap.push(State {
-                        cost: new_cost + heuristic(next),
-                        pos: next,
-                        dir: Some(new_dir),
-                    });
-                    came_from.insert((next, Some(new_dir)), (pos, dir));
-                }
-            }
-        }
-        Err(PyValueError::new_err("No path found"))
-    }
-    fn as_group(&self) -> PyResult<PixelGroup> {
-        let mut path_map: HashSet<Point> = self.path.clone().into_iter().collect();
-        for (pos, _) in self.paths.iter() {
-            path_map.insert(*pos);
-        }
-        if let Some(start_dir) = self.start_direction {
-            path_map.insert(self.start + start_dir.delta());
-        }
-        if let Some(end_dir) = self.end_direction {
-            path_map.insert(self.end + end_dir.delta());
-        }
-        let path_neighbors: Vec<(bool, bool, bool, bool)> = self
-            .path
-            .iter()
-            .map(|pos| {
-                (
-                    path_map.contains(&(pos + Direction::Up.delta())),
-                    path_map.contains(&(pos + Direction::Right.delta())),
-                    path_map.contains(&(pos + Direction::Down.delta())),
-                    path_map.contains(&(pos + Direction::Left.delta())),
-                )
-            })
-            .collect();
-        let pixels = self
-            .path
-            .iter()
-            .zip(path_neighbors)
-            .map(|(pos, n)| Pixel {
-                character: self.line_style.get_char(n),
-                position: *pos,
-                weight: self.weight,
-                style: self.style.clone(),
-            })
-            .collect();
-        Ok(PixelGroup {
-            pixels,
-            position: self.position,
-            style: TextStyle::default(),
-            weight: Some(0),
-        })
-    }
-}
-
-/// Generate a list of TextPaths between multiple start and end points.
-///
-/// Parameters
-/// ----------
-/// start : list of Point or tuple of ints
-///     The starting point of the path.
-/// end : list of Point or tuple of ints
-///     The ending point of the path.
-/// style : str, optional
-///     The style to apply to the path.
-/// line_style : {'light', 'heavy', 'double'}, optional
-///     The set of characters to use for the path.
-/// weight : int, optional
-///     The weights to apply to each pixel in the path.
-/// start_direction : list of {'up', 'right', 'down', 'left'}, optional
-///     The direction to use for the start pixel.
-/// end_direction : list of {'up', 'right', 'down', 'left'}, optional
-///     The direction to use for the end pixel.
-/// bend_penalty : int, default=1
-///     The penalty weight to apply to bends in the path.
-/// environment : list
-///     A list of objects (TextPath, Box, Pixel, or PixelGroup) which the pathfinding algorithm can
-///     see with their given weights.
-/// barriers : list
-///     A list of objects (TextPath, Box, Pixel, or PixelGroup) which the pathfinding algorithm
-///     considers impassible.
-/// paths : list
-///     A list of objects (TextPath, Box, Pixel, or PixelGroup) which the pathfinding algorithm
-///     will try to follow (these objects have no additional weight cost but also count when
-///     determining neighbors for generating path characters).
-/// bbox : BoundingBox, optional
-///     If provided, limits the search to the bounding box.
-/// optimize : bool, default=False
-///     If True, iterate through all permutations of path orderings to minimize total cost.
-///
-#[pyfunction]
-#[pyo3(signature = (starts, ends, position = None, style = None, *, line_style = "light".to_string(), weight = None, start_directions = None, end_directions = None, bend_penalty = 1, environment = None, barriers = None, paths = None, bbox = None, optimize = false))]
-fn multipath(
-    py: Python,
-    starts: Bound<PyAny>,
-    ends: Bound<PyAny>,
-    position: Option<Bound<PyAny>>,
-    style: Option<String>,
-    line_style: String,
-    weight: Option<usize>,
-    start_directions: Option<Vec<Option<String>>>,
-    end_directions: Option<Vec<Option<String>>>,
-    bend_penalty: usize,
-    environment: Option<Bound<'_, PyAny>>,
-    barriers: Option<Bound<'_, PyAny>>,
-    paths: Option<Bound<'_, PyAny>>,
-    bbox: Option<Bound<'_, PyAny>>,
-    optimize: bool,
-) -> PyResult<Vec<TextPath>> {
-    let starts: Vec<Point> = starts
-        .downcast::<PyList>()?
-        .iter()
-        .map(|p| Point::extract_bound(&p))
-        .collect::<PyResult<Vec<_>>>()?;
-    let ends: Vec<Point> = ends
-        .downcast::<PyList>()?
-        .iter()
-        .map(|p| Point::extract_bound(&p))
-        .collect::<PyResult<Vec<_>>>()?;
-    if starts.len() != ends.len() {
-        return Err(PyValueError::new_err(
-            "The number of start and end points must be equal",
-        ));
-    }
-    let position = position.map(|p| Point::extract_bound(&p)).transpose()?;
-    let start_directions = start_directions.unwrap_or(vec![None; starts.len()]);
-    if starts.len() != start_directions.len() {
-        return Err(PyValueError::new_err(
-            "The number of start points and starting directions must be equal",
-        ));
-    }
-    let end_directions = end_directions.unwrap_or(vec![None; ends.len()]);
-    if ends.len() != end_directions.len() {
-        return Err(PyValueError::new_err(
-            "The number of end points and ending directions must be equal",
-        ));
-    }
-    let environment = objs_to_map(&environment.unwrap_or(PyTuple::empty(py).as_any().clone()))?;
-    let barriers = objs_to_map(&barriers.unwrap_or(PyTuple::empty(py).as_any().clone()))?;
-    let paths = objs_to_map(&paths.unwrap_or(PyTuple::empty(py).as_any().clone()))?;
-    let bbox = bbox
-        .map(|bb| {
-            if let Ok(boundingbox) = bb.extract::<BoundingBox>() {
-                Ok(boundingbox)
-            } else if let Ok(tuple_bbox) = bb.extract::<(isize, isize, isize, isize)>() {
-                Ok(BoundingBox::new(
-                    tuple_bbox.0,
-                    tuple_bbox.1,
-                    tuple_bbox.2,
-                    tuple_bbox.3,
-                ))
-            } else {
-                return Err(PyValueError::new_err(
-                    "bbox must be a BoundingBox or a tuple[int, int, int, int]",
-                ));
-            }
-        })
-        .transpose()?;
-    let mut best_paths: Vec<TextPath> = Vec::with_capacity(starts.len());
-    let mut best_cost: usize = usize::MAX;
-    let order: Vec<usize> = (0..starts.len()).collect();
-    let permutations = if optimize {
-        order
+impl VStack {
+    fn layout(&self) -> PyResult<ContainerLayout> {
+        let rows = self
+            .children
             .iter()
-            .permutations(order.len())
-            .map(|p| p.into_iter().copied().collect())
-            .collect_vec()
-    } else {
-        vec![order]
-    };
-    for permutation in permutations {
-        let mut local_cost = 0;
-        let mut local_paths: Vec<TextPath> = Vec::with_capacity(starts.len());
-        let mut local_groups: Vec<PixelGroup> = Vec::with_capacity(starts.len());
-        for i in permutation {
-            let local_path_map: HashMap<Point, Pixel> = local_groups
-                .iter()
-                .map(|g| pixels_to_map(&g.pixels))
-                .flatten()
-                .collect();
-            let all_paths = local_path_map
-                .into_iter()
-                .chain(paths.clone().into_iter())
-                .collect();
-            let textpath = TextPath::calculate_path(
-                starts[i],
-                ends[i],
-                position,
-                style.clone(),
-                line_style.clone(),
-                weight,
-                start_directions[i].clone(),
-                end_directions[i].clone(),
-                bend_penalty,
-                &environment,
-                &barriers,
-                &all_paths,
-                bbox,
-            )?;
-            local_cost += textpath.cost;
-            local_groups.push(textpath.as_group()?);
-            local_paths.push(textpath);
-        }
-        if local_cost < best_cost {
-            best_cost = local_cost;
-            best_paths = local_paths;
-        }
-    }
-    Ok(best_paths)
-}
-
-#[derive(Clone, Copy, Default)]
-enum Alignment {
-    #[default]
-    Top,
-    Center,
-    Bottom,
-}
-impl FromStr for Alignment {
-    type Err = PyErr;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_lowercase().as_str() {
-            "top" => Ok(Alignment::Top),
-            "center" => Ok(Alignment::Center),
-            "bottom" => Ok(Alignment::Bottom),
-            _ => Err(PyValueError::new_err("Invalid alignment")),
-        }
-    }
-}
-impl Display for Alignment {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                Alignment::Top => "top",
-                Alignment::Center => "center",
-                Alignment::Bottom => "bottom",
-            }
-        )
-    }
-}
-#[derive(Clone, Copy, Default)]
-enum Justification {
-    #[default]
-    Right,
-    Center,
-    Left,
-}
-impl FromStr for Justification {
-    type Err = PyErr;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_lowercase().as_str() {
-            "right" => Ok(Justification::Right),
-            "center" => Ok(Justification::Center),
-            "left" => Ok(Justification::Left),
-            _ => Err(PyValueError::new_err("Invalid alignment")),
-        }
-    }
-}
-impl Display for Justification {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                Justification::Right => "right",
-                Justification::Center => "center",
-                Justification::Left => "left",
-            }
+            .map(|child| vec![child.clone()])
+            .collect_vec();
+        ContainerLayout::arrange(
+            &rows,
+            self.position,
+            (0, self.gap),
+            self.padding,
+            Alignment::Top,
+            self.justify,
         )
     }
 }
 
-/// A box which can contain some text in a border.
+/// A container which arranges rows of children into aligned columns.
+///
+/// The positions of the children are ignored. Each column is as wide as its widest child, each
+/// row is as tall as its tallest child, and rows may have different numbers of children.
 ///
 /// Parameters
 /// ----------
-/// text : str, default=''
-///     The text contained in the box.
+/// children : list of list
+///     The rows of objects to arrange, from top to bottom.
 /// position : Point or tuple of ints, optional
-///     The position of the box (bottom-left corner).
-/// width : int, optional
-///     The width of the box (automatically determined by text if None).
-/// height : int, optional
-///     The height of the box (automatically determined by text if None).
-/// style : str, optional
-///     The style to apply to the text.
-/// border_style : str, optional
-///     The style to apply to the border.
-/// line_style : {'light', 'heavy', 'double'}, optional
-///     The set of characters to use for the border.
-/// weight : int, optional
-///     The weight to apply to each pixel in the box.
-/// padding : tuple of ints, default=(0, 1, 0, 1)
-///     The padding applied to each side of the text (top, right, bottom, left).
-/// padding_style : str, optional
-///     The style to apply to the padding.
-/// align : {'top', 'center', 'bottom'}
-///     The alignment of the text in the box.
-/// justify : {'left', 'center', 'right'}
-///     The justification of the text in the box.
-/// truncate_string : str, optional
-///     String to use if text is truncated.
-/// transparent : bool, default=False
-///     If True, the space inside the bounding box of the text will be transparent.
-/// transparent_padding : bool, default=False
-///     If True, the padding space will be transparent.
+///     The position of the container (bottom-left corner, or top-left in screen coordinates,
+///     including padding).
+/// gap : tuple of ints, default=(1, 1)
+///     The number of columns and rows between adjacent children.
+/// padding : tuple of ints, default=(0, 0, 0, 0)
+///     The empty space around the children (top, right, bottom, left).
+/// align : {'top', 'center', 'bottom'}, default='top'
+///     The vertical alignment of each child within its row.
+/// justify : {'left', 'center', 'right'}, default='left'
+///     The horizontal justification of each child within its column.
 ///
 /// Attributes
 /// ----------
-/// text : str
-///     The text contained in the box.
-/// position : Point or None
-///     The position of the box (bottom-left corner).
-/// width : int or None
-///     The width of the box (automatically determined by text if None).
-/// height : int or None
-///     The height of the box (automatically determined by text if None).
-/// style : str or None
-///     The style to apply to the text.
-/// border_style : str or None
-///     The style to apply to the border.
-/// line_style : {'light', 'heavy', 'double'} or None
-///     The set of characters to use for the border.
-/// weight : int or None
-///     The weight to apply to each pixel in the box.
+/// children : list of list
+///     The rows of objects to arrange, at their original positions.
+/// position : Point
+///     The position of the container (bottom-left corner, or top-left in screen coordinates,
+///     including padding).
+/// gap : tuple of ints
+///     The number of columns and rows between adjacent children.
 /// padding : tuple of ints
-///     The padding applied to each side of the text (top, right, bottom, left).
-/// padding_style : str or None
-///     The style to apply to the padding.
+///     The empty space around the children (top, right, bottom, left).
 /// align : {'top', 'center', 'bottom'}
-///     The alignment of the text in the box.
+///     The vertical alignment of each child within its row.
 /// justify : {'left', 'center', 'right'}
-///     The justification of the text in the box.
-/// truncate_string : str or None
-///     String to use if text is truncated.
-/// transparent : bool
-///     If True, the space inside the bounding box of the text will be transparent.
-/// transparent_padding : bool
-///     If True, the padding space will be transparent.
+///     The horizontal justification of each child within its column.
+/// arranged : list of list
+///     Copies of the children moved to their place in the container.
 /// bbox : BoundingBox
-///     The bounding box of the box itself.
+///     The bounding box of the container, including padding.
 ///
-#[pyclass]
-#[derive(Clone)]
-struct Box {
+/// Examples
+/// --------
+/// >>> from textdraw import Box, Grid, render
+/// >>> print(render([Grid([[Box('a'), Box('bb')], [Box('ccc')]], gap=(1, 0), justify='center')]))
+///  ┌─┐  ┌──┐
+///  │a│  │bb│
+///  └─┘  └──┘
+/// ┌───┐
+/// │ccc│
+/// └───┘
+///
+#[pyclass(module = "textdraw", name = "Grid")]
+#[derive(Clone, Serialize, Deserialize)]
+struct GridLayout {
     #[pyo3(get, set)]
-    text: String,
+    children: Vec<Vec<Element>>,
     #[pyo3(get, set)]
     position: Point,
     #[pyo3(get, set)]
-    width: Option<usize>,
-    #[pyo3(get, set)]
-    height: Option<usize>,
-    #[pyo3(get, set)]
-    style: TextStyle,
-    #[pyo3(get, set)]
-    border_style: TextStyle,
-    line_style: Option<LineStyle>,
-    #[pyo3(get, set)]
-    weight: Option<usize>,
-    #[pyo3(get, set)]
-    padding: Option<(usize, usize, usize, usize)>,
+    gap: (usize, usize),
     #[pyo3(get, set)]
-    padding_style: TextStyle,
+    padding: (usize, usize, usize, usize),
     align: Alignment,
     justify: Justification,
-    #[pyo3(get, set)]
-    truncate_string: Option<String>,
-    #[pyo3(get, set)]
-    transparent: bool,
-    #[pyo3(get, set)]
-    transparent_padding: bool,
 }
 #[pymethods]
-impl Box {
+impl GridLayout {
     #[new]
-    #[pyo3(signature = (text = "", position = None, width = None, height = None, style = None, *, border_style = None, line_style = Some("light".to_string()), weight = 1, padding = None, padding_style = None, align = "top", justify= "left", truncate_string = None, transparent = false, transparent_padding = false))]
+    #[pyo3(signature = (children, position = None, *, gap = (1, 1), padding = (0, 0, 0, 0), align = "top", justify = "left"))]
     fn new(
-        text: &str,
+        children: Vec<Vec<Element>>,
         position: Option<Bound<PyAny>>,
-        width: Option<usize>,
-        height: Option<usize>,
-        style: Option<String>,
-        border_style: Option<String>,
-        line_style: Option<String>,
-        weight: Option<usize>,
-        padding: Option<(usize, usize, usize, usize)>,
-        padding_style: Option<String>,
+        gap: (usize, usize),
+        padding: (usize, usize, usize, usize),
         align: &str,
         justify: &str,
-        truncate_string: Option<String>,
-        transparent: bool,
-        transparent_padding: bool,
     ) -> PyResult<Self> {
-        let pad = padding.unwrap_or_default();
-        if let Some(w) = width {
-            let min_width = pad.1 + pad.3 + 2;
-            if w < min_width {
-                return Err(PyValueError::new_err(format!(
-                    "Width {} is too small. Minimum width with current padding is {}",
-                    w, min_width
-                )));
-            }
-        }
-
-        if let Some(h) = height {
-            let min_height = pad.0 + pad.2 + 2;
-            if h < min_height {
-                return Err(PyValueError::new_err(format!(
-                    "Height {} is too small. Minimum height with current padding is {}",
-                    h, min_height
-                )));
-            }
-        }
         Ok(Self {
-            text: text.to_string(),
+            children,
             position: position
                 .map(|p| Point::extract_bound(&p))
                 .transpose()?
                 .unwrap_or_default(),
-            width,
-            height,
-            style: style.unwrap_or_default().parse()?,
-            border_style: border_style.unwrap_or_default().parse()?,
-            line_style: line_style.map(|s| s.parse()).transpose()?,
-            weight,
+            gap,
             padding,
-            padding_style: padding_style.unwrap_or_default().parse()?,
             align: align.parse()?,
             justify: justify.parse()?,
-            truncate_string,
-            transparent,
-            transparent_padding,
         })
     }
-    #[getter]
-    fn get_line_style(&self) -> Option<String> {
-        self.line_style.map(|s| s.to_string())
+    fn __reduce__<'py>(&self, py: Python<'py>) -> PyResult<(Bound<'py, PyAny>, (String,))> {
+        reduce(py, Record::Grid(self.clone()))
     }
-    #[setter]
-    fn set_line_style(&mut self, line_style: Option<String>) -> PyResult<()> {
-        self.line_style = line_style.map(|s| s.parse()).transpose()?;
-        Ok(())
+    fn __copy__(&self) -> Self {
+        self.clone()
+    }
+    fn __deepcopy__(&self, _memo: Bound<PyAny>) -> Self {
+        self.clone()
     }
     #[getter]
     fn get_align(&self) -> String {
@@ -2416,26 +7847,24 @@ impl Box {
         Ok(())
     }
     #[getter]
-    fn get_bbox(&self) -> BoundingBox {
-        let (_, bbox) = self.format_box();
-        bbox
+    fn get_bbox(&self) -> PyResult<BoundingBox> {
+        Ok(self.layout()?.bbox)
     }
     #[getter]
-    fn get_text_bbox(&self) -> BoundingBox {
-        let (_, bbox) = self.format_text();
-        bbox
+    fn get_arranged(&self) -> PyResult<Vec<Vec<Element>>> {
+        Ok(self.layout()?.children)
     }
-    /// Duplicate a Box at a given position.
+    /// Duplicate a Grid at a given position.
     ///
     /// Parameters
     /// ----------
     /// position : Point or tuple of ints, optional
-    ///     The (x, y) coordinates of the new Box.
+    ///     The (x, y) coordinates of the new Grid.
     ///
     /// Returns
     /// -------
-    /// Box
-    ///     A new Box at the given position.
+    /// Grid
+    ///     A new Grid at the given position.
     ///
     #[pyo3(name = "duplicate", signature = (position = None))]
     fn py_duplicate(&self, position: Option<Bound<PyAny>>) -> PyResult<Self> {
@@ -2446,217 +7875,857 @@ impl Box {
                 .unwrap_or_default(),
         ))
     }
-    /// Duplicate a Box shifted by the given delta.
+    /// Duplicate a Grid shifted by the given delta.
+    ///
+    /// Parameters
+    /// ----------
+    /// delta : Point or tuple of ints
+    ///     The (x, y) shift difference.
+    ///
+    /// Returns
+    /// -------
+    /// Grid
+    ///     A new Grid shifted by the given delta.
+    ///
+    #[pyo3(name = "duplicate_shifted")]
+    fn py_duplicate_shifted(&self, delta: Bound<PyAny>) -> PyResult<Self> {
+        Ok(self.duplicate_shifted(Point::extract_bound(&delta)?))
+    }
+}
+impl GridLayout {
+    fn layout(&self) -> PyResult<ContainerLayout> {
+        ContainerLayout::arrange(
+            &self.children,
+            self.position,
+            self.gap,
+            self.padding,
+            self.align,
+            self.justify,
+        )
+    }
+}
+
+create_exception!(
+    textdraw,
+    ConstraintError,
+    PyValueError,
+    "Raised when a set of positioning constraints cannot all be satisfied.
+
+Attributes
+----------
+constraint : int
+    The index of the first constraint which conflicts with the ones before it.
+relation : str
+    The name of the conflicting constraint (e.g. 'left_of')."
+);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Axis {
+    X,
+    Y,
+}
+impl Display for Axis {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Axis::X => "horizontal",
+                Axis::Y => "vertical",
+            }
+        )
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Relation {
+    LeftOf(usize),
+    RightOf(usize),
+    Above(usize),
+    Below(usize),
+    AlignLeft,
+    AlignRight,
+    AlignTop,
+    AlignBottom,
+    AlignCenter(Axis),
+    Fix(Point),
+}
+impl Display for Relation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Relation::LeftOf(_) => "left_of",
+                Relation::RightOf(_) => "right_of",
+                Relation::Above(_) => "above",
+                Relation::Below(_) => "below",
+                Relation::AlignLeft => "align_left",
+                Relation::AlignRight => "align_right",
+                Relation::AlignTop => "align_top",
+                Relation::AlignBottom => "align_bottom",
+                Relation::AlignCenter(_) => "align_center_with",
+                Relation::Fix(_) => "fix",
+            }
+        )
+    }
+}
+impl Relation {
+    /// The axis this relation acts on and the required difference between the shift of the
+    /// object and the shift of the other object, given their current bounding boxes.
+    fn equation(self, obj: BoundingBox, other: BoundingBox) -> (Axis, isize) {
+        match self {
+            Relation::LeftOf(gap) => (Axis::X, other.left - obj.right - gap as isize - 1),
+            Relation::RightOf(gap) => (Axis::X, other.right + gap as isize + 1 - obj.left),
+            Relation::Above(gap) => (Axis::Y, other.top + gap as isize + 1 - obj.bottom),
+            Relation::Below(gap) => (Axis::Y, other.bottom - gap as isize - 1 - obj.top),
+            Relation::AlignLeft => (Axis::X, other.left - obj.left),
+            Relation::AlignRight => (Axis::X, other.right - obj.right),
+            Relation::AlignTop => (Axis::Y, other.top - obj.top),
+            Relation::AlignBottom => (Axis::Y, other.bottom - obj.bottom),
+            Relation::AlignCenter(Axis::X) => (Axis::X, other.center().0 - obj.center().0),
+            Relation::AlignCenter(Axis::Y) => (Axis::Y, other.center().1 - obj.center().1),
+            Relation::Fix(_) => unreachable!("fixed positions are resolved per axis"),
+        }
+    }
+}
+
+struct Constraint {
+    object: usize,
+    /// The object this one is positioned relative to, or None for a fixed position.
+    other: Option<usize>,
+    relation: Relation,
+}
+
+/// The relative shifts of objects along one axis, stored as a union-find where each object
+/// records its shift relative to its parent.
+struct ShiftForest {
+    parents: Vec<usize>,
+    offsets: Vec<isize>,
+}
+impl ShiftForest {
+    fn new(n: usize) -> Self {
+        Self {
+            parents: (0..n).collect(),
+            offsets: vec![0; n],
+        }
+    }
+    /// The root of the object's set and the object's shift minus the root's shift.
+    fn find(&self, mut i: usize) -> (usize, isize) {
+        let mut offset = 0;
+        while self.parents[i] != i {
+            offset += self.offsets[i];
+            i = self.parents[i];
+        }
+        (i, offset)
+    }
+    /// Requires the shift of `a` minus the shift of `b` to be `difference`. If the two are already
+    /// related, the existing difference is returned when it disagrees.
+    fn relate(&mut self, a: usize, b: usize, difference: isize) -> Result<(), isize> {
+        let (root_a, offset_a) = self.find(a);
+        let (root_b, offset_b) = self.find(b);
+        if root_a == root_b {
+            let existing = offset_a - offset_b;
+            return if existing == difference {
+                Ok(())
+            } else {
+                Err(existing)
+            };
+        }
+        self.parents[root_a] = root_b;
+        self.offsets[root_a] = difference - offset_a + offset_b;
+        Ok(())
+    }
+}
+
+/// A set of constraints which position objects relative to one another.
+///
+/// Each constraint relates the bounding boxes of two objects along one axis. Calling `solve`
+/// moves every constrained object (by setting its `position`) so that all constraints hold.
+/// Objects which are not tied to a fixed position keep the position of the first object added
+/// to their group, so a diagram can be laid out around one box without any coordinates.
+///
+/// Objects with a `__textdraw__` method can be constrained too, as long as they have a settable
+/// `position` attribute (a Point or a tuple of ints) which moves what they draw. Adding a
+/// constraint on any other object raises a TypeError.
+///
+/// Examples
+/// --------
+/// >>> from textdraw import Box, Constraints, render
+/// >>> a, b = Box('A'), Box('B\nB')
+/// >>> c = Constraints()
+/// >>> c.right_of(b, a, gap=4)
+/// >>> c.align_center_with(b, a, axis='y')
+/// >>> c.solve()
+/// >>> print(render([a, b]))
+///        ┌─┐
+/// ┌─┐    │B│
+/// │A│    │B│
+/// └─┘    └─┘
+///
+#[pyclass(module = "textdraw")]
+struct Constraints {
+    objects: Vec<Py<PyAny>>,
+    constraints: Vec<Constraint>,
+}
+#[pymethods]
+impl Constraints {
+    #[new]
+    fn new() -> Self {
+        Self {
+            objects: Vec::new(),
+            constraints: Vec::new(),
+        }
+    }
+    fn __len__(&self) -> usize {
+        self.constraints.len()
+    }
+    /// Place an object to the left of another.
+    ///
+    /// Parameters
+    /// ----------
+    /// obj : TextPath, Box, Table, HStack, VStack, Grid, Pixel, or PixelGroup
+    ///     The object to move.
+    /// other : TextPath, Box, Table, HStack, VStack, Grid, Pixel, or PixelGroup
+    ///     The object to place it relative to.
+    /// gap : int, default=1
+    ///     The number of empty columns between the two objects.
+    ///
+    #[pyo3(signature = (obj, other, gap = 1))]
+    fn left_of(&mut self, obj: Bound<PyAny>, other: Bound<PyAny>, gap: usize) -> PyResult<()> {
+        self.add(&obj, Some(&other), Relation::LeftOf(gap))
+    }
+    /// Place an object to the right of another.
+    ///
+    /// Parameters
+    /// ----------
+    /// obj : TextPath, Box, Table, HStack, VStack, Grid, Pixel, or PixelGroup
+    ///     The object to move.
+    /// other : TextPath, Box, Table, HStack, VStack, Grid, Pixel, or PixelGroup
+    ///     The object to place it relative to.
+    /// gap : int, default=1
+    ///     The number of empty columns between the two objects.
+    ///
+    #[pyo3(signature = (obj, other, gap = 1))]
+    fn right_of(&mut self, obj: Bound<PyAny>, other: Bound<PyAny>, gap: usize) -> PyResult<()> {
+        self.add(&obj, Some(&other), Relation::RightOf(gap))
+    }
+    /// Place an object above another.
+    ///
+    /// Parameters
+    /// ----------
+    /// obj : TextPath, Box, Table, HStack, VStack, Grid, Pixel, or PixelGroup
+    ///     The object to move.
+    /// other : TextPath, Box, Table, HStack, VStack, Grid, Pixel, or PixelGroup
+    ///     The object to place it relative to.
+    /// gap : int, default=1
+    ///     The number of empty rows between the two objects.
+    ///
+    #[pyo3(signature = (obj, other, gap = 1))]
+    fn above(&mut self, obj: Bound<PyAny>, other: Bound<PyAny>, gap: usize) -> PyResult<()> {
+        self.add(&obj, Some(&other), Relation::Above(gap))
+    }
+    /// Place an object below another.
+    ///
+    /// Parameters
+    /// ----------
+    /// obj : TextPath, Box, Table, HStack, VStack, Grid, Pixel, or PixelGroup
+    ///     The object to move.
+    /// other : TextPath, Box, Table, HStack, VStack, Grid, Pixel, or PixelGroup
+    ///     The object to place it relative to.
+    /// gap : int, default=1
+    ///     The number of empty rows between the two objects.
+    ///
+    #[pyo3(signature = (obj, other, gap = 1))]
+    fn below(&mut self, obj: Bound<PyAny>, other: Bound<PyAny>, gap: usize) -> PyResult<()> {
+        self.add(&obj, Some(&other), Relation::Below(gap))
+    }
+    /// Line up the left edges of two objects.
+    ///
+    /// Parameters
+    /// ----------
+    /// obj : TextPath, Box, Table, HStack, VStack, Grid, Pixel, or PixelGroup
+    ///     The object to move.
+    /// other : TextPath, Box, Table, HStack, VStack, Grid, Pixel, or PixelGroup
+    ///     The object to align it with.
+    ///
+    fn align_left(&mut self, obj: Bound<PyAny>, other: Bound<PyAny>) -> PyResult<()> {
+        self.add(&obj, Some(&other), Relation::AlignLeft)
+    }
+    /// Line up the right edges of two objects.
+    ///
+    /// Parameters
+    /// ----------
+    /// obj : TextPath, Box, Table, HStack, VStack, Grid, Pixel, or PixelGroup
+    ///     The object to move.
+    /// other : TextPath, Box, Table, HStack, VStack, Grid, Pixel, or PixelGroup
+    ///     The object to align it with.
+    ///
+    fn align_right(&mut self, obj: Bound<PyAny>, other: Bound<PyAny>) -> PyResult<()> {
+        self.add(&obj, Some(&other), Relation::AlignRight)
+    }
+    /// Line up the top edges of two objects.
+    ///
+    /// Parameters
+    /// ----------
+    /// obj : TextPath, Box, Table, HStack, VStack, Grid, Pixel, or PixelGroup
+    ///     The object to move.
+    /// other : TextPath, Box, Table, HStack, VStack, Grid, Pixel, or PixelGroup
+    ///     The object to align it with.
+    ///
+    fn align_top(&mut self, obj: Bound<PyAny>, other: Bound<PyAny>) -> PyResult<()> {
+        self.add(&obj, Some(&other), Relation::AlignTop)
+    }
+    /// Line up the bottom edges of two objects.
+    ///
+    /// Parameters
+    /// ----------
+    /// obj : TextPath, Box, Table, HStack, VStack, Grid, Pixel, or PixelGroup
+    ///     The object to move.
+    /// other : TextPath, Box, Table, HStack, VStack, Grid, Pixel, or PixelGroup
+    ///     The object to align it with.
+    ///
+    fn align_bottom(&mut self, obj: Bound<PyAny>, other: Bound<PyAny>) -> PyResult<()> {
+        self.add(&obj, Some(&other), Relation::AlignBottom)
+    }
+    /// Line up the centers of two objects.
+    ///
+    /// Parameters
+    /// ----------
+    /// obj : TextPath, Box, Table, HStack, VStack, Grid, Pixel, or PixelGroup
+    ///     The object to move.
+    /// other : TextPath, Box, Table, HStack, VStack, Grid, Pixel, or PixelGroup
+    ///     The object to align it with.
+    /// axis : {'x', 'y', 'both'}, default='both'
+    ///     Whether to line up the horizontal centers ('x'), the vertical centers ('y'), or both.
+    ///
+    #[pyo3(signature = (obj, other, axis = "both"))]
+    fn align_center_with(
+        &mut self,
+        obj: Bound<PyAny>,
+        other: Bound<PyAny>,
+        axis: &str,
+    ) -> PyResult<()> {
+        let axes = match axis.to_lowercase().as_str() {
+            "x" => vec![Axis::X],
+            "y" => vec![Axis::Y],
+            "both" => vec![Axis::X, Axis::Y],
+            _ => return Err(PyValueError::new_err(format!("Invalid axis [{}]", axis))),
+        };
+        for axis in axes {
+            self.add(&obj, Some(&other), Relation::AlignCenter(axis))?;
+        }
+        Ok(())
+    }
+    /// Pin an object to a position.
     ///
     /// Parameters
     /// ----------
-    /// delta : Point or tuple of ints
-    ///     The (x, y) shift difference.
+    /// obj : TextPath, Box, Table, HStack, VStack, Grid, Pixel, or PixelGroup
+    ///     The object to pin.
+    /// position : Point or tuple of ints, optional
+    ///     The position of the object (defaults to its current position).
     ///
-    /// Returns
-    /// -------
-    /// Box
-    ///     A new Box shifted by the given delta.
+    #[pyo3(signature = (obj, position = None))]
+    fn fix(&mut self, obj: Bound<PyAny>, position: Option<Bound<PyAny>>) -> PyResult<()> {
+        let position = match position {
+            Some(p) => Point::extract_bound(&p)?,
+            None => Self::position_of(&obj)?,
+        };
+        self.add(&obj, None, Relation::Fix(position))
+    }
+    /// Move every constrained object so that all of the constraints hold.
     ///
-    #[pyo3(name = "duplicate_shifted")]
-    fn py_duplicate_shifted(&self, delta: Bound<PyAny>) -> PyResult<Self> {
-        Ok(self.duplicate_shifted(Point::extract_bound(&delta)?))
+    /// Raises
+    /// ------
+    /// ConstraintError
+    ///     If a constraint conflicts with the constraints before it. No objects are moved.
+    ///
+    fn solve(&self, py: Python<'_>) -> PyResult<()> {
+        let n = self.objects.len();
+        let positions = self
+            .objects
+            .iter()
+            .map(|obj| Self::position_of(obj.bind(py)))
+            .collect::<PyResult<Vec<_>>>()?;
+        let bboxes = self
+            .objects
+            .iter()
+            .map(|obj| obj.bind(py).extract::<Element>()?.bbox())
+            .collect::<PyResult<Vec<_>>>()?;
+        // The last node stands in for the fixed origin, which never moves.
+        let ground = n;
+        let mut x = ShiftForest::new(n + 1);
+        let mut y = ShiftForest::new(n + 1);
+        for (index, constraint) in self.constraints.iter().enumerate() {
+            let obj = constraint.object;
+            let equations = match (constraint.relation, constraint.other) {
+                (Relation::Fix(p), _) => vec![
+                    (Axis::X, ground, p.0 - positions[obj].0),
+                    (Axis::Y, ground, p.1 - positions[obj].1),
+                ],
+                (relation, Some(other)) => {
+                    let (axis, difference) = relation.equation(bboxes[obj], bboxes[other]);
+                    vec![(axis, other, difference)]
+                }
+                (_, None) => unreachable!("only fixed positions have no other object"),
+            };
+            for (axis, other, difference) in equations {
+                let forest = if axis == Axis::X { &mut x } else { &mut y };
+                if let Err(existing) = forest.relate(obj, other, difference) {
+                    return Err(self.conflict(py, index, axis, difference - existing));
+                }
+            }
+        }
+        let shifts = |forest: &ShiftForest| -> Vec<isize> {
+            let found = (0..=n).map(|i| forest.find(i)).collect_vec();
+            (0..n)
+                .map(|i| {
+                    let (root, offset) = found[i];
+                    // Anchor each group to the origin if it is fixed, or else to its first object.
+                    let anchor = if found[ground].0 == root {
+                        ground
+                    } else {
+                        (0..n).find(|&j| found[j].0 == root).unwrap_or(i)
+                    };
+                    offset - found[anchor].1
+                })
+                .collect()
+        };
+        for ((obj, position), (dx, dy)) in self
+            .objects
+            .iter()
+            .zip(positions)
+            .zip(shifts(&x).into_iter().zip(shifts(&y)))
+        {
+            if dx != 0 || dy != 0 {
+                obj.bind(py).setattr("position", position + Point(dx, dy))?;
+            }
+        }
+        Ok(())
     }
 }
-impl Box {
-    fn duplicate(&self, position: Point) -> Self {
-        let mut new_obj = self.clone();
-        new_obj.position = position;
-        new_obj
+impl Constraints {
+    /// The position of an object, which `solve` sets to move it. Every textdraw object has one,
+    /// but objects with a `__textdraw__` method need their own settable `position` attribute.
+    fn position_of(obj: &Bound<PyAny>) -> PyResult<Point> {
+        if !obj.hasattr("position")? {
+            return Err(PyTypeError::new_err(format!(
+                "Only objects with a settable position attribute can be constrained, and this {} \
+                 object has none",
+                obj.get_type().name()?
+            )));
+        }
+        Point::extract_bound(&obj.getattr("position")?)
     }
-    fn duplicate_shifted(&self, delta: Point) -> Self {
-        let mut new_obj = self.clone();
-        new_obj.position += delta;
-        new_obj
+    fn index_of(&mut self, obj: &Bound<PyAny>) -> PyResult<usize> {
+        if let Some(i) = self.objects.iter().position(|o| o.bind(obj.py()).is(obj)) {
+            return Ok(i);
+        }
+        obj.extract::<Element>()?;
+        Self::position_of(obj)?;
+        self.objects.push(obj.clone().unbind());
+        Ok(self.objects.len() - 1)
+    }
+    fn add(
+        &mut self,
+        obj: &Bound<PyAny>,
+        other: Option<&Bound<PyAny>>,
+        relation: Relation,
+    ) -> PyResult<()> {
+        // Register the reference object first so that it is the one left in place when nothing
+        // in its group is fixed.
+        let other = other.map(|other| self.index_of(other)).transpose()?;
+        let object = self.index_of(obj)?;
+        self.constraints.push(Constraint {
+            object,
+            other,
+            relation,
+        });
+        Ok(())
     }
-    fn format_box(&self) -> (HashMap<Point, Pixel>, BoundingBox) {
-        let padding = self.padding.unwrap_or_default();
-        let (text, bb_text) = self.format_text();
-        let (total_width, total_height) = if let (Some(w), Some(h)) = (self.width, self.height) {
-            (w, h)
+    fn conflict(&self, py: Python<'_>, index: usize, axis: Axis, error: isize) -> PyErr {
+        let constraint = &self.constraints[index];
+        let relation = constraint.relation.to_string();
+        let message = if constraint.other == Some(constraint.object) {
+            format!(
+                "Constraint {} ({}) cannot be satisfied because it relates object {} to itself",
+                index, relation, constraint.object
+            )
         } else {
-            let width = self
-                .width
-                .unwrap_or_else(|| bb_text.width() as usize + padding.1 + padding.3 + 2);
-            let height = self
-                .height
-                .unwrap_or_else(|| bb_text.height() as usize + padding.0 + padding.2 + 2);
-            (width, height)
+            format!(
+                "Constraint {} ({}) on object {} conflicts with earlier constraints, which \
+                 already fix its {} position {} cell(s) away",
+                index,
+                relation,
+                constraint.object,
+                axis,
+                error.abs()
+            )
         };
-        let bb_border = BoundingBox::new(
-            self.position.1 + total_height as isize - 1,
-            self.position.0 + total_width as isize - 1,
-            self.position.1,
-            self.position.0,
-        );
-        let mut pixels: HashMap<Point, Pixel> = bb_border.as_map(
-            &self.border_style,
-            &self.padding_style,
-            self.line_style,
-            self.weight,
-            self.transparent_padding,
-        );
-        pixels.extend(text);
-        (pixels, bb_border)
-    }
-    fn as_group(&self) -> PixelGroup {
-        PixelGroup {
-            pixels: self.format_box().0.into_values().collect(),
-            position: Point::default(),
-            style: TextStyle::default(),
-            weight: self.weight,
+        let err = ConstraintError::new_err(message);
+        let value = err.value(py);
+        let attributes = || -> PyResult<()> {
+            value.setattr("constraint", index)?;
+            value.setattr("relation", relation)?;
+            Ok(())
+        };
+        match attributes() {
+            Ok(()) => err,
+            Err(e) => e,
         }
     }
-    fn format_text(&self) -> (HashMap<Point, Pixel>, BoundingBox) {
-        let trunc = self.truncate_string.clone().unwrap_or("".to_string());
-        let mut raw_lines = Vec::new();
-        let padding = self.padding.unwrap_or_default();
+}
 
-        let content_width = if let Some(total_width) = self.width {
-            total_width.saturating_sub(2 + padding.3 + padding.1)
-        } else {
-            self.text.lines().map(|line| line.len()).max().unwrap_or(0)
-        };
+/// Stores maps keyed by cells as lists of `[cell, value]` pairs (in a fixed order), since JSON
+/// object keys must be strings.
+mod cell_map {
+    use super::*;
 
-        for line in self.text.lines() {
-            if content_width > 0 {
-                let mut processed_line = line.to_string();
+    pub(super) fn serialize<S: Serializer, V: Serialize>(
+        map: &HashMap<Point, V>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(map.iter().sorted_by_key(|(p, _)| (p.0, p.1)))
+    }
 
-                if processed_line.len() > content_width {
-                    if !trunc.is_empty() && trunc.len() <= content_width {
-                        processed_line.truncate(content_width - trunc.len());
-                        processed_line.push_str(&trunc);
-                    } else if !trunc.is_empty() && trunc.len() > content_width {
-                        processed_line.clear();
-                    } else {
-                        processed_line.truncate(content_width);
-                    }
-                }
+    pub(super) fn deserialize<'de, D: Deserializer<'de>, V: Deserialize<'de>>(
+        deserializer: D,
+    ) -> Result<HashMap<Point, V>, D::Error> {
+        Ok(Vec::<(Point, V)>::deserialize(deserializer)?
+            .into_iter()
+            .collect())
+    }
+}
 
-                raw_lines.push(processed_line);
-            } else {
-                raw_lines.push(String::new());
+/// The version of the JSON schema written by `to_json`.
+const JSON_VERSION: usize = 1;
+
+/// One object in the JSON schema, tagged with its Python class name.
+#[derive(Clone, Serialize, Deserialize)]
+#[allow(clippy::large_enum_variant)]
+enum Record {
+    Point(Point),
+    BoundingBox(BoundingBox),
+    Style(TextStyle),
+    Pixel(Pixel),
+    PixelGroup(PixelGroup),
+    TextPath(TextPath),
+    Box(Box),
+    Table(Table),
+    HStack(HStack),
+    VStack(VStack),
+    Grid(GridLayout),
+    Region(Region),
+}
+impl Record {
+    /// Converts an object to a record. Objects with a `__textdraw__` method have no schema of their
+    /// own, so they are stored as a PixelGroup of their pixels.
+    fn extract_bound(obj: &Bound<'_, PyAny>) -> PyResult<Self> {
+        if let Ok(point) = obj.extract::<Point>() {
+            Ok(Record::Point(point))
+        } else if let Ok(bbox) = obj.extract::<BoundingBox>() {
+            Ok(Record::BoundingBox(bbox))
+        } else if let Ok(style) = obj.extract::<TextStyle>() {
+            Ok(Record::Style(style))
+        } else {
+            Ok(match obj.extract::<Element>()? {
+                Element::Pixel(pixel) => Record::Pixel(pixel),
+                Element::PixelGroup(group) => Record::PixelGroup(group),
+                Element::TextPath(path) => Record::TextPath(path),
+                Element::Box(textbox) => Record::Box(textbox),
+                Element::Table(table) => Record::Table(table),
+                Element::HStack(stack) => Record::HStack(stack),
+                Element::VStack(stack) => Record::VStack(stack),
+                Element::Grid(grid) => Record::Grid(grid),
+                Element::Region(region) => Record::Region(region),
+                element @ Element::Custom(_) => Record::flattened(&element)?,
+            })
+        }
+    }
+    /// Moves an object positioned by a corner (see `Coordinates::corner`) from the corner used in
+    /// `coordinates` to the one in use, so that it covers the same cells. Other objects are stored
+    /// with y increasing upwards anyway, so they are returned as they are.
+    fn converted(self, coordinates: Coordinates) -> PyResult<Self> {
+        fn reanchor<T: Drawable>(mut obj: T, coordinates: Coordinates) -> PyResult<T> {
+            let height = obj.bbox()?.height() as isize;
+            if let Some(position) = obj.position_mut() {
+                position.1 += match (coordinates, Coordinates::current()) {
+                    (Coordinates::Screen, Coordinates::Math) => 1 - height,
+                    (Coordinates::Math, Coordinates::Screen) => height - 1,
+                    _ => 0,
+                };
+            }
+            Ok(obj)
+        }
+        Ok(match self {
+            Record::Box(obj) => Record::Box(reanchor(obj, coordinates)?),
+            Record::Table(obj) => Record::Table(reanchor(obj, coordinates)?),
+            Record::HStack(obj) => Record::HStack(reanchor(obj, coordinates)?),
+            Record::VStack(obj) => Record::VStack(reanchor(obj, coordinates)?),
+            Record::Grid(obj) => Record::Grid(reanchor(obj, coordinates)?),
+            record => record,
+        })
+    }
+    /// A PixelGroup which renders the same way as an element.
+    fn flattened(element: &Element) -> PyResult<Self> {
+        Ok(Record::PixelGroup(PixelGroup {
+            pixels: element.pixels(true)?,
+            position: Point::default(),
+            style: TextStyle::default(),
+            weight: Some(0),
+        }))
+    }
+}
+/// The children of pickled layouts are stored as records. A `__textdraw__` object cannot be stored
+/// itself, so it is stored as a PixelGroup of what it draws.
+impl Serialize for Element {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.clone() {
+            Element::Pixel(obj) => Record::Pixel(obj),
+            Element::PixelGroup(obj) => Record::PixelGroup(obj),
+            Element::TextPath(obj) => Record::TextPath(obj),
+            Element::Box(obj) => Record::Box(obj),
+            Element::Table(obj) => Record::Table(obj),
+            Element::HStack(obj) => Record::HStack(obj),
+            Element::VStack(obj) => Record::VStack(obj),
+            Element::Grid(obj) => Record::Grid(obj),
+            Element::Region(obj) => Record::Region(obj),
+            element @ Element::Custom(_) => {
+                Record::flattened(&element).map_err(serde::ser::Error::custom)?
             }
         }
+        .serialize(serializer)
+    }
+}
+impl<'de> Deserialize<'de> for Element {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match Record::deserialize(deserializer)? {
+            Record::Pixel(obj) => Element::Pixel(obj),
+            Record::PixelGroup(obj) => Element::PixelGroup(obj),
+            Record::TextPath(obj) => Element::TextPath(obj),
+            Record::Box(obj) => Element::Box(obj),
+            Record::Table(obj) => Element::Table(obj),
+            Record::HStack(obj) => Element::HStack(obj),
+            Record::VStack(obj) => Element::VStack(obj),
+            Record::Grid(obj) => Element::Grid(obj),
+            Record::Region(obj) => Element::Region(obj),
+            Record::Point(_) | Record::BoundingBox(_) | Record::Style(_) => {
+                return Err(serde::de::Error::custom("expected a drawable object"));
+            }
+        })
+    }
+}
+impl<'py> IntoPyObject<'py> for Record {
+    type Target = PyAny;
+    type Output = Bound<'py, PyAny>;
+    type Error = PyErr;
 
-        // This doesn't seem necessary
-        let effective_width = if self.width.is_some() {
-            content_width
-        } else {
-            raw_lines.iter().map(|l| l.len()).max().unwrap_or(0)
-        };
+    fn into_pyobject(self, py: Python<'py>) -> PyResult<Self::Output> {
+        Ok(match self {
+            Record::Point(obj) => Bound::new(py, obj)?.into_any(),
+            Record::BoundingBox(obj) => Bound::new(py, obj)?.into_any(),
+            Record::Style(obj) => Bound::new(py, obj)?.into_any(),
+            Record::Pixel(obj) => Bound::new(py, obj)?.into_any(),
+            Record::PixelGroup(obj) => Bound::new(py, obj)?.into_any(),
+            Record::TextPath(obj) => Bound::new(py, obj)?.into_any(),
+            Record::Box(obj) => Bound::new(py, obj)?.into_any(),
+            Record::Table(obj) => Bound::new(py, obj)?.into_any(),
+            Record::HStack(obj) => Bound::new(py, obj)?.into_any(),
+            Record::VStack(obj) => Bound::new(py, obj)?.into_any(),
+            Record::Grid(obj) => Bound::new(py, obj)?.into_any(),
+            Record::Region(obj) => Bound::new(py, obj)?.into_any(),
+        })
+    }
+}
 
-        let effective_height = if let Some(total_height) = self.height {
-            total_height.saturating_sub(2 + padding.0 + padding.2)
-        } else {
-            raw_lines.len().max(0)
-        };
+/// The module's `from_json` function, which unpickles objects reduced by `reduce`.
+static FROM_JSON: GILOnceCell<Py<PyAny>> = GILOnceCell::new();
 
-        if raw_lines.len() > effective_height {
-            raw_lines.truncate(effective_height);
-        }
+/// Implements `__reduce__` by storing an object as its JSON record, so pickling keeps all of its
+/// settings (including the computed route of a TextPath) and unpickling goes through `from_json`.
+/// Layouts keep their children, except that a child with a `__textdraw__` method is stored as the
+/// pixels it draws. The coordinate system is stored too, so an object unpickled in another one
+/// still covers the same cells.
+fn reduce(py: Python<'_>, record: Record) -> PyResult<(Bound<'_, PyAny>, (String,))> {
+    let from_json = FROM_JSON
+        .get(py)
+        .expect("from_json is stored when the module is initialized");
+    let object = StoredObject {
+        coordinates: Coordinates::current(),
+        record,
+    };
+    let json = serde_json::to_string(&object)
+        .map_err(|e| PyValueError::new_err(format!("Failed to pickle: {}", e)))?;
+    Ok((from_json.bind(py).clone(), (json,)))
+}
 
-        let pad_line = |line: &str| -> Vec<Option<String>> {
-            let padding_chars = effective_width.saturating_sub(line.len());
-            let (left_pad, right_pad) = match self.justify {
-                Justification::Left => (0, padding_chars),
-                Justification::Right => (padding_chars, 0),
-                Justification::Center => (padding_chars / 2, padding_chars - padding_chars / 2),
-            };
-            let mut row = vec![None; left_pad];
-            row.extend(line.chars().map(|c| Some(c.to_string())));
-            row.extend(std::iter::repeat_n(None, right_pad));
-            row
-        };
+/// A single object and the coordinate system it was made in. Documents without one were made in
+/// math coordinates.
+#[derive(Serialize, Deserialize)]
+struct StoredObject {
+    #[serde(default)]
+    coordinates: Coordinates,
+    #[serde(flatten)]
+    record: Record,
+}
 
-        let padded_lines: Vec<Vec<Option<String>>> =
-            raw_lines.iter().map(|l| pad_line(l)).collect();
+/// A versioned list of objects making up a diagram. Single objects are stored as a `StoredObject`.
+#[derive(Serialize, Deserialize)]
+struct Diagram {
+    version: usize,
+    /// The coordinate system the diagram was made in.
+    #[serde(default)]
+    coordinates: Coordinates,
+    objects: Vec<Record>,
+}
 
-        let blank_row: Vec<Option<String>> = vec![None; effective_width];
-        let vertical_padding = effective_height.saturating_sub(padded_lines.len());
-        let (top_pad, bottom_pad) = match self.align {
-            Alignment::Top => (0, vertical_padding),
-            Alignment::Bottom => (vertical_padding, 0),
-            Alignment::Center => (
-                vertical_padding / 2,
-                vertical_padding - vertical_padding / 2,
-            ),
+/// Serializes an object or a list of objects to JSON.
+///
+/// Objects are stored with all of their settings, including the computed route of each TextPath,
+/// so loading them with `from_json` does not route paths again. HStacks, VStacks and Grids keep
+/// their children, except that objects with a `__textdraw__` method (at any depth) are stored as a
+/// PixelGroup which renders the same way.
+///
+/// Coordinates are always stored with y increasing upwards (see `set_coordinates`), whichever
+/// coordinate system is active, so `Point(1, 2)` is stored as `[1, -2]` with screen coordinates.
+/// The document also records the coordinate system it was made in, since objects like Boxes are
+/// positioned by a different corner in each, and `from_json` moves them to the corner used by the
+/// coordinate system in use so that they cover the same cells.
+///
+/// Parameters
+/// ----------
+/// obj : object or list
+///     A single object, or a list of objects making up a diagram.
+/// indent : int, optional
+///     If given, the JSON is pretty-printed with this many spaces of indentation.
+///
+/// Returns
+/// -------
+/// str
+///     The JSON document.
+///
+/// Raises
+/// ------
+/// TypeError
+///     If an object cannot be serialized.
+///
+/// Examples
+/// --------
+/// >>> from textdraw import Point, from_json, to_json
+/// >>> from_json(to_json(Point(1, 2)))
+/// Point(1, 2)
+///
+#[pyfunction(signature = (obj, *, indent = None))]
+fn to_json(obj: Bound<'_, PyAny>, indent: Option<usize>) -> PyResult<String> {
+    let json = if obj.is_instance_of::<PyList>() || obj.is_instance_of::<PyTuple>() {
+        let diagram = Diagram {
+            version: JSON_VERSION,
+            coordinates: Coordinates::current(),
+            objects: obj
+                .try_iter()?
+                .map(|obj| Record::extract_bound(&obj?))
+                .collect::<PyResult<_>>()?,
         };
+        write_json(&diagram, indent)
+    } else {
+        let object = StoredObject {
+            coordinates: Coordinates::current(),
+            record: Record::extract_bound(&obj)?,
+        };
+        write_json(&object, indent)
+    };
+    json.map_err(|e| PyValueError::new_err(format!("Failed to serialize to JSON: {}", e)))
+}
 
-        let mut result = Vec::new();
-        result.extend(std::iter::repeat_n(blank_row.clone(), bottom_pad));
-        result.extend(padded_lines.into_iter().rev().collect::<Vec<_>>());
-        result.extend(std::iter::repeat_n(blank_row, top_pad));
-
-        let text_start_x = self.position.0 + 1 + padding.3 as isize;
-        let text_start_y = self.position.1 + 1 + padding.2 as isize;
+/// Writes a value as compact JSON, or pretty-printed with `indent` spaces.
+fn write_json<T: Serialize>(value: &T, indent: Option<usize>) -> serde_json::Result<String> {
+    let Some(indent) = indent else {
+        return serde_json::to_string(value);
+    };
+    let indent = " ".repeat(indent);
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes());
+    let mut buffer = Vec::new();
+    value.serialize(&mut serde_json::Serializer::with_formatter(
+        &mut buffer,
+        formatter,
+    ))?;
+    Ok(String::from_utf8(buffer).expect("serde_json writes UTF-8"))
+}
 
-        (
-            result
-                .iter()
-                .enumerate()
-                .flat_map(|(j, chars)| {
-                    chars
-                        .iter()
-                        .enumerate()
-                        .filter_map(|(i, c)| match c {
-                            Some(chr) => Some(Pixel {
-                                character: chr.chars().collect::<Vec<char>>()[0],
-                                position: Point::new(
-                                    text_start_x + i as isize,
-                                    text_start_y + j as isize,
-                                ),
-                                style: self.style.clone(),
-                                weight: self.weight,
-                            }),
-                            None => {
-                                if self.transparent {
-                                    None
-                                } else {
-                                    Some(Pixel {
-                                        character: ' ',
-                                        position: Point::new(
-                                            text_start_x + i as isize,
-                                            text_start_y + j as isize,
-                                        ),
-                                        style: self.style.clone(),
-                                        weight: self.weight,
-                                    })
-                                }
-                            }
-                        })
-                        .collect::<Vec<Pixel>>()
-                })
-                .map(|p| (p.position, p))
-                .collect(),
-            BoundingBox {
-                top: text_start_y + effective_height as isize,
-                right: text_start_x + effective_width as isize,
-                bottom: text_start_y,
-                left: text_start_x,
-            },
-        )
-    }
+/// Loads an object or a list of objects from JSON written by `to_json`.
+///
+/// Parameters
+/// ----------
+/// json : str
+///     The JSON document.
+///
+/// Returns
+/// -------
+/// object or list
+///     The stored object, or a list of objects if a diagram was stored.
+///
+/// Raises
+/// ------
+/// ValueError
+///     If the JSON does not match the schema or was written by a newer version of textdraw.
+///
+#[pyfunction]
+fn from_json(py: Python<'_>, json: &str) -> PyResult<Py<PyAny>> {
+    let invalid =
+        |e: serde_json::Error| PyValueError::new_err(format!("Invalid textdraw JSON: {}", e));
+    let document: serde_json::Value = serde_json::from_str(json).map_err(invalid)?;
+    if document.get("version").is_none() {
+        let object = StoredObject::deserialize(document).map_err(invalid)?;
+        let record = object.record.converted(object.coordinates)?;
+        return Ok(record.into_pyobject(py)?.unbind());
+    }
+    let diagram = Diagram::deserialize(document).map_err(invalid)?;
+    if diagram.version > JSON_VERSION {
+        return Err(PyValueError::new_err(format!(
+            "Unsupported textdraw JSON version [{}]",
+            diagram.version
+        )));
+    }
+    let objects = diagram
+        .objects
+        .into_iter()
+        .map(|record| record.converted(diagram.coordinates))
+        .collect::<PyResult<Vec<_>>>()?;
+    Ok(objects.into_pyobject(py)?.into_any().unbind())
 }
 
 #[pymodule]
 fn textdraw(m: &Bound<PyModule>) -> PyResult<()> {
     m.add_class::<BoundingBox>()?;
+    m.add_class::<Region>()?;
     m.add_class::<PixelGroup>()?;
     m.add_class::<TextStyle>()?;
     m.add_class::<Pixel>()?;
     m.add_function(wrap_pyfunction!(render, m)?)?;
+    m.add_function(wrap_pyfunction!(render_snapshot, m)?)?;
     m.add_function(wrap_pyfunction!(arrow, m)?)?;
     m.add_function(wrap_pyfunction!(text, m)?)?;
     m.add_function(wrap_pyfunction!(multipath, m)?)?;
     m.add_function(wrap_pyfunction!(duplicate_shifted, m)?)?;
+    m.add_function(wrap_pyfunction!(set_coordinates, m)?)?;
+    m.add_function(wrap_pyfunction!(mirror, m)?)?;
+    m.add_function(wrap_pyfunction!(rotate, m)?)?;
+    m.add_function(wrap_pyfunction!(transpose, m)?)?;
+    m.add_function(wrap_pyfunction!(scale, m)?)?;
+    m.add_function(wrap_pyfunction!(to_json, m)?)?;
+    m.add_function(wrap_pyfunction!(from_json, m)?)?;
+    let _ = FROM_JSON.set(m.py(), m.getattr("from_json")?.unbind());
+    m.add_function(wrap_pyfunction!(get_coordinates, m)?)?;
     m.add_class::<TextPath>()?;
     m.add_class::<Box>()?;
+    m.add_class::<Table>()?;
+    m.add_class::<HStack>()?;
+    m.add_class::<VStack>()?;
+    m.add_class::<GridLayout>()?;
     m.add_class::<Point>()?;
+    m.add_class::<Constraints>()?;
+    m.add("RouteError", m.py().get_type::<RouteError>())?;
+    m.add("ConstraintError", m.py().get_type::<ConstraintError>())?;
     Ok(())
 }